@@ -0,0 +1,159 @@
+use serde::Serialize;
+use serde_json::Value;
+use surrealdb::Surreal;
+
+use crate::models::{AuditChange, AuditEntry, Identity};
+
+// The audit_<table> events (migrations/20261019_add_audit_log.surql) write the audit_log
+// rows; the API only tells them who is writing by binding an AuditContext as `$audit`.
+
+/// Tables whose writes are audited
+pub const AUDITED_TABLES: [&str; 14] = [
+    "guitars",
+    "guitar_models",
+    "guitar_dimensions",
+    "guitar_appointments",
+    "guitar_woods",
+    "guitar_finish",
+    "guitar_hardware",
+    "guitar_electronics",
+    "guitar_setup",
+    "guitar_case",
+    "guitar_pickups",
+    "guitar_controls",
+    "guitar_badges",
+    "guitar_provenance",
+];
+
+pub const EVENTS: [&str; 3] = ["CREATE", "UPDATE", "DELETE"];
+
+/// Who made a write and through which route or job; bind it as `$audit` on every write query
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditContext {
+    /// "user:<username>", "api_key:<name>" or "system"
+    pub actor: String,
+    /// Route (e.g. "PUT /api/guitars/{id}/images") or job name
+    pub source: String,
+}
+
+impl AuditContext {
+    /// A write made by an authenticated caller through `route`
+    pub fn for_identity(identity: &Identity, route: &str) -> Self {
+        let kind = if identity.method == "api_key" { "api_key" } else { "user" };
+        Self {
+            actor: format!("{kind}:{}", identity.subject),
+            source: route.to_string(),
+        }
+    }
+
+    /// A write made by a background job or command-line tool
+    pub fn system(job: &str) -> Self {
+        Self {
+            actor: "system".to_string(),
+            source: job.to_string(),
+        }
+    }
+}
+
+/// Filters of the audit feed; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub table: Option<String>,
+    pub actor: Option<String>,
+    pub source: Option<String>,
+    pub event: Option<String>,
+    /// Key of the guitar the rows belong to
+    pub guitar_key: Option<String>,
+    /// RFC 3339 bounds on the write time
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: usize,
+}
+
+/// Audit entries matching `filter`, newest first
+pub async fn fetch(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    filter: AuditFilter,
+) -> surrealdb::Result<Vec<AuditEntry>> {
+    let mut conditions = Vec::new();
+    if filter.table.is_some() {
+        conditions.push("table_name = $table");
+    }
+    if filter.actor.is_some() {
+        conditions.push("actor = $actor");
+    }
+    if filter.source.is_some() {
+        conditions.push("source = $source");
+    }
+    if filter.event.is_some() {
+        conditions.push("event = $event");
+    }
+    if filter.guitar_key.is_some() {
+        conditions.push("guitar_id = type::thing('guitars', $guitar)");
+    }
+    if filter.since.is_some() {
+        conditions.push("at >= <datetime> $since");
+    }
+    if filter.until.is_some() {
+        conditions.push("at < <datetime> $until");
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    let mut response = db
+        .query(format!("SELECT * FROM audit_log{where_clause} ORDER BY at DESC LIMIT $limit"))
+        .bind(("table", filter.table))
+        .bind(("actor", filter.actor))
+        .bind(("source", filter.source))
+        .bind(("event", filter.event))
+        .bind(("guitar", filter.guitar_key))
+        .bind(("since", filter.since))
+        .bind(("until", filter.until))
+        .bind(("limit", filter.limit))
+        .await?;
+    // Read as plain JSON so record ids come out as "table:key" and datetimes as RFC 3339
+    let rows: surrealdb::Value = response.take(0)?;
+    let rows = match rows.into_inner().into_json() {
+        Value::Array(rows) => rows,
+        _ => Vec::new(),
+    };
+    Ok(rows.iter().map(entry).collect())
+}
+
+fn text(row: &Value, field: &str) -> String {
+    row.get(field).and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+/// An audit_log row with its before/after snapshots reduced to the changed fields
+fn entry(row: &Value) -> AuditEntry {
+    let side = |name: &str, field: &str| row.get(name).and_then(|doc| doc.get(field)).cloned().unwrap_or(Value::Null);
+    let changes = row
+        .get("changed_fields")
+        .and_then(Value::as_array)
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|field| AuditChange {
+                    field: field.to_string(),
+                    before: side("before", field),
+                    after: side("after", field),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    AuditEntry {
+        id: text(row, "id"),
+        table: text(row, "table_name"),
+        record_id: text(row, "record_id"),
+        guitar_id: row.get("guitar_id").and_then(Value::as_str).map(str::to_string),
+        event: text(row, "event"),
+        actor: text(row, "actor"),
+        source: text(row, "source"),
+        at: text(row, "at"),
+        changes,
+    }
+}
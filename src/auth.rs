@@ -0,0 +1,319 @@
+use std::env;
+use std::marker::PhantomData;
+
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::http::header;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse};
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Utc};
+use futures_util::future::LocalBoxFuture;
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use surrealdb::Surreal;
+
+use crate::models::{ApiKey, Identity, Role, Tier};
+
+/// Session signing key and lifetime
+#[derive(Clone)]
+pub struct AuthSettings {
+    pub jwt_secret: String,
+    pub session_hours: i64,
+}
+
+impl AuthSettings {
+    /// Read `JWT_SECRET` and `SESSION_HOURS` (default 12). Without a secret a random one is
+    /// generated, so sessions don't survive a restart.
+    pub fn from_env() -> Self {
+        let jwt_secret = env::var("JWT_SECRET")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| {
+                log::warn!("JWT_SECRET is not set; using a random secret, sessions end on restart");
+                random_hex(32)
+            });
+        Self {
+            jwt_secret,
+            session_hours: env::var("SESSION_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|h| *h > 0)
+                .unwrap_or(12),
+        }
+    }
+}
+
+/// Prefix of generated API keys, so they can be told apart from session tokens
+pub const API_KEY_PREFIX: &str = "gk_";
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+/// A new random API key (`gk_` + 64 hex digits)
+pub fn generate_api_key() -> String {
+    format!("{API_KEY_PREFIX}{}", random_hex(32))
+}
+
+/// API keys are long and random, so a plain SHA-256 is enough to store them
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.trim().as_bytes()))
+}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+/// Argon2 hash (default parameters) checked against for unknown usernames, so a failed login
+/// takes as long whether or not the user exists
+const DUMMY_PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$QLnnGn5NAcAUFeNXIGcpfA$SY5D3+pI8mK9flbwnJj8LVyfxuLm4iAvaOGS+KnzJb4";
+
+/// Verify `password` against a user's hash, or against a dummy hash when there is no such user
+pub fn verify_login(password: &str, hash: Option<&str>) -> bool {
+    match hash {
+        Some(hash) => verify_password(password, hash),
+        None => {
+            verify_password(password, DUMMY_PASSWORD_HASH);
+            false
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    role: Role,
+    #[serde(default)]
+    tier: Tier,
+    iat: i64,
+    exp: i64,
+}
+
+/// Sign a session token for a user; returns the token and its expiry
+pub fn issue_session(
+    settings: &AuthSettings,
+    username: &str,
+    role: Role,
+    tier: Tier,
+) -> Result<(String, DateTime<Utc>), jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let expires_at = now + Duration::hours(settings.session_hours);
+    let claims = Claims {
+        sub: username.to_string(),
+        role,
+        tier,
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+    let token = jsonwebtoken::encode(&Header::default(), &claims, &EncodingKey::from_secret(settings.jwt_secret.as_bytes()))?;
+    Ok((token, expires_at))
+}
+
+/// The identity in a valid, unexpired session token
+pub fn verify_session(settings: &AuthSettings, token: &str) -> Option<Identity> {
+    let data = jsonwebtoken::decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(settings.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .ok()?;
+    Some(Identity {
+        subject: data.claims.sub,
+        role: data.claims.role,
+        tier: data.claims.tier,
+        method: "session".to_string(),
+    })
+}
+
+/// Look up an active API key, stamping its last use at most once a minute
+pub async fn verify_api_key(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    key: &str,
+) -> surrealdb::Result<Option<Identity>> {
+    let mut response = db
+        .query(
+            "UPDATE api_keys SET last_used_at = time::now()
+               WHERE key_hash = $hash AND revoked = false AND (last_used_at = NONE OR last_used_at < time::now() - 1m)
+               RETURN NONE;
+             SELECT * FROM api_keys WHERE key_hash = $hash AND revoked = false LIMIT 1;",
+        )
+        .bind(("hash", hash_api_key(key)))
+        .await?;
+    let keys: Vec<ApiKey> = response.take(1)?;
+    Ok(keys.into_iter().next().map(|key| Identity {
+        subject: key.name,
+        role: key.role,
+        tier: key.tier,
+        method: "api_key".to_string(),
+    }))
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    /// Credentials were sent but are not valid
+    InvalidCredentials,
+    Database(String),
+}
+
+/// The caller's identity from `X-API-Key` or `Authorization: Bearer` (session token or API key);
+/// None when no credentials were sent
+pub async fn identify(req: &HttpRequest) -> Result<Option<Identity>, AuthError> {
+    let headers = req.headers();
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    let api_key = headers
+        .get("X-API-Key")
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .or(bearer.filter(|token| token.starts_with(API_KEY_PREFIX)));
+
+    if let Some(key) = api_key {
+        let db = req
+            .app_data::<web::Data<Surreal<surrealdb::engine::remote::http::Client>>>()
+            .ok_or_else(|| AuthError::Database("database not configured".to_string()))?;
+        return match verify_api_key(db, key).await {
+            Ok(Some(identity)) => Ok(Some(identity)),
+            Ok(None) => Err(AuthError::InvalidCredentials),
+            Err(e) => Err(AuthError::Database(e.to_string())),
+        };
+    }
+    match bearer {
+        Some(token) => {
+            let settings = req
+                .app_data::<web::Data<AuthSettings>>()
+                .ok_or_else(|| AuthError::Database("auth not configured".to_string()))?;
+            verify_session(settings, token).map(Some).ok_or(AuthError::InvalidCredentials)
+        }
+        None => Ok(None),
+    }
+}
+
+/// The least role a route accepts
+pub trait MinimumRole {
+    const ROLE: Role;
+}
+
+pub struct ViewerRole;
+pub struct EditorRole;
+pub struct AdminRole;
+
+impl MinimumRole for ViewerRole {
+    const ROLE: Role = Role::Viewer;
+}
+impl MinimumRole for EditorRole {
+    const ROLE: Role = Role::Editor;
+}
+impl MinimumRole for AdminRole {
+    const ROLE: Role = Role::Admin;
+}
+
+/// Extractor that rejects the request with 401 (no or bad credentials) or 403 (role too low)
+/// unless the caller has at least role `R`
+pub struct Authorized<R: MinimumRole> {
+    pub identity: Identity,
+    role: PhantomData<R>,
+}
+
+pub type RequireViewer = Authorized<ViewerRole>;
+pub type RequireEditor = Authorized<EditorRole>;
+pub type RequireAdmin = Authorized<AdminRole>;
+
+fn reject(response: HttpResponse, message: &str) -> actix_web::Error {
+    InternalError::from_response(message.to_string(), response).into()
+}
+
+fn unauthorized(error: &str) -> actix_web::Error {
+    reject(
+        HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .json(json!({"error": error})),
+        error,
+    )
+}
+
+/// Extractor for public routes whose response depends on who is asking (e.g. paywalled data).
+/// Anonymous callers get `None`; credentials that were sent must still be valid.
+pub struct OptionalIdentity {
+    pub identity: Option<Identity>,
+}
+
+impl FromRequest for OptionalIdentity {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            match identify(&req).await {
+                Ok(identity) => Ok(OptionalIdentity { identity }),
+                Err(AuthError::InvalidCredentials) => Err(unauthorized("invalid or expired credentials")),
+                Err(AuthError::Database(e)) => Err(reject(HttpResponse::InternalServerError().json(json!({"error": e})), &e)),
+            }
+        })
+    }
+}
+
+impl<R: MinimumRole + 'static> FromRequest for Authorized<R> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            match identify(&req).await {
+                Ok(Some(identity)) if identity.role >= R::ROLE => Ok(Authorized {
+                    identity,
+                    role: PhantomData,
+                }),
+                Ok(Some(identity)) => Err(reject(
+                    HttpResponse::Forbidden().json(json!({
+                        "error": "insufficient role",
+                        "role": identity.role,
+                        "required": R::ROLE
+                    })),
+                    "insufficient role",
+                )),
+                Ok(None) => Err(unauthorized("authentication required")),
+                Err(AuthError::InvalidCredentials) => Err(unauthorized("invalid or expired credentials")),
+                Err(AuthError::Database(e)) => Err(reject(HttpResponse::InternalServerError().json(json!({"error": e})), &e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dummy_hash_costs_a_real_verification() {
+        // An unparsable dummy would return immediately and bring the timing difference back
+        let parsed = PasswordHash::new(DUMMY_PASSWORD_HASH).expect("dummy hash parses");
+        let defaults = hash_password("anything").unwrap();
+        let expected = PasswordHash::new(&defaults).unwrap();
+        assert_eq!(parsed.algorithm, expected.algorithm);
+        assert_eq!(parsed.params, expected.params);
+        assert!(!verify_login("gear_api login timing dummy", None));
+    }
+
+    #[test]
+    fn login_verifies_known_users() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(verify_login("correct horse", Some(&hash)));
+        assert!(!verify_login("wrong", Some(&hash)));
+    }
+}
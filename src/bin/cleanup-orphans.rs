@@ -0,0 +1,49 @@
+use gear_api::audit::AuditContext;
+use gear_api::config::AppConfig;
+use gear_api::trash;
+use surrealdb::opt::auth::Root;
+use surrealdb::Surreal;
+
+/// Report spec, image, link-check and price-history rows whose guitar no longer exists.
+/// `--remove` deletes them as well.
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+    env_logger::init();
+
+    let remove = std::env::args().any(|arg| arg == "--remove");
+    let cfg = AppConfig::from_env();
+
+    let db = Surreal::new::<surrealdb::engine::remote::http::Https>(&cfg.surreal_url)
+        .await
+        .expect("connect Surreal");
+    db.signin(Root {
+        username: &cfg.surreal_user,
+        password: &cfg.surreal_pass,
+    })
+    .await
+    .expect("signin");
+    db.use_ns(&cfg.surreal_ns)
+        .use_db(&cfg.surreal_db)
+        .await
+        .expect("use ns/db");
+
+    let audit = AuditContext::system("cleanup-orphans");
+    match trash::orphans(&db, remove, &audit).await {
+        Ok(counts) => {
+            let total: usize = counts.values().sum();
+            if remove {
+                println!("✅ Removed {total} orphaned rows");
+            } else {
+                println!("🔎 Found {total} orphaned rows (run with --remove to delete them)");
+            }
+            for (table, count) in counts.iter().filter(|(_, count)| **count > 0) {
+                println!("   {table:<20} {count}");
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ Orphan cleanup failed: {e}");
+            std::process::exit(1);
+        }
+    }
+}
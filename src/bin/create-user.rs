@@ -0,0 +1,74 @@
+use gear_api::auth;
+use gear_api::config::AppConfig;
+use gear_api::models::{Role, Tier};
+use surrealdb::opt::auth::Root;
+use surrealdb::Surreal;
+
+/// Create a user (or reset their password, role and tier), e.g. the first admin:
+/// `create-user <username> <viewer|editor|admin> [free|subscriber]`, reading the password from `USER_PASSWORD`.
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (Some(username), Some(role)) = (args.first(), args.get(1)) else {
+        eprintln!("Usage: create-user <username> <viewer|editor|admin> [free|subscriber]  (password in USER_PASSWORD)");
+        std::process::exit(2);
+    };
+    let role_name = role.to_lowercase();
+    let Ok(role) = serde_json::from_value::<Role>(serde_json::Value::String(role_name.clone())) else {
+        eprintln!("❌ Unknown role {role}; expected viewer, editor or admin");
+        std::process::exit(2);
+    };
+    let tier_name = args.get(2).map_or("free".to_string(), |tier| tier.to_lowercase());
+    let Ok(tier) = serde_json::from_value::<Tier>(serde_json::Value::String(tier_name.clone())) else {
+        eprintln!("❌ Unknown tier {tier_name}; expected free or subscriber");
+        std::process::exit(2);
+    };
+    let password = std::env::var("USER_PASSWORD").unwrap_or_default();
+    if password.chars().count() < 12 {
+        eprintln!("❌ Set USER_PASSWORD to a password of at least 12 characters");
+        std::process::exit(2);
+    }
+
+    let cfg = AppConfig::from_env();
+    let db = Surreal::new::<surrealdb::engine::remote::http::Https>(&cfg.surreal_url)
+        .await
+        .expect("connect Surreal");
+    db.signin(Root {
+        username: &cfg.surreal_user,
+        password: &cfg.surreal_pass,
+    })
+    .await
+    .expect("signin");
+    db.use_ns(&cfg.surreal_ns)
+        .use_db(&cfg.surreal_db)
+        .await
+        .expect("use ns/db");
+
+    let username = username.trim().to_lowercase();
+    let password_hash = auth::hash_password(&password).expect("hash password");
+    let result = db
+        .query(
+            "LET $existing = (SELECT VALUE id FROM users WHERE username = $username LIMIT 1)[0];
+             IF $existing THEN
+                 UPDATE $existing SET password_hash = $password_hash, role = $role, tier = $tier
+             ELSE
+                 CREATE users CONTENT { username: $username, password_hash: $password_hash, role: $role, tier: $tier }
+             END;",
+        )
+        .bind(("username", username.clone()))
+        .bind(("password_hash", password_hash))
+        .bind(("role", role))
+        .bind(("tier", tier))
+        .await
+        .and_then(|response| response.check());
+    match result {
+        Ok(_) => println!("✅ User {username} saved with role {role_name} and tier {tier_name}"),
+        Err(e) => {
+            eprintln!("❌ Could not save user: {e}");
+            std::process::exit(1);
+        }
+    }
+}
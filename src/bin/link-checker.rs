@@ -0,0 +1,44 @@
+use gear_api::config::AppConfig;
+use gear_api::link_health::{self, LinkChecker};
+use surrealdb::opt::auth::Root;
+use surrealdb::Surreal;
+
+/// Check listing and image URLs once and record the results.
+/// `--force` rechecks links that were checked recently too.
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+    env_logger::init();
+
+    let force = std::env::args().any(|arg| arg == "--force");
+    let cfg = AppConfig::from_env();
+
+    let db = Surreal::new::<surrealdb::engine::remote::http::Https>(&cfg.surreal_url)
+        .await
+        .expect("connect Surreal");
+    db.signin(Root {
+        username: &cfg.surreal_user,
+        password: &cfg.surreal_pass,
+    })
+    .await
+    .expect("signin");
+    db.use_ns(&cfg.surreal_ns)
+        .use_db(&cfg.surreal_db)
+        .await
+        .expect("use ns/db");
+
+    let checker = LinkChecker::new(cfg.link_check_settings.clone());
+    match link_health::run(&db, &checker, force).await {
+        Ok(summary) => {
+            println!("✅ Link check finished");
+            println!("   checked:       {}", summary.checked);
+            println!("   ok:            {}", summary.ok);
+            println!("   broken:        {}", summary.broken);
+            println!("   probably sold: {}", summary.probably_sold);
+        }
+        Err(e) => {
+            eprintln!("❌ Link check failed: {e}");
+            std::process::exit(1);
+        }
+    }
+}
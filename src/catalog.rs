@@ -0,0 +1,113 @@
+use serde_json::{Map, Value};
+
+use crate::models::{GuitarModel, SpecDeviation};
+use crate::specs::{MULTI_ROW_SECTIONS, SINGLE_ROW_SECTIONS};
+
+/// Core `guitars` fields an instance inherits from its catalog model when left empty
+pub const INHERITED_FIELDS: [&str; 5] = [
+    "body_style",
+    "line",
+    "variant",
+    "year_reference",
+    "spec_version_year",
+];
+
+/// Treat null, empty strings and empty arrays as "not overridden"
+fn is_unset(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.trim().is_empty(),
+        Some(Value::Array(items)) => items.is_empty(),
+        _ => false,
+    }
+}
+
+fn deviation(field: String, factory: &Value, instance: &Value) -> SpecDeviation {
+    SpecDeviation {
+        field,
+        factory: factory.clone(),
+        instance: instance.clone(),
+    }
+}
+
+/// Merge a catalog model's factory spec into a guitar detail document.
+///
+/// Fields the instance leaves empty are filled from the model, fields it sets win.
+/// Returns every field where the instance overrides a factory value with a different one.
+pub fn merge_effective_spec(document: &mut Map<String, Value>, model: &GuitarModel) -> Vec<SpecDeviation> {
+    let mut deviations = Vec::new();
+    let factory_core = match serde_json::to_value(model) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+
+    for field in INHERITED_FIELDS {
+        let factory = factory_core.get(field);
+        if is_unset(factory) {
+            continue;
+        }
+        let factory = factory.cloned().unwrap_or_default();
+        if is_unset(document.get(field)) {
+            document.insert(field.to_string(), factory);
+        } else if document.get(field) != Some(&factory) {
+            let instance = document.get(field).cloned().unwrap_or_default();
+            deviations.push(deviation(field.to_string(), &factory, &instance));
+        }
+    }
+
+    let empty = Map::new();
+    let factory_specs = model.specs.as_ref().unwrap_or(&empty);
+
+    for (section, _) in SINGLE_ROW_SECTIONS {
+        let Some(Value::Object(factory)) = factory_specs.get(section) else {
+            continue;
+        };
+        let mut merged = factory.clone();
+        if let Some(Value::Object(instance)) = document.get(section) {
+            for (key, value) in instance {
+                if is_unset(Some(value)) {
+                    merged.entry(key.clone()).or_insert_with(|| value.clone());
+                    continue;
+                }
+                if let Some(factory_value) = factory.get(key).filter(|v| !is_unset(Some(v))) {
+                    if factory_value != value {
+                        deviations.push(deviation(format!("{section}.{key}"), factory_value, value));
+                    }
+                }
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        document.insert(section.to_string(), Value::Object(merged));
+    }
+
+    for (section, _) in MULTI_ROW_SECTIONS {
+        let Some(factory) = factory_specs.get(section).filter(|v| !is_unset(Some(v))) else {
+            continue;
+        };
+        match document.get(section) {
+            Some(instance) if !is_unset(Some(instance)) => {
+                if !same_rows(factory, instance) {
+                    deviations.push(deviation(section.to_string(), factory, instance));
+                }
+            }
+            _ => {
+                document.insert(section.to_string(), factory.clone());
+            }
+        }
+    }
+
+    deviations
+}
+
+/// Compare two lists of spec rows regardless of row order
+fn same_rows(a: &Value, b: &Value) -> bool {
+    let sorted = |v: &Value| {
+        let mut rows: Vec<String> = v
+            .as_array()
+            .map(|rows| rows.iter().map(Value::to_string).collect())
+            .unwrap_or_default();
+        rows.sort();
+        rows
+    };
+    sorted(a) == sorted(b)
+}
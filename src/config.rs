@@ -1,6 +1,32 @@
 use std::env;
 
+use crate::auth::AuthSettings;
+use crate::image_proxy::ProxySettings;
+use crate::ingest::IngestSettings;
+use crate::link_health::LinkCheckSettings;
+use crate::rate_limit::RateLimitSettings;
+use crate::security::{CorsSettings, SecurityHeaders};
+use crate::similarity::SimilarityWeights;
+use crate::storage::ImageStorage;
+
+/// Deployment profile from `APP_PROFILE`; `dev` relaxes CORS and drops HSTS for local work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Production,
+    Dev,
+}
+
+impl Profile {
+    pub fn from_env() -> Self {
+        match env::var("APP_PROFILE").unwrap_or_default().trim().to_lowercase().as_str() {
+            "dev" | "development" | "local" => Profile::Dev,
+            _ => Profile::Production,
+        }
+    }
+}
+
 pub struct AppConfig {
+    pub profile: Profile,
     pub host: String,
     pub port: u16,
     pub surreal_url: String,
@@ -8,11 +34,25 @@ pub struct AppConfig {
     pub surreal_db: String,
     pub surreal_user: String,
     pub surreal_pass: String,
+    pub similarity_weights: SimilarityWeights,
+    pub serial_rules_path: Option<String>,
+    pub exchange_rates_path: Option<String>,
+    pub paywall_policy_path: Option<String>,
+    pub image_storage: ImageStorage,
+    pub ingest_settings: IngestSettings,
+    pub proxy_settings: ProxySettings,
+    pub link_check_settings: LinkCheckSettings,
+    pub auth_settings: AuthSettings,
+    pub rate_limit_settings: RateLimitSettings,
+    pub cors_settings: CorsSettings,
+    pub security_headers: SecurityHeaders,
 }
 
 impl AppConfig {
     pub fn from_env() -> Self {
+        let profile = Profile::from_env();
         Self {
+            profile,
             host: env::var("HOST").unwrap_or_else(|_| "127.0.0.1".into()),
             port: env::var("PORT")
                 .ok()
@@ -23,6 +63,18 @@ impl AppConfig {
             surreal_db: env::var("SURREAL_DB").expect("SURREAL_DB"),
             surreal_user: env::var("SURREAL_USER").expect("SURREAL_USER"),
             surreal_pass: env::var("SURREAL_PASS").expect("SURREAL_PASS"),
+            similarity_weights: SimilarityWeights::from_env(),
+            serial_rules_path: env::var("SERIAL_RULES_PATH").ok(),
+            exchange_rates_path: env::var("EXCHANGE_RATES_PATH").ok(),
+            paywall_policy_path: env::var("PAYWALL_POLICY_PATH").ok(),
+            image_storage: ImageStorage::from_env(),
+            ingest_settings: IngestSettings::from_env(),
+            proxy_settings: ProxySettings::from_env(),
+            link_check_settings: LinkCheckSettings::from_env(),
+            auth_settings: AuthSettings::from_env(),
+            rate_limit_settings: RateLimitSettings::from_env(),
+            cors_settings: CorsSettings::from_env(profile),
+            security_headers: SecurityHeaders::from_env(profile),
         }
     }
 }
@@ -0,0 +1,679 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+use surrealdb::Surreal;
+
+use crate::audit::AuditContext;
+use crate::images;
+use crate::ingest;
+use crate::lineage;
+use crate::models::{DuplicateGuitar, DuplicatePair, Guitar, Image, ImageRole, MergeResponse, SimilarityDimension};
+use crate::money::ExchangeRates;
+use crate::similarity::{self, SimilarityWeights, SpecProfile};
+use crate::specs;
+use crate::versions;
+
+// A pair of guitars is a candidate when they share a serial number, a listing URL or a
+// near-identical photo; brand/model and specs only move its score. Merging folds one guitar
+// into the other in one transaction and leaves the old slug in slug_redirects.
+
+const SERIAL_WEIGHT: f64 = 4.0;
+const LISTING_URL_WEIGHT: f64 = 3.0;
+const IMAGES_WEIGHT: f64 = 3.0;
+const BRAND_MODEL_WEIGHT: f64 = 2.0;
+const SPECS_WEIGHT: f64 = 2.0;
+
+/// Serial numbers (normalized) that mean "unknown" rather than identify a guitar
+const PLACEHOLDER_SERIALS: [&str; 5] = ["NONE", "UNKNOWN", "NOSERIAL", "TBD", "NOTAVAILABLE"];
+
+/// Spec sections stored as several rows per guitar, as `(section, table)`; a merge keeps one
+/// guitar's rows rather than mixing both
+const LIST_SECTIONS: [(&str, &str); 4] = [
+    ("pickups", "guitar_pickups"),
+    ("controls", "guitar_controls"),
+    ("badges", "guitar_badges"),
+    ("provenance", "guitar_provenance"),
+];
+
+/// Guitar fields never taken from the merged guitar: identity, derived or cached values
+const KEPT_FIELDS: [&str; 9] = [
+    "id",
+    "slug",
+    "created_at",
+    "updated_at",
+    "deleted_at",
+    "hero_image_url",
+    "image_gallery",
+    "condition_grade",
+    "originality_score",
+];
+
+/// Guitar fields that only make sense together, so they are taken from one guitar as a group
+const FIELD_GROUPS: [&[&str]; 5] = [
+    &["price_cents", "price_currency"],
+    &["status", "status_changed_at", "sold_at"],
+    &["condition", "condition_slug", "condition_display"],
+    &[
+        "external_source",
+        "external_id",
+        "listing_url",
+        "listing_currency",
+        "published_at",
+        "created_at_market",
+        "shop_id",
+        "shop_name",
+        "shop_slug",
+        "shop_ref",
+    ],
+    &["probably_sold", "probably_sold_at"],
+];
+
+/// Spec row fields that are not specs
+const ROW_FIELDS: [&str; 4] = ["id", "guitar_id", "created_at", "updated_at"];
+
+/// Serial number in upper case without separators; None when blank or a placeholder
+pub fn normalize_serial(raw: &str) -> Option<String> {
+    let serial: String = raw
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    Some(serial).filter(|s| s.len() >= 3 && !PLACEHOLDER_SERIALS.contains(&s.as_str()))
+}
+
+/// Listing URL without scheme, `www.`, fragment, `utm_*` parameters or trailing slash
+pub fn normalize_listing_url(raw: &str) -> Option<String> {
+    let url = reqwest::Url::parse(raw.trim()).ok()?;
+    let host = url.host_str()?.trim_start_matches("www.").to_string();
+    let query: Vec<String> = url
+        .query_pairs()
+        .filter(|(name, _)| !name.starts_with("utm_"))
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    let mut normalized = format!("{host}{}", url.path().trim_end_matches('/'));
+    if !query.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&query.join("&"));
+    }
+    Some(normalized)
+}
+
+/// Brand and model in lower case, punctuation dropped
+fn normalize_brand_model(guitar: &Guitar) -> String {
+    format!("{} {}", guitar.brand, guitar.model)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// What one guitar is compared on
+struct Fingerprint<'a> {
+    guitar: &'a Guitar,
+    serial: Option<String>,
+    listing_url: Option<String>,
+    brand_model: String,
+    profile: SpecProfile,
+    hashes: Vec<u64>,
+}
+
+/// Photos of each guitar in a pair with a near-identical photo on the other guitar
+#[derive(Default)]
+struct PhotoMatch {
+    first: BTreeSet<usize>,
+    second: BTreeSet<usize>,
+}
+
+fn signal(dimension: &str, weight: f64, score: f64, compared: bool, reason: String) -> SimilarityDimension {
+    SimilarityDimension {
+        dimension: dimension.to_string(),
+        weight,
+        score,
+        compared,
+        reason,
+    }
+}
+
+fn not_compared(dimension: &str, weight: f64) -> SimilarityDimension {
+    signal(dimension, weight, 0.0, false, "Not enough data to compare".to_string())
+}
+
+/// Score a pair; None unless a serial number, listing URL or photo identifies them as one guitar
+fn score_pair(
+    first: &Fingerprint,
+    second: &Fingerprint,
+    photos: Option<&PhotoMatch>,
+    weights: &SimilarityWeights,
+) -> Option<(f64, Vec<SimilarityDimension>)> {
+    let serial = match (&first.serial, &second.serial) {
+        (Some(a), Some(b)) if a == b => signal("serial_number", SERIAL_WEIGHT, 1.0, true, format!("Same serial number: {a}")),
+        (Some(a), Some(b)) => signal("serial_number", SERIAL_WEIGHT, 0.0, true, format!("Different serial numbers: {a} vs {b}")),
+        _ => not_compared("serial_number", SERIAL_WEIGHT),
+    };
+    // Dealers list the same guitar under their own URLs and photos, so only a match counts
+    let listing_url = match (&first.listing_url, &second.listing_url) {
+        (Some(a), Some(b)) if a == b => signal("listing_url", LISTING_URL_WEIGHT, 1.0, true, format!("Same listing URL: {a}")),
+        (Some(_), Some(_)) => signal("listing_url", LISTING_URL_WEIGHT, 0.0, false, "Different listing URLs".to_string()),
+        _ => not_compared("listing_url", LISTING_URL_WEIGHT),
+    };
+    let images = match photos {
+        Some(photos) => {
+            let matched = photos.first.len().min(photos.second.len());
+            let fewest = first.hashes.len().min(second.hashes.len()).max(1);
+            let score = (matched as f64 / fewest as f64).min(1.0);
+            signal("images", IMAGES_WEIGHT, score, true, format!("{matched} near-identical photos"))
+        }
+        None if !first.hashes.is_empty() && !second.hashes.is_empty() => {
+            signal("images", IMAGES_WEIGHT, 0.0, false, "No photos in common".to_string())
+        }
+        None => not_compared("images", IMAGES_WEIGHT),
+    };
+    let identified = serial.score >= 1.0 || listing_url.score >= 1.0 || images.score > 0.0;
+    if !identified {
+        return None;
+    }
+
+    let brand_model = if first.brand_model == second.brand_model {
+        signal(
+            "brand_model",
+            BRAND_MODEL_WEIGHT,
+            1.0,
+            true,
+            format!("Same brand and model: {}", first.guitar.get_display_title()),
+        )
+    } else {
+        signal(
+            "brand_model",
+            BRAND_MODEL_WEIGHT,
+            similarity::token_overlap(&first.brand_model, &second.brand_model),
+            true,
+            format!("Different brand/model: {} vs {}", first.guitar.get_display_title(), second.guitar.get_display_title()),
+        )
+    };
+    let spec_score = similarity::score(&first.profile, &second.profile, weights);
+    let specs = if spec_score.dimensions.iter().any(|d| d.compared) {
+        signal(
+            "specs",
+            SPECS_WEIGHT,
+            spec_score.score,
+            true,
+            format!("Specs {:.0}% alike", spec_score.score * 100.0),
+        )
+    } else {
+        not_compared("specs", SPECS_WEIGHT)
+    };
+
+    let signals = vec![serial, listing_url, images, brand_model, specs];
+    let (weighted, total_weight) = signals
+        .iter()
+        .filter(|s| s.compared)
+        .fold((0.0, 0.0), |(sum, total), s| (sum + s.score * s.weight, total + s.weight));
+    let score = if total_weight > 0.0 { weighted / total_weight } else { 0.0 };
+    Some(((score * 1000.0).round() / 1000.0, signals))
+}
+
+fn duplicate_guitar(guitar: &Guitar) -> DuplicateGuitar {
+    DuplicateGuitar {
+        id: guitar.id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+        slug: guitar.get_slug(),
+        display_title: guitar.get_display_title(),
+        shop_slug: guitar.shop_slug.clone(),
+        listing_url: guitar.listing_url.clone(),
+    }
+}
+
+/// Candidate duplicate pairs among guitars not in the trash scoring at least `min_score`,
+/// best first. Photos count as the same within `max_distance` bits of perceptual hash.
+pub async fn find(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    weights: &SimilarityWeights,
+    rates: &ExchangeRates,
+    max_distance: u32,
+    min_score: f64,
+) -> surrealdb::Result<Vec<DuplicatePair>> {
+    let guitars = specs::fetch_guitars(db).await?;
+    let dimensions = specs::fetch_spec_rows_by_guitar(db, "guitar_dimensions").await?;
+    let woods = specs::fetch_spec_rows_by_guitar(db, "guitar_woods").await?;
+    let pickups = specs::fetch_spec_rows_by_guitar(db, "guitar_pickups").await?;
+    let neck_profiles = specs::fetch_normalizations(db, "neck_profile").await?;
+    let mut response = db
+        .query("SELECT * FROM images WHERE phash != NONE AND guitar_id != NONE")
+        .await?;
+    let hashed: Vec<Image> = response.take(0)?;
+
+    let mut hashes_by_guitar: HashMap<String, Vec<u64>> = HashMap::new();
+    for image in &hashed {
+        let (Some(guitar_id), Some(phash)) = (&image.guitar_id, &image.phash) else {
+            continue;
+        };
+        if let Ok(phash) = u64::from_str_radix(phash, 16) {
+            hashes_by_guitar.entry(specs::record_key(guitar_id)).or_default().push(phash);
+        }
+    }
+
+    let fingerprints: Vec<Fingerprint> = guitars
+        .iter()
+        .map(|guitar| {
+            let key = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+            Fingerprint {
+                guitar,
+                serial: normalize_serial(&guitar.serial_number),
+                listing_url: guitar.listing_url.as_deref().and_then(normalize_listing_url),
+                brand_model: normalize_brand_model(guitar),
+                profile: SpecProfile::build(
+                    guitar,
+                    dimensions.get(&key).and_then(|rows| rows.first()),
+                    woods.get(&key).and_then(|rows| rows.first()),
+                    pickups.get(&key).map(Vec::as_slice).unwrap_or_default(),
+                    &neck_profiles,
+                    rates,
+                ),
+                hashes: hashes_by_guitar.remove(&key).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    // Photos within max_distance of a photo on another guitar
+    let photos: Vec<(usize, usize, u64)> = fingerprints
+        .iter()
+        .enumerate()
+        .flat_map(|(guitar, fp)| fp.hashes.iter().enumerate().map(move |(photo, hash)| (guitar, photo, *hash)))
+        .collect();
+    let mut photo_matches: BTreeMap<(usize, usize), PhotoMatch> = BTreeMap::new();
+    for (i, (guitar_a, photo_a, hash_a)) in photos.iter().enumerate() {
+        for (guitar_b, photo_b, hash_b) in &photos[i + 1..] {
+            if guitar_a == guitar_b || ingest::hash_distance(*hash_a, *hash_b) > max_distance {
+                continue;
+            }
+            let (pair, first, second) = if guitar_a < guitar_b {
+                ((*guitar_a, *guitar_b), *photo_a, *photo_b)
+            } else {
+                ((*guitar_b, *guitar_a), *photo_b, *photo_a)
+            };
+            let matched = photo_matches.entry(pair).or_default();
+            matched.first.insert(first);
+            matched.second.insert(second);
+        }
+    }
+
+    // Only pairs sharing a serial number, listing URL or photo are scored
+    let mut candidates: BTreeSet<(usize, usize)> = photo_matches.keys().copied().collect();
+    let mut blocks: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+    for (index, fp) in fingerprints.iter().enumerate() {
+        if let Some(serial) = &fp.serial {
+            blocks.entry(("serial", serial)).or_default().push(index);
+        }
+        if let Some(url) = &fp.listing_url {
+            blocks.entry(("url", url)).or_default().push(index);
+        }
+    }
+    for members in blocks.values() {
+        for (i, a) in members.iter().enumerate() {
+            for b in &members[i + 1..] {
+                candidates.insert((*a.min(b), *a.max(b)));
+            }
+        }
+    }
+
+    let mut pairs: Vec<DuplicatePair> = candidates
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let (first, second) = (&fingerprints[a], &fingerprints[b]);
+            let (score, signals) = score_pair(first, second, photo_matches.get(&(a, b)), weights)?;
+            (score >= min_score).then(|| DuplicatePair {
+                score,
+                first: duplicate_guitar(first.guitar),
+                second: duplicate_guitar(second.guitar),
+                signals,
+            })
+        })
+        .collect();
+    pairs.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(pairs)
+}
+
+#[derive(Debug)]
+pub enum MergeError {
+    /// Missing or in the trash
+    NotFound(String),
+    SameGuitar,
+    /// Names in `take_from_merged` that are no field or section of the merged guitar
+    UnknownFields(Vec<String>),
+    Database(Box<surrealdb::Error>),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::NotFound(id) => write!(f, "{id} not found"),
+            MergeError::SameGuitar => write!(f, "a guitar cannot be merged into itself"),
+            MergeError::UnknownFields(fields) => write!(f, "unknown fields: {}", fields.join(", ")),
+            MergeError::Database(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl From<surrealdb::Error> for MergeError {
+    fn from(e: surrealdb::Error) -> Self {
+        MergeError::Database(Box::new(e))
+    }
+}
+
+/// Null, blank, zero or an empty list or object
+fn is_empty(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.trim().is_empty(),
+        Some(Value::Number(n)) => n.as_f64() == Some(0.0),
+        Some(Value::Array(items)) => items.is_empty(),
+        Some(Value::Object(fields)) => fields.is_empty(),
+        Some(Value::Bool(_)) => false,
+    }
+}
+
+/// Field names are written into the merge query, so only plain identifiers are taken
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Rows of a query result as JSON objects
+fn json_rows(value: surrealdb::Value) -> Vec<Map<String, Value>> {
+    match value.into_inner().into_json() {
+        Value::Array(rows) => rows.into_iter().filter_map(|row| row.as_object().cloned()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Guitar fields to take from `merged`: where `kept` has no value, or requested. Grouped
+/// fields (price and currency, listing and shop, ...) are taken together.
+fn guitar_fields(kept: &Map<String, Value>, merged: &Map<String, Value>, requested: &BTreeSet<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    let mut taken = Vec::new();
+    for field in merged.keys() {
+        if KEPT_FIELDS.contains(&field.as_str()) || seen.contains(field) {
+            continue;
+        }
+        let group: Vec<&str> = FIELD_GROUPS
+            .iter()
+            .find(|group| group.contains(&field.as_str()))
+            .map(|group| group.to_vec())
+            .unwrap_or_else(|| vec![field.as_str()]);
+        seen.extend(group.iter().map(|f| f.to_string()));
+
+        let merged_has = group.iter().any(|f| !is_empty(merged.get(*f)));
+        let kept_empty = group.iter().all(|f| is_empty(kept.get(*f)));
+        let wanted = group.iter().any(|f| requested.contains(*f));
+        if merged_has && (kept_empty || wanted) {
+            taken.extend(
+                group
+                    .iter()
+                    .filter(|f| merged.contains_key(**f) && is_identifier(f))
+                    .map(|f| f.to_string()),
+            );
+        }
+    }
+    taken
+}
+
+/// Fold guitar `merged_key` into `keep_key` in one transaction: empty (or requested) fields
+/// and spec values are taken from the merged guitar, its rows are re-pointed to the kept one,
+/// and its slug is left as a redirect before it is deleted
+pub async fn merge(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    keep_key: &str,
+    merged_key: &str,
+    take_from_merged: &[String],
+    max_distance: u32,
+    audit: &AuditContext,
+) -> Result<MergeResponse, MergeError> {
+    if keep_key == merged_key {
+        return Err(MergeError::SameGuitar);
+    }
+    let keep_guitar = specs::fetch_guitar(db, keep_key)
+        .await?
+        .ok_or_else(|| MergeError::NotFound(format!("guitars:{keep_key}")))?;
+    let merged_guitar = specs::fetch_guitar(db, merged_key)
+        .await?
+        .ok_or_else(|| MergeError::NotFound(format!("guitars:{merged_key}")))?;
+
+    // Both guitars and their spec rows as JSON, plus counts of the rows that simply move
+    const COUNTED: [&str; 3] = ["price_history", "link_checks", "slug_redirects"];
+    let mut statements = vec![
+        "LET $keep = type::thing('guitars', $keep_key);".to_string(),
+        "LET $merged = type::thing('guitars', $merged_key);".to_string(),
+        "SELECT * FROM $keep;".to_string(),
+        "SELECT * FROM $merged;".to_string(),
+    ];
+    for table in versions::SPEC_TABLES {
+        statements.push(format!("SELECT * FROM {table} WHERE guitar_id = $keep ORDER BY id;"));
+        statements.push(format!("SELECT * FROM {table} WHERE guitar_id = $merged ORDER BY id;"));
+    }
+    for table in COUNTED {
+        statements.push(format!("array::len((SELECT VALUE id FROM {table} WHERE guitar_id = $merged));"));
+    }
+    let mut response = db
+        .query(statements.join("\n"))
+        .bind(("keep_key", keep_key.to_string()))
+        .bind(("merged_key", merged_key.to_string()))
+        .await?;
+    let kept = json_rows(response.take(2)?).into_iter().next().unwrap_or_default();
+    let merged = json_rows(response.take(3)?).into_iter().next().unwrap_or_default();
+    let mut kept_rows = HashMap::new();
+    let mut merged_rows = HashMap::new();
+    for (index, table) in versions::SPEC_TABLES.iter().enumerate() {
+        kept_rows.insert(*table, json_rows(response.take(4 + 2 * index)?));
+        merged_rows.insert(*table, json_rows(response.take(5 + 2 * index)?));
+    }
+    let mut counts = HashMap::new();
+    for (index, table) in COUNTED.iter().enumerate() {
+        let count: Option<usize> = response.take(4 + 2 * versions::SPEC_TABLES.len() + index)?;
+        counts.insert(*table, count.unwrap_or_default());
+    }
+
+    // Everything requested must name something the merged guitar has
+    let requested: BTreeSet<String> = take_from_merged.iter().map(|name| name.trim().to_string()).collect();
+    let unknown: Vec<String> = requested
+        .iter()
+        .filter(|name| {
+            let guitar_field = merged.contains_key(name.as_str()) && !KEPT_FIELDS.contains(&name.as_str());
+            let list_section = LIST_SECTIONS.iter().any(|(section, _)| section == name);
+            let spec_field = name.split_once('.').is_some_and(|(section, field)| {
+                specs::SINGLE_ROW_SECTIONS.iter().any(|(s, table)| {
+                    *s == section && merged_rows[table].first().is_some_and(|row| row.contains_key(field))
+                })
+            });
+            !(guitar_field || list_section || spec_field)
+        })
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Err(MergeError::UnknownFields(unknown));
+    }
+
+    let mut fields_from_merged = guitar_fields(&kept, &merged, &requested);
+    let mut moved: BTreeMap<String, usize> = BTreeMap::new();
+    let mut discarded: BTreeMap<String, usize> = BTreeMap::new();
+    let mut plan = vec![
+        "BEGIN TRANSACTION;".to_string(),
+        "LET $defer_versioning = true;".to_string(),
+        "LET $keep = type::thing('guitars', $keep_key);".to_string(),
+        "LET $merged = type::thing('guitars', $merged_key);".to_string(),
+        "LET $other = (SELECT * FROM $merged)[0];".to_string(),
+    ];
+
+    // One-row sections: fill the kept row's empty fields, or move the row when there is none
+    for (section, table) in specs::SINGLE_ROW_SECTIONS {
+        let Some(theirs) = merged_rows[table].first() else {
+            continue;
+        };
+        let Some(mine) = kept_rows[table].first() else {
+            plan.push(format!("UPDATE {table} SET guitar_id = $keep WHERE guitar_id = $merged;"));
+            *moved.entry(table.to_string()).or_default() += merged_rows[table].len();
+            continue;
+        };
+        let fields: Vec<&String> = theirs
+            .keys()
+            .filter(|field| !ROW_FIELDS.contains(&field.as_str()) && is_identifier(field))
+            .filter(|field| !is_empty(theirs.get(*field)))
+            .filter(|field| is_empty(mine.get(*field)) || requested.contains(&format!("{section}.{field}")))
+            .collect();
+        if !fields.is_empty() {
+            let assignments: Vec<String> = fields.iter().map(|field| format!("{field} = $row.{field}")).collect();
+            plan.push(format!("LET $row = (SELECT * FROM {table} WHERE guitar_id = $merged)[0];"));
+            plan.push(format!("UPDATE {table} SET {} WHERE guitar_id = $keep;", assignments.join(", ")));
+            fields_from_merged.extend(fields.iter().map(|field| format!("{section}.{field}")));
+        }
+        plan.push(format!("DELETE {table} WHERE guitar_id = $merged;"));
+        *discarded.entry(table.to_string()).or_default() += merged_rows[table].len();
+    }
+
+    // Multi-row sections: one guitar's rows win, the kept guitar's unless it has none
+    for (section, table) in LIST_SECTIONS {
+        let (mine, theirs) = (kept_rows[table].len(), merged_rows[table].len());
+        if theirs == 0 {
+            continue;
+        }
+        if mine == 0 || requested.contains(section) {
+            if mine > 0 {
+                plan.push(format!("DELETE {table} WHERE guitar_id = $keep;"));
+                *discarded.entry(table.to_string()).or_default() += mine;
+            }
+            plan.push(format!("UPDATE {table} SET guitar_id = $keep WHERE guitar_id = $merged;"));
+            *moved.entry(table.to_string()).or_default() += theirs;
+            fields_from_merged.push(section.to_string());
+        } else {
+            plan.push(format!("DELETE {table} WHERE guitar_id = $merged;"));
+            *discarded.entry(table.to_string()).or_default() += theirs;
+        }
+    }
+
+    // Photos go after the kept guitar's; near-identical ones are dropped and a second hero
+    // becomes a gallery image
+    let kept_images = images::fetch_for_guitar(db, keep_key).await?;
+    let merged_images = images::fetch_for_guitar(db, merged_key).await?;
+    let kept_hashes: Vec<u64> = kept_images
+        .iter()
+        .filter_map(|image| u64::from_str_radix(image.phash.as_deref()?, 16).ok())
+        .collect();
+    let kept_sources: HashSet<&str> = kept_images.iter().map(|image| image.src.as_str()).collect();
+    let kept_hero = kept_images.iter().any(|image| image.role == ImageRole::Hero);
+    let mut image_moves = Vec::new();
+    let mut image_drops = Vec::new();
+    for image in &merged_images {
+        let Some(key) = image.id.as_ref().map(specs::record_key) else {
+            continue;
+        };
+        let phash = image.phash.as_deref().and_then(|hash| u64::from_str_radix(hash, 16).ok());
+        let duplicate = kept_sources.contains(image.src.as_str())
+            || phash.is_some_and(|hash| kept_hashes.iter().any(|kept| ingest::hash_distance(hash, *kept) <= max_distance));
+        if duplicate {
+            image_drops.push(key);
+        } else {
+            let role = if image.role == ImageRole::Hero && kept_hero { ImageRole::Gallery } else { image.role };
+            let position = kept_images.len() + image_moves.len();
+            image_moves.push(json!({"key": key, "position": position, "role": role.as_str()}));
+        }
+    }
+    if !image_drops.is_empty() {
+        plan.push("FOR $image IN $image_drops { DELETE type::thing('images', $image); };".to_string());
+        discarded.insert("images".to_string(), image_drops.len());
+    }
+    if !image_moves.is_empty() {
+        plan.push(
+            "FOR $move IN $image_moves {
+                 UPDATE type::thing('images', $move.key) SET guitar_id = $keep, position = $move.position, role = $move.role;
+             };"
+            .to_string(),
+        );
+        moved.insert("images".to_string(), image_moves.len());
+    }
+
+    // Price history is the same guitar's; link checks are redone for the kept listing
+    plan.push("UPDATE price_history SET guitar_id = $keep WHERE guitar_id = $merged;".to_string());
+    plan.push("DELETE link_checks WHERE guitar_id = $merged;".to_string());
+    plan.push("UPDATE slug_redirects SET guitar_id = $keep WHERE guitar_id = $merged;".to_string());
+    for table in COUNTED {
+        if counts[table] > 0 {
+            let target = if table == "link_checks" { &mut discarded } else { &mut moved };
+            target.insert(table.to_string(), counts[table]);
+        }
+    }
+
+    // Lineage edges of the merged guitar are redrawn from or to the kept one
+    for relation in lineage::RELATIONS {
+        plan.push(format!(
+            "FOR $edge IN (SELECT * FROM {relation} WHERE in = $merged AND out != $keep) {{
+                 LET $target = $edge.out;
+                 IF array::len((SELECT id FROM {relation} WHERE in = $keep AND out = $target)) = 0 {{
+                     RELATE $keep->{relation}->$target SET note = $edge.note, created_at = $edge.created_at;
+                 }};
+             }};
+             FOR $edge IN (SELECT * FROM {relation} WHERE out = $merged AND in != $keep) {{
+                 LET $source = $edge.in;
+                 IF array::len((SELECT id FROM {relation} WHERE in = $source AND out = $keep)) = 0 {{
+                     RELATE $source->{relation}->$keep SET note = $edge.note, created_at = $edge.created_at;
+                 }};
+             }};"
+        ));
+    }
+
+    let merged_slug = merged_guitar.get_slug();
+    let redirect_slug = (merged_slug != keep_guitar.get_slug()).then_some(merged_slug);
+    if redirect_slug.is_some() {
+        plan.push("DELETE slug_redirects WHERE slug = $old_slug;".to_string());
+        plan.push("CREATE slug_redirects CONTENT { slug: $old_slug, guitar_id: $keep };".to_string());
+    }
+
+    // The merged guitar goes first so its listing and external ids are free for the kept one
+    plan.push("DELETE $merged;".to_string());
+    let guitar_assignments: Vec<String> = fields_from_merged
+        .iter()
+        .filter(|field| !field.contains('.') && !LIST_SECTIONS.iter().any(|(section, _)| section == field))
+        .map(|field| format!("{field} = $other.{field}"))
+        .collect();
+    if !guitar_assignments.is_empty() {
+        plan.push(format!("UPDATE $keep SET {};", guitar_assignments.join(", ")));
+    }
+    plan.push("fn::record_guitar_version($keep, $audit);".to_string());
+    plan.push("fn::record_guitar_version($merged, $audit);".to_string());
+    plan.push("COMMIT TRANSACTION;".to_string());
+
+    db.query(plan.join("\n"))
+        .bind(("keep_key", keep_key.to_string()))
+        .bind(("merged_key", merged_key.to_string()))
+        .bind(("image_drops", image_drops))
+        .bind(("image_moves", image_moves))
+        .bind(("old_slug", redirect_slug.clone()))
+        .bind(("audit", audit.clone()))
+        .await?
+        .check()?;
+
+    Ok(MergeResponse {
+        guitar_id: format!("guitars:{keep_key}"),
+        merged_id: format!("guitars:{merged_key}"),
+        redirect_slug,
+        fields_from_merged,
+        moved,
+        discarded,
+    })
+}
+
+/// Key of the guitar an old slug now redirects to
+pub async fn redirect_target(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    slug: &str,
+) -> surrealdb::Result<Option<String>> {
+    let mut response = db
+        .query("SELECT VALUE record::id(guitar_id) FROM slug_redirects WHERE slug = $slug LIMIT 1")
+        .bind(("slug", slug.to_string()))
+        .await?;
+    let keys: Vec<Value> = response.take(0)?;
+    Ok(keys.into_iter().next().map(|key| match key {
+        Value::String(key) => key,
+        other => other.to_string(),
+    }))
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use sha2::{Digest, Sha256};
+
+use crate::ingest;
+
+/// Cache location and limits of the `/img` proxy
+#[derive(Debug, Clone)]
+pub struct ProxySettings {
+    pub cache_dir: PathBuf,
+    /// Total size of cached files; least recently used files are evicted beyond it
+    pub max_cache_bytes: u64,
+    /// Largest width or height a rendition may be requested at
+    pub max_dimension: u32,
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        Self {
+            cache_dir: PathBuf::from("storage/cache"),
+            max_cache_bytes: 512 * 1024 * 1024,
+            max_dimension: 2560,
+        }
+    }
+}
+
+impl ProxySettings {
+    /// Read `IMAGE_CACHE_PATH`, `IMAGE_CACHE_MAX_BYTES` and `IMAGE_PROXY_MAX_DIMENSION`, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            cache_dir: env::var("IMAGE_CACHE_PATH").map(PathBuf::from).unwrap_or(defaults.cache_dir),
+            max_cache_bytes: env::var("IMAGE_CACHE_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_cache_bytes),
+            max_dimension: env::var("IMAGE_PROXY_MAX_DIMENSION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(defaults.max_dimension),
+        }
+    }
+}
+
+struct CacheEntry {
+    size: u64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+/// Size-bounded disk cache of fetched originals and renditions that evicts the least recently used files
+#[derive(Clone)]
+pub struct ImageCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Largest width or height a rendition may be requested at
+    pub max_dimension: u32,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl ImageCache {
+    /// Open (and create) the cache directory, picking up files left by a previous run
+    pub fn open(settings: &ProxySettings) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&settings.cache_dir)?;
+        let mut files: Vec<(String, u64, SystemTime)> = Vec::new();
+        for entry in std::fs::read_dir(&settings.cache_dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((entry.file_name().to_string_lossy().to_string(), metadata.len(), modified));
+            }
+        }
+        // Oldest files count as least recently used
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut state = CacheState::default();
+        for (name, size, _) in files {
+            state.clock += 1;
+            state.total_bytes += size;
+            state.entries.insert(name, CacheEntry { size, last_used: state.clock });
+        }
+        let cache = Self {
+            dir: settings.cache_dir.clone(),
+            max_bytes: settings.max_cache_bytes,
+            max_dimension: settings.max_dimension,
+            state: Arc::new(Mutex::new(state)),
+        };
+        let evicted = cache.evict(None);
+        for name in evicted {
+            let _ = std::fs::remove_file(cache.dir.join(name));
+        }
+        Ok(cache)
+    }
+
+    /// Drop least recently used entries until the cache fits, never `keep`; returns the evicted names
+    fn evict(&self, keep: Option<&str>) -> Vec<String> {
+        let mut state = self.state.lock().expect("image cache lock");
+        let mut evicted = Vec::new();
+        while state.total_bytes > self.max_bytes {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .filter(|(name, _)| Some(name.as_str()) != keep)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(name, _)| name.clone())
+            else {
+                break;
+            };
+            if let Some(entry) = state.entries.remove(&oldest) {
+                state.total_bytes -= entry.size;
+            }
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    /// Cached bytes of `name`, marking it as recently used
+    pub async fn get(&self, name: &str) -> Option<Vec<u8>> {
+        {
+            let mut state = self.state.lock().expect("image cache lock");
+            state.clock += 1;
+            let clock = state.clock;
+            state.entries.get_mut(name)?.last_used = clock;
+        }
+        match tokio::fs::read(self.dir.join(name)).await {
+            Ok(bytes) => Some(bytes),
+            Err(_) => {
+                let mut state = self.state.lock().expect("image cache lock");
+                if let Some(entry) = state.entries.remove(name) {
+                    state.total_bytes -= entry.size;
+                }
+                None
+            }
+        }
+    }
+
+    /// Store `bytes` as `name`, evicting older files when the cache grows past its limit
+    pub async fn put(&self, name: &str, bytes: &[u8]) {
+        let size = bytes.len() as u64;
+        if size > self.max_bytes {
+            return;
+        }
+        if let Err(e) = tokio::fs::write(self.dir.join(name), bytes).await {
+            log::warn!("Could not cache {name}: {e}");
+            return;
+        }
+        {
+            let mut state = self.state.lock().expect("image cache lock");
+            state.clock += 1;
+            let last_used = state.clock;
+            if let Some(previous) = state.entries.insert(name.to_string(), CacheEntry { size, last_used }) {
+                state.total_bytes -= previous.size;
+            }
+            state.total_bytes += size;
+        }
+        for evicted in self.evict(Some(name)) {
+            let _ = tokio::fs::remove_file(self.dir.join(evicted)).await;
+        }
+    }
+}
+
+/// Size and format of a requested rendition
+#[derive(Debug, Clone, Copy)]
+pub struct RenderRequest {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Extension understood by `ingest::encode`
+    pub format: &'static str,
+}
+
+/// Output formats of the proxy, by `fmt` value
+pub fn parse_format(raw: &str) -> Option<&'static str> {
+    match raw.trim().to_lowercase().as_str() {
+        "jpg" | "jpeg" => Some("jpg"),
+        "png" => Some("png"),
+        "webp" => Some("webp"),
+        #[cfg(feature = "avif")]
+        "avif" => Some("avif"),
+        _ => None,
+    }
+}
+
+pub fn content_type(format: &str) -> &'static str {
+    match format {
+        "jpg" => "image/jpeg",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        _ => "image/png",
+    }
+}
+
+/// Placeholders are drawn no larger than this on either side; bigger requests get a scaled-down one
+pub const MAX_PLACEHOLDER_DIMENSION: u32 = 640;
+
+impl RenderRequest {
+    /// Size a placeholder is drawn at: the requested box (4:3 at 640px wide by default),
+    /// scaled down to fit `MAX_PLACEHOLDER_DIMENSION`
+    pub fn placeholder_size(&self) -> (u32, u32) {
+        let width = self.width.unwrap_or(640).max(1);
+        let height = self.height.unwrap_or(width * 3 / 4).max(1);
+        let longest = width.max(height);
+        if longest <= MAX_PLACEHOLDER_DIMENSION {
+            return (width, height);
+        }
+        let scale = |side: u32| ((side as u64 * MAX_PLACEHOLDER_DIMENSION as u64 / longest as u64) as u32).max(1);
+        (scale(width), scale(height))
+    }
+
+    /// Cache file name of the placeholder for this request
+    pub fn placeholder_cache_name(&self) -> String {
+        let (width, height) = self.placeholder_size();
+        format!("placeholder-{width}x{height}.{}", self.format)
+    }
+
+    /// Cache file name of this rendition of the original cached as `base`
+    pub fn cache_name(&self, base: &str) -> String {
+        format!("{base}-{}x{}.{}", self.width.unwrap_or(0), self.height.unwrap_or(0), self.format)
+    }
+}
+
+/// Cache file prefix for an image: its key plus a hash of its source, so a changed src is refetched
+pub fn cache_base(image_key: &str, src: &str) -> String {
+    let key: String = image_key
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    let digest = hex::encode(Sha256::digest(src.as_bytes()));
+    format!("{key}-{}", &digest[..12])
+}
+
+/// Resize to fit within the requested box (never upscaling) and encode
+pub fn render(original: &[u8], request: RenderRequest) -> Result<Vec<u8>, ingest::IngestError> {
+    let image = image::load_from_memory(original)?;
+    let (width, height) = (image.width(), image.height());
+    let target_width = request.width.unwrap_or(width).min(width);
+    let target_height = request.height.unwrap_or(height).min(height);
+    let resized = if target_width < width || target_height < height {
+        image.resize(target_width, target_height, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    Ok(ingest::encode(&resized, request.format)?.bytes)
+}
+
+/// A neutral striped placeholder at `request.placeholder_size()`
+pub fn placeholder(request: RenderRequest) -> Vec<u8> {
+    let (width, height) = request.placeholder_size();
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        if (x + y) / 12 % 2 == 0 {
+            Rgb([229, 231, 235])
+        } else {
+            Rgb([220, 223, 228])
+        }
+    });
+    ingest::encode(&DynamicImage::ImageRgb8(image), request.format)
+        .map(|rendition| rendition.bytes)
+        .unwrap_or_default()
+}
@@ -0,0 +1,273 @@
+use serde_json::json;
+use surrealdb::Surreal;
+
+use crate::audit::AuditContext;
+use crate::models::{Image, ImageCreateRequest, ImageRole, ImageVariant};
+use crate::storage::ImageStorage;
+
+// The guitars_image_views event (migrations/20261019_add_guitar_images.surql) keeps
+// guitars.hero_image_url and guitars.image_gallery in sync with these records; writes that
+// change them take an AuditContext for the audit log of the guitar.
+
+/// Extract the id part of an image id (`images:abc` -> `abc`)
+pub fn image_key(raw: &str) -> String {
+    let key = raw.strip_prefix("images:").unwrap_or(raw);
+    key.trim_start_matches('⟨').trim_end_matches('⟩').to_string()
+}
+
+/// Images of one guitar in display order
+pub async fn fetch_for_guitar(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+) -> surrealdb::Result<Vec<Image>> {
+    let mut response = db
+        .query("SELECT * FROM images WHERE guitar_id = type::thing('guitars', $key) ORDER BY position ASC")
+        .bind(("key", guitar_key.to_string()))
+        .await?;
+    response.take(0)
+}
+
+/// One image, unless its guitar is in the trash
+pub async fn fetch_visible(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    image_key: &str,
+) -> surrealdb::Result<Option<Image>> {
+    let mut response = db
+        .query("SELECT * FROM type::thing('images', $image) WHERE guitar_id.deleted_at = NONE")
+        .bind(("image", image_key.to_string()))
+        .await?;
+    let rows: Vec<Image> = response.take(0)?;
+    Ok(rows.into_iter().next())
+}
+
+/// Add an image at `request.position` (appended when omitted), shifting later images down.
+/// A new hero demotes the current one to a gallery image.
+pub async fn add(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+    request: ImageCreateRequest,
+    audit: &AuditContext,
+) -> surrealdb::Result<Option<Image>> {
+    let count = fetch_for_guitar(db, guitar_key).await?.len() as i32;
+    let position = request.position.map_or(count, |p| p.clamp(0, count));
+    let role = request.role.unwrap_or_default();
+
+    let mut response = db
+        .query(
+            "BEGIN TRANSACTION;
+             UPDATE images SET position += 1 WHERE guitar_id = type::thing('guitars', $key) AND position >= $position;
+             IF $role = 'hero' {
+                 UPDATE images SET role = 'gallery' WHERE guitar_id = type::thing('guitars', $key) AND role = 'hero';
+             };
+             LET $created = CREATE images CONTENT {
+                 guitar_id: type::thing('guitars', $key),
+                 src: $src,
+                 alt: $alt,
+                 w: $w,
+                 h: $h,
+                 role: $role,
+                 source: $source,
+                 position: $position
+             };
+             COMMIT TRANSACTION;
+             $created;",
+        )
+        .bind(("key", guitar_key.to_string()))
+        .bind(("position", position))
+        .bind(("role", role.as_str()))
+        .bind(("src", request.src))
+        .bind(("alt", request.alt))
+        .bind(("w", request.w))
+        .bind(("h", request.h))
+        .bind(("source", request.source))
+        .bind(("audit", audit.clone()))
+        .await?
+        .check()?;
+    let last = response.num_statements() - 1;
+    let created: Vec<Image> = response.take(last)?;
+    Ok(created.into_iter().next())
+}
+
+/// Record the perceptual hash, storage prefix and thumbnails of an image stored by the API
+pub async fn set_assets(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    image_key: &str,
+    phash: &str,
+    storage_key: &str,
+    variants: Vec<ImageVariant>,
+) -> surrealdb::Result<Option<Image>> {
+    let mut response = db
+        .query("UPDATE type::thing('images', $image) SET phash = $phash, storage_key = $storage_key, variants = $variants RETURN AFTER;")
+        .bind(("image", image_key.to_string()))
+        .bind(("phash", phash.to_string()))
+        .bind(("storage_key", storage_key.to_string()))
+        .bind(("variants", variants))
+        .await?;
+    let updated: Vec<Image> = response.take(0)?;
+    Ok(updated.into_iter().next())
+}
+
+/// Give the images the positions of their keys in `ordered_keys`
+pub async fn reorder(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+    ordered_keys: &[String],
+    audit: &AuditContext,
+) -> surrealdb::Result<()> {
+    let order: Vec<serde_json::Value> = ordered_keys
+        .iter()
+        .enumerate()
+        .map(|(position, key)| json!({"key": key, "position": position}))
+        .collect();
+    db.query(
+        "FOR $entry IN $order {
+             UPDATE type::thing('images', $entry.key) SET position = $entry.position
+             WHERE guitar_id = type::thing('guitars', $guitar);
+         };",
+    )
+    .bind(("guitar", guitar_key.to_string()))
+    .bind(("order", order))
+    .bind(("audit", audit.clone()))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+/// Delete one image of a guitar and close the gap in positions; false if it wasn't the guitar's
+pub async fn remove(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+    image_key: &str,
+    audit: &AuditContext,
+) -> surrealdb::Result<bool> {
+    let mut response = db
+        .query("DELETE type::thing('images', $image) WHERE guitar_id = type::thing('guitars', $key) RETURN BEFORE;")
+        .bind(("key", guitar_key.to_string()))
+        .bind(("image", image_key.to_string()))
+        .bind(("audit", audit.clone()))
+        .await?;
+    let deleted: Vec<Image> = response.take(0)?;
+    if deleted.is_empty() {
+        return Ok(false);
+    }
+
+    let remaining: Vec<String> = fetch_for_guitar(db, guitar_key)
+        .await?
+        .iter()
+        .filter_map(|image| image.id.as_ref().map(|id| id.id.to_raw()))
+        .collect();
+    reorder(db, guitar_key, &remaining, audit).await?;
+    Ok(true)
+}
+
+/// A write through the legacy URL-array shape of `PUT /api/guitars/{id}/images`.
+/// `hero` and `gallery` are only applied when sent; `Some(None)` clears the hero.
+#[derive(Debug, Clone, Default)]
+pub struct LegacyImageUpdate {
+    pub hero: Option<Option<String>>,
+    pub gallery: Option<Vec<String>>,
+    pub image_source: Option<String>,
+    /// Condition label, already validated
+    pub condition: Option<String>,
+}
+
+/// Apply a legacy update in one transaction: replace the images of the roles that were sent
+/// (hero and/or gallery, detail images are never touched), keep the others in their order,
+/// and set the guitar's image_source and condition. A new hero goes first and new gallery
+/// images after the kept ones. Returns the images that were removed.
+pub async fn apply_legacy_update(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+    update: LegacyImageUpdate,
+    audit: &AuditContext,
+) -> surrealdb::Result<Vec<Image>> {
+    let mut replaced = Vec::new();
+    if update.hero.is_some() {
+        replaced.push(ImageRole::Hero);
+    }
+    if update.gallery.is_some() {
+        replaced.push(ImageRole::Gallery);
+    }
+    let (removed, kept): (Vec<Image>, Vec<Image>) = fetch_for_guitar(db, guitar_key)
+        .await?
+        .into_iter()
+        .partition(|image| replaced.contains(&image.role));
+
+    let hero = update.hero.flatten().filter(|url| !url.trim().is_empty());
+    let new_hero = hero.iter().map(|url| (url.as_str(), ImageRole::Hero));
+    let new_gallery = update
+        .gallery
+        .iter()
+        .flatten()
+        .filter(|url| !url.trim().is_empty() && Some(*url) != hero.as_ref())
+        .map(|url| (url.as_str(), ImageRole::Gallery));
+    let hero_count = new_hero.len();
+    let created: Vec<serde_json::Value> = new_hero
+        .chain(new_gallery)
+        .enumerate()
+        .map(|(index, (src, role))| {
+            let position = if index < hero_count { index } else { index + kept.len() };
+            json!({"src": src, "role": role, "position": position, "source": update.image_source})
+        })
+        .collect();
+    let kept: Vec<serde_json::Value> = kept
+        .iter()
+        .filter_map(|image| image.id.as_ref().map(|id| id.id.to_raw()))
+        .enumerate()
+        .map(|(index, key)| json!({"key": key, "position": index + hero_count}))
+        .collect();
+    let removed_keys: Vec<String> = removed.iter().filter_map(|image| image.id.as_ref().map(|id| id.id.to_raw())).collect();
+
+    db.query(
+        "BEGIN TRANSACTION;
+         FOR $image IN $removed {
+             DELETE type::thing('images', $image) WHERE guitar_id = type::thing('guitars', $key);
+         };
+         FOR $entry IN $kept {
+             UPDATE type::thing('images', $entry.key) SET position = $entry.position
+             WHERE guitar_id = type::thing('guitars', $key);
+         };
+         FOR $image IN $created {
+             CREATE images CONTENT {
+                 guitar_id: type::thing('guitars', $key),
+                 src: $image.src,
+                 role: $image.role,
+                 position: $image.position,
+                 source: $image.source
+             };
+         };
+         UPDATE type::thing('guitars', $key) SET
+             image_source = $image_source ?? image_source,
+             condition = $condition ?? condition,
+             updated_at = time::now();
+         COMMIT TRANSACTION;",
+    )
+    .bind(("key", guitar_key.to_string()))
+    .bind(("removed", removed_keys))
+    .bind(("kept", kept))
+    .bind(("created", created))
+    .bind(("image_source", update.image_source))
+    .bind(("condition", update.condition))
+    .bind(("audit", audit.clone()))
+    .await?
+    .check()?;
+    Ok(removed)
+}
+
+/// Delete the stored original and thumbnails of an image ingested by the API; images that
+/// only link elsewhere have nothing stored. Failures are logged, the record is already gone.
+pub async fn delete_stored_assets(storage: &ImageStorage, image: &Image) {
+    let Some(prefix) = image.storage_key.as_deref() else {
+        return;
+    };
+    let original = image.src.rsplit('/').next().map(|file| format!("{prefix}/{file}"));
+    let thumbnails = image
+        .variants
+        .iter()
+        .map(|variant| format!("{prefix}/w{}.{}", variant.width, variant.format));
+    for key in original.into_iter().chain(thumbnails) {
+        if let Err(e) = storage.delete(&key).await {
+            log::warn!("Could not remove stored image {key}: {e}");
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use std::env;
+use std::fmt;
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use surrealdb::Surreal;
+
+use crate::audit::AuditContext;
+use crate::images;
+use crate::models::{Image, ImageCreateRequest, ImageRole, ImageVariant};
+use crate::specs;
+use crate::storage::{ImageStorage, StorageError};
+
+/// Limits and thumbnail sizes for ingested images
+#[derive(Debug, Clone)]
+pub struct IngestSettings {
+    /// Largest accepted download or upload, in bytes
+    pub max_bytes: usize,
+    /// Widths of the generated thumbnails; widths not smaller than the original are skipped
+    pub thumbnail_widths: Vec<u32>,
+    /// Perceptual hashes this many bits apart or closer count as the same picture
+    pub duplicate_distance: u32,
+    /// MIME types accepted by the upload endpoint
+    pub allowed_types: Vec<String>,
+    /// Most files accepted in one upload request
+    pub max_files: usize,
+}
+
+impl Default for IngestSettings {
+    fn default() -> Self {
+        Self {
+            max_bytes: 20 * 1024 * 1024,
+            thumbnail_widths: vec![320, 640, 1280],
+            duplicate_distance: 4,
+            allowed_types: ["image/jpeg", "image/png", "image/webp", "image/gif"]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            max_files: 10,
+        }
+    }
+}
+
+impl IngestSettings {
+    /// Read `IMAGE_MAX_BYTES`, `IMAGE_THUMBNAIL_WIDTHS` and `IMAGE_ALLOWED_TYPES` (comma separated),
+    /// `IMAGE_DUPLICATE_DISTANCE` and `IMAGE_MAX_FILES`, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let widths: Vec<u32> = env::var("IMAGE_THUMBNAIL_WIDTHS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|w| w.trim().parse().ok())
+            .filter(|w| *w > 0)
+            .collect();
+        let types: Vec<String> = env::var("IMAGE_ALLOWED_TYPES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        Self {
+            max_bytes: env::var("IMAGE_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_bytes),
+            thumbnail_widths: if widths.is_empty() { defaults.thumbnail_widths } else { widths },
+            duplicate_distance: env::var("IMAGE_DUPLICATE_DISTANCE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.duplicate_distance),
+            allowed_types: if types.is_empty() { defaults.allowed_types } else { types },
+            max_files: env::var("IMAGE_MAX_FILES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.max_files),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum IngestError {
+    TooLarge { limit: usize },
+    Download(String),
+    /// Not an image, or a format that can't be decoded
+    Unsupported(String),
+    /// A near-identical picture is already stored
+    Duplicate { image: Box<Image>, distance: u32 },
+    Storage(StorageError),
+    Database(Box<surrealdb::Error>),
+    /// The image record could not be written
+    NotRecorded,
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestError::TooLarge { limit } => write!(f, "image is larger than {limit} bytes"),
+            IngestError::Download(message) => write!(f, "could not download image: {message}"),
+            IngestError::Unsupported(message) => write!(f, "unsupported image: {message}"),
+            IngestError::Duplicate { image, distance } => write!(
+                f,
+                "duplicate of {} (perceptual hash distance {distance})",
+                image.id.as_ref().map(|id| id.to_string()).unwrap_or_default()
+            ),
+            IngestError::Storage(e) => write!(f, "{e}"),
+            IngestError::Database(e) => write!(f, "{e}"),
+            IngestError::NotRecorded => write!(f, "image was not recorded"),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+impl From<surrealdb::Error> for IngestError {
+    fn from(e: surrealdb::Error) -> Self {
+        IngestError::Database(Box::new(e))
+    }
+}
+
+impl From<image::ImageError> for IngestError {
+    fn from(e: image::ImageError) -> Self {
+        IngestError::Unsupported(e.to_string())
+    }
+}
+
+/// One encoded file: the cleaned original or a thumbnail
+#[derive(Debug, Clone)]
+pub struct Rendition {
+    pub width: u32,
+    pub height: u32,
+    /// File extension, also the variant format ("jpg", "png", "webp", "avif")
+    pub extension: &'static str,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    pub width: u32,
+    pub height: u32,
+    pub phash: u64,
+    pub original: Rendition,
+    pub thumbnails: Vec<Rendition>,
+}
+
+/// dHash: compare neighbouring pixels of a 9x8 grayscale copy, one bit per pair.
+/// Robust to rescaling and recompression, so the same photo re-uploaded elsewhere matches.
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// MIME type of the image format detected from the file's magic bytes
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    image::guess_format(bytes).ok().map(|format| format.to_mime_type())
+}
+
+/// Number of differing bits between two perceptual hashes
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Encode as "jpg", "webp", "avif" (with the `avif` feature) or, for anything else, "png"
+pub fn encode(image: &DynamicImage, extension: &'static str) -> Result<Rendition, IngestError> {
+    let mut bytes = Vec::new();
+    let content_type = match extension {
+        "jpg" => {
+            // JPEG has no alpha channel
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, 90))?;
+            "image/jpeg"
+        }
+        "webp" => {
+            DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?;
+            "image/webp"
+        }
+        #[cfg(feature = "avif")]
+        "avif" => {
+            use image::codecs::avif::AvifEncoder;
+            DynamicImage::ImageRgba8(image.to_rgba8())
+                .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut bytes, 8, 70))?;
+            "image/avif"
+        }
+        _ => {
+            image.write_with_encoder(PngEncoder::new(&mut bytes))?;
+            "image/png"
+        }
+    };
+    Ok(Rendition {
+        width: image.width(),
+        height: image.height(),
+        extension,
+        content_type,
+        bytes,
+    })
+}
+
+/// Formats thumbnails are generated in
+fn thumbnail_formats() -> Vec<&'static str> {
+    let mut formats = vec!["webp"];
+    if cfg!(feature = "avif") {
+        formats.push("avif");
+    }
+    formats
+}
+
+/// Decode an image, apply its EXIF orientation and re-encode it without metadata
+/// (EXIF, GPS and camera data are dropped), then hash it and generate thumbnails.
+/// CPU-bound; call it off the async executor.
+pub fn process(bytes: &[u8], settings: &IngestSettings) -> Result<ProcessedImage, IngestError> {
+    if bytes.len() > settings.max_bytes {
+        return Err(IngestError::TooLarge { limit: settings.max_bytes });
+    }
+    let reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| IngestError::Unsupported(e.to_string()))?;
+    let format = reader
+        .format()
+        .ok_or_else(|| IngestError::Unsupported("unrecognized file format".to_string()))?;
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    // Keep JPEG photos as JPEG and WebP as WebP; everything else (PNG, GIF) is stored as PNG
+    let original = match format {
+        ImageFormat::Jpeg => encode(&image, "jpg")?,
+        ImageFormat::WebP => encode(&image, "webp")?,
+        _ => encode(&image, "png")?,
+    };
+
+    let mut widths: Vec<u32> = settings
+        .thumbnail_widths
+        .iter()
+        .copied()
+        .filter(|w| *w < image.width())
+        .collect();
+    widths.sort_unstable();
+    widths.dedup();
+    if widths.is_empty() {
+        widths.push(image.width());
+    }
+    let mut thumbnails = Vec::new();
+    for width in widths {
+        let resized = if width == image.width() {
+            image.clone()
+        } else {
+            image.resize(width, u32::MAX, FilterType::Lanczos3)
+        };
+        for extension in thumbnail_formats() {
+            thumbnails.push(encode(&resized, extension)?);
+        }
+    }
+
+    Ok(ProcessedImage {
+        width: image.width(),
+        height: image.height(),
+        phash: perceptual_hash(&image),
+        original,
+        thumbnails,
+    })
+}
+
+/// Download an image, refusing bodies over `max_bytes`
+pub async fn download(url: &str, max_bytes: usize) -> Result<Vec<u8>, IngestError> {
+    let mut response = reqwest::get(url)
+        .await
+        .map_err(|e| IngestError::Download(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(IngestError::Download(format!("{url} returned {}", response.status())));
+    }
+    if response.content_length().is_some_and(|length| length as usize > max_bytes) {
+        return Err(IngestError::TooLarge { limit: max_bytes });
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| IngestError::Download(e.to_string()))? {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(IngestError::TooLarge { limit: max_bytes });
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// The stored image closest to `phash` within `max_distance` bits, on any guitar
+pub async fn find_duplicate(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    phash: u64,
+    max_distance: u32,
+) -> surrealdb::Result<Option<(Image, u32)>> {
+    let mut response = db.query("SELECT * FROM images WHERE phash != NONE").await?;
+    let hashed: Vec<Image> = response.take(0)?;
+    Ok(hashed
+        .into_iter()
+        .filter_map(|image| {
+            let other = u64::from_str_radix(image.phash.as_deref()?, 16).ok()?;
+            Some((image, hash_distance(phash, other)))
+        })
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by_key(|(_, distance)| *distance))
+}
+
+/// Alt text, role and placement of an image being ingested
+#[derive(Debug, Clone, Default)]
+pub struct IngestOptions {
+    pub alt: Option<String>,
+    pub role: Option<ImageRole>,
+    pub source: Option<String>,
+    pub position: Option<i32>,
+}
+
+/// Clean, deduplicate and store an image for a guitar, then add it as an image record
+/// pointing at the stored original, with its dimensions, hash and thumbnails.
+pub async fn ingest(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    storage: &ImageStorage,
+    settings: &IngestSettings,
+    guitar_key: &str,
+    bytes: Vec<u8>,
+    options: IngestOptions,
+    audit: &AuditContext,
+) -> Result<Image, IngestError> {
+    let processing = settings.clone();
+    let processed = tokio::task::spawn_blocking(move || process(&bytes, &processing))
+        .await
+        .map_err(|e| IngestError::Unsupported(format!("image processing failed: {e}")))??;
+
+    if let Some((image, distance)) = find_duplicate(db, processed.phash, settings.duplicate_distance).await? {
+        return Err(IngestError::Duplicate { image: Box::new(image), distance });
+    }
+
+    let phash = format!("{:016x}", processed.phash);
+    let folder: String = guitar_key
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    let prefix = format!("guitars/{folder}/{phash}");
+
+    let mut stored = Vec::new();
+    let mut recorded = None;
+    let original_key = format!("{prefix}/original.{}", processed.original.extension);
+    let result = async {
+        let src = storage
+            .put(&original_key, processed.original.bytes.clone(), processed.original.content_type)
+            .await
+            .map_err(IngestError::Storage)?;
+        stored.push(original_key.clone());
+
+        let mut variants = Vec::new();
+        for thumbnail in &processed.thumbnails {
+            let key = format!("{prefix}/w{}.{}", thumbnail.width, thumbnail.extension);
+            let url = storage
+                .put(&key, thumbnail.bytes.clone(), thumbnail.content_type)
+                .await
+                .map_err(IngestError::Storage)?;
+            stored.push(key);
+            variants.push(ImageVariant {
+                src: url,
+                width: thumbnail.width as i32,
+                height: thumbnail.height as i32,
+                format: thumbnail.extension.to_string(),
+            });
+        }
+
+        let request = ImageCreateRequest {
+            src,
+            alt: options.alt,
+            w: Some(processed.width as i32),
+            h: Some(processed.height as i32),
+            role: options.role,
+            source: options.source,
+            position: options.position,
+        };
+        let created = images::add(db, guitar_key, request, audit).await?.ok_or(IngestError::NotRecorded)?;
+        let image_key = created.id.as_ref().map(specs::record_key).unwrap_or_default();
+        recorded = Some(image_key.clone());
+        images::set_assets(db, &image_key, &phash, &prefix, variants)
+            .await?
+            .ok_or(IngestError::NotRecorded)
+    }
+    .await;
+
+    // Don't leave a record or files behind for an image that wasn't fully recorded
+    if result.is_err() {
+        if let Some(image_key) = &recorded {
+            if let Err(e) = images::remove(db, guitar_key, image_key, audit).await {
+                log::warn!("Could not remove images:{image_key} after a failed ingest: {e}");
+            }
+        }
+        for key in &stored {
+            if let Err(e) = storage.delete(key).await {
+                log::warn!("Could not remove {key} after a failed ingest: {e}");
+            }
+        }
+    }
+    result
+}
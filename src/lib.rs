@@ -1,3 +1,26 @@
+pub mod audit;
+pub mod auth;
+pub mod catalog;
 pub mod config;
+pub mod duplicates;
+pub mod image_proxy;
+pub mod images;
+pub mod ingest;
+pub mod lineage;
+pub mod link_health;
+pub mod market;
 pub mod models;
+pub mod money;
+pub mod originality;
+pub mod paywall;
+pub mod price_history;
+pub mod rate_limit;
 pub mod routes;
+pub mod security;
+pub mod serial;
+pub mod similarity;
+pub mod specs;
+pub mod storage;
+pub mod trash;
+pub mod valuation;
+pub mod versions;
@@ -0,0 +1,130 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use serde_json::{Map, Value};
+
+use crate::models::SpecChange;
+
+/// Graph edge tables linking guitars and catalog models
+pub const RELATIONS: [&str; 3] = ["reissue_of", "successor_of", "signature_of"];
+
+/// Synthetic relation between a guitar and the catalog model it was built to
+pub const INSTANCE_OF: &str = "instance_of";
+
+/// Node tables an edge may point at
+pub const NODE_TABLES: [&str; 2] = ["guitars", "guitar_models"];
+
+/// Core fields included in spec deltas alongside the spec sections
+const CORE_FIELDS: [&str; 5] = ["body_style", "line", "variant", "year_reference", "spec_version_year"];
+
+/// An edge as loaded from one of the relation tables
+#[derive(Debug, Clone)]
+pub struct RawEdge {
+    pub relation: String,
+    pub from: String,
+    pub to: String,
+    pub note: Option<String>,
+}
+
+/// Collect every node reachable from `roots`, following edges in both directions.
+///
+/// Returns the reachable node ids in discovery order and the edges between them.
+pub fn collect_family(roots: &[String], edges: &[RawEdge]) -> (Vec<String>, Vec<RawEdge>) {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut order = Vec::new();
+    let mut queue: VecDeque<String> = roots.iter().cloned().collect();
+
+    while let Some(node) = queue.pop_front() {
+        if !seen.insert(node.clone()) {
+            continue;
+        }
+        order.push(node.clone());
+        for edge in edges {
+            if edge.from == node && !seen.contains(&edge.to) {
+                queue.push_back(edge.to.clone());
+            } else if edge.to == node && !seen.contains(&edge.from) {
+                queue.push_back(edge.from.clone());
+            }
+        }
+    }
+
+    let family_edges = edges
+        .iter()
+        .filter(|e| seen.contains(&e.from) && seen.contains(&e.to))
+        .cloned()
+        .collect();
+    (order, family_edges)
+}
+
+/// Flatten a spec document into `section.field` keys, skipping empty values.
+///
+/// Multi-row sections (pickups, controls) are keyed by position or name.
+pub fn flatten_spec(document: &Map<String, Value>) -> BTreeMap<String, Value> {
+    let mut flat = BTreeMap::new();
+    let mut put = |key: String, value: &Value| {
+        let empty = match value {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            Value::Array(items) => items.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            flat.insert(key, value.clone());
+        }
+    };
+
+    for field in CORE_FIELDS {
+        if let Some(value) = document.get(field) {
+            put(field.to_string(), value);
+        }
+    }
+
+    for (section, _) in crate::specs::SINGLE_ROW_SECTIONS {
+        if let Some(Value::Object(row)) = document.get(section) {
+            for (field, value) in row {
+                put(format!("{section}.{field}"), value);
+            }
+        }
+    }
+
+    for (section, key_field) in [("pickups", "position"), ("controls", "name")] {
+        let rows = document.get(section).and_then(Value::as_array);
+        for (index, row) in rows.into_iter().flatten().enumerate() {
+            let Some(row) = row.as_object() else {
+                continue;
+            };
+            let key = row
+                .get(key_field)
+                .and_then(Value::as_str)
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| s.to_lowercase())
+                .unwrap_or_else(|| index.to_string());
+            for (field, value) in row {
+                if field != key_field {
+                    put(format!("{section}.{key}.{field}"), value);
+                }
+            }
+        }
+    }
+
+    flat
+}
+
+/// Fields that differ between an older spec and a newer one
+pub fn spec_delta(older: &Map<String, Value>, newer: &Map<String, Value>) -> Vec<SpecChange> {
+    let older = flatten_spec(older);
+    let newer = flatten_spec(newer);
+    let fields: std::collections::BTreeSet<&String> = older.keys().chain(newer.keys()).collect();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let from = older.get(field);
+            let to = newer.get(field);
+            (from != to).then(|| SpecChange {
+                field: field.clone(),
+                from: from.cloned().unwrap_or(Value::Null),
+                to: to.cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect()
+}
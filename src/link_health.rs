@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use surrealdb::Surreal;
+
+use crate::audit::AuditContext;
+use crate::models::{Guitar, LinkCheck, LinkHealthSummary};
+use crate::specs;
+
+/// How often and how hard the link checker runs
+#[derive(Debug, Clone)]
+pub struct LinkCheckSettings {
+    /// Seconds between background runs; 0 disables the background job (the CLI still works)
+    pub interval_secs: u64,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    pub timeout_secs: u64,
+    /// Links checked more recently than this are skipped
+    pub recheck_after_hours: i64,
+    /// Phrases on a listing page that mean the guitar is gone (matched case-insensitively)
+    pub sold_markers: Vec<String>,
+}
+
+impl Default for LinkCheckSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 0,
+            concurrency: 8,
+            timeout_secs: 15,
+            recheck_after_hours: 24,
+            // Whole listing phrases only: bare "sold" or "sold out" also appears in navigation,
+            // related-item carousels and variant pickers of pages that are still for sale
+            sold_markers: [
+                "this item has sold",
+                "this item is sold",
+                "this guitar has sold",
+                "this listing has sold",
+                "this listing has ended",
+                "this listing is no longer available",
+                "this item is no longer available",
+            ]
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl LinkCheckSettings {
+    /// Read the `LINK_CHECK_*` environment variables, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str| env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+        let markers: Vec<String> = env::var("LINK_CHECK_SOLD_MARKERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|m| m.trim().to_lowercase())
+            .filter(|m| !m.is_empty())
+            .collect();
+        Self {
+            interval_secs: number("LINK_CHECK_INTERVAL_SECS").unwrap_or(defaults.interval_secs),
+            concurrency: number("LINK_CHECK_CONCURRENCY")
+                .filter(|n| *n > 0)
+                .map_or(defaults.concurrency, |n| n as usize),
+            timeout_secs: number("LINK_CHECK_TIMEOUT_SECS")
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.timeout_secs),
+            recheck_after_hours: number("LINK_CHECK_RECHECK_HOURS").map_or(defaults.recheck_after_hours, |n| n as i64),
+            sold_markers: if markers.is_empty() { defaults.sold_markers } else { markers },
+        }
+    }
+}
+
+/// What a checked URL is used for on the guitar
+pub const KIND_LISTING: &str = "listing";
+pub const KIND_HERO_IMAGE: &str = "hero_image";
+pub const KIND_GALLERY_IMAGE: &str = "gallery_image";
+
+/// Listing pages are read up to this size when looking for sold markers
+const MAX_PAGE_BYTES: usize = 1024 * 1024;
+
+/// One URL of one guitar to check
+#[derive(Debug, Clone)]
+pub struct LinkTarget {
+    pub guitar_key: String,
+    pub url: String,
+    pub kind: &'static str,
+}
+
+/// Result of requesting one URL
+#[derive(Debug, Clone)]
+pub struct LinkOutcome {
+    pub status_code: Option<u16>,
+    pub ok: bool,
+    pub probably_sold: bool,
+    pub error: Option<String>,
+}
+
+/// Checks URLs with its own HTTP client; pass a custom client to point it at a stub server
+#[derive(Clone)]
+pub struct LinkChecker {
+    client: reqwest::Client,
+    pub settings: LinkCheckSettings,
+}
+
+impl LinkChecker {
+    pub fn new(settings: LinkCheckSettings) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .user_agent(concat!("gear_api-link-checker/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("link checker HTTP client");
+        Self::with_client(client, settings)
+    }
+
+    pub fn with_client(client: reqwest::Client, settings: LinkCheckSettings) -> Self {
+        Self { client, settings }
+    }
+
+    /// Images are checked with HEAD (falling back to GET for servers that refuse it);
+    /// listing pages are fetched and searched for sold markers.
+    pub async fn check(&self, url: &str, kind: &str) -> LinkOutcome {
+        let response = if kind == KIND_LISTING {
+            self.client.get(url).send().await
+        } else {
+            match self.client.head(url).send().await {
+                Ok(response) if matches!(response.status().as_u16(), 403 | 405 | 501) => self.client.get(url).send().await,
+                other => other,
+            }
+        };
+        let mut response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                return LinkOutcome {
+                    status_code: None,
+                    ok: false,
+                    probably_sold: false,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+
+        let status = response.status();
+        let gone = matches!(status.as_u16(), 404 | 410);
+        let mut probably_sold = kind == KIND_LISTING && gone;
+        if kind == KIND_LISTING && status.is_success() {
+            let mut page = Vec::new();
+            while let Ok(Some(chunk)) = response.chunk().await {
+                page.extend_from_slice(&chunk);
+                if page.len() >= MAX_PAGE_BYTES {
+                    break;
+                }
+            }
+            let text = String::from_utf8_lossy(&page).to_lowercase();
+            probably_sold = self.settings.sold_markers.iter().any(|marker| text.contains(marker.as_str()));
+        }
+        LinkOutcome {
+            status_code: Some(status.as_u16()),
+            ok: status.is_success(),
+            probably_sold,
+            error: None,
+        }
+    }
+}
+
+/// Record id of the check of `url` on a guitar
+pub fn check_key(guitar_key: &str, url: &str) -> String {
+    let digest = hex::encode(Sha256::digest(url.as_bytes()));
+    format!("{guitar_key}_{}", &digest[..16])
+}
+
+/// Listing, hero and gallery URLs of the guitars
+pub fn targets(guitars: &[Guitar]) -> Vec<LinkTarget> {
+    let mut targets = Vec::new();
+    for guitar in guitars {
+        let Some(guitar_key) = guitar.id.as_ref().map(specs::record_key) else {
+            continue;
+        };
+        let mut add = |url: Option<&str>, kind: &'static str| {
+            let Some(url) = url.map(str::trim).filter(|u| u.starts_with("http://") || u.starts_with("https://")) else {
+                return;
+            };
+            if !targets.iter().any(|t: &LinkTarget| t.guitar_key == guitar_key && t.url == url) {
+                targets.push(LinkTarget {
+                    guitar_key: guitar_key.clone(),
+                    url: url.to_string(),
+                    kind,
+                });
+            }
+        };
+        add(guitar.listing_url.as_deref(), KIND_LISTING);
+        add(guitar.hero_image_url.as_deref(), KIND_HERO_IMAGE);
+        for url in guitar.image_gallery.iter().flatten() {
+            add(Some(url), KIND_GALLERY_IMAGE);
+        }
+    }
+    targets
+}
+
+/// Check every listing and image URL not checked within `recheck_after_hours` (all of them when
+/// `force`), record the results in link_checks and flag guitars whose listing looks sold.
+pub async fn run(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    checker: &LinkChecker,
+    force: bool,
+) -> surrealdb::Result<LinkHealthSummary> {
+    let guitars: Vec<Guitar> = specs::fetch_guitars(db).await?;
+    let previous: Vec<LinkCheck> = db.select("link_checks").await?;
+    let last_checked: HashMap<String, DateTime<Utc>> = previous
+        .iter()
+        .filter_map(|check| {
+            let checked_at: DateTime<Utc> = check.checked_at.clone()?.into();
+            Some((check_key(&specs::record_key(check.guitar_id.as_ref()?), &check.url), checked_at))
+        })
+        .collect();
+    let cutoff = Utc::now() - chrono::Duration::hours(checker.settings.recheck_after_hours);
+    let due: Vec<LinkTarget> = targets(&guitars)
+        .into_iter()
+        .filter(|t| force || last_checked.get(&check_key(&t.guitar_key, &t.url)).is_none_or(|at| *at < cutoff))
+        .collect();
+
+    let results: Vec<(LinkTarget, LinkOutcome)> = stream::iter(due)
+        .map(|target| async move {
+            let outcome = checker.check(&target.url, target.kind).await;
+            (target, outcome)
+        })
+        .buffer_unordered(checker.settings.concurrency)
+        .collect()
+        .await;
+
+    let mut summary = LinkHealthSummary::default();
+    for (_, outcome) in &results {
+        summary.checked += 1;
+        match (outcome.ok, outcome.probably_sold) {
+            (_, true) => summary.probably_sold += 1,
+            (true, false) => summary.ok += 1,
+            (false, false) => summary.broken += 1,
+        }
+    }
+
+    let rows: Vec<serde_json::Value> = results
+        .iter()
+        .map(|(target, outcome)| {
+            json!({
+                "key": check_key(&target.guitar_key, &target.url),
+                "guitar": target.guitar_key,
+                "url": target.url,
+                "kind": target.kind,
+                "status_code": outcome.status_code,
+                "ok": outcome.ok,
+                "probably_sold": outcome.probably_sold,
+                "error": outcome.error,
+            })
+        })
+        .collect();
+    // Only answered listing requests change the flag; timeouts say nothing about the guitar
+    let listings: Vec<serde_json::Value> = results
+        .iter()
+        .filter(|(target, outcome)| target.kind == KIND_LISTING && outcome.status_code.is_some())
+        .map(|(target, outcome)| json!({"guitar": target.guitar_key, "probably_sold": outcome.probably_sold}))
+        .collect();
+
+    db.query(
+        "FOR $row IN $rows {
+             UPSERT type::thing('link_checks', $row.key) CONTENT {
+                 guitar_id: type::thing('guitars', $row.guitar),
+                 url: $row.url,
+                 kind: $row.kind,
+                 status_code: $row.status_code,
+                 ok: $row.ok,
+                 probably_sold: $row.probably_sold,
+                 error: $row.error,
+                 checked_at: time::now()
+             };
+         };
+         FOR $listing IN $listings {
+             UPDATE type::thing('guitars', $listing.guitar) SET
+                 probably_sold = $listing.probably_sold,
+                 probably_sold_at = IF $listing.probably_sold THEN probably_sold_at ?? time::now() ELSE NONE END;
+         };",
+    )
+    .bind(("rows", rows))
+    .bind(("listings", listings))
+    .bind(("audit", AuditContext::system("link-checker")))
+    .await?
+    .check()?;
+
+    Ok(summary)
+}
+
+/// Run the checker every `interval_secs` for as long as the server is up
+pub async fn run_periodically(db: Surreal<surrealdb::engine::remote::http::Client>, checker: LinkChecker) {
+    let interval = Duration::from_secs(checker.settings.interval_secs);
+    loop {
+        match run(&db, &checker, false).await {
+            Ok(summary) => log::info!(
+                "Link check: {} checked, {} ok, {} broken, {} probably sold",
+                summary.checked,
+                summary.ok,
+                summary.broken,
+                summary.probably_sold
+            ),
+            Err(e) => log::warn!("Link check failed: {e}"),
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve `respond(method, path) -> (status, body)` over plain HTTP/1.1 on a free local port
+    async fn stub_server(respond: fn(&str, &str) -> (u16, &'static str)) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                let head = String::from_utf8_lossy(&request);
+                let mut parts = head.split_whitespace();
+                let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+                let (status, body) = respond(method, path);
+                let response = format!(
+                    "HTTP/1.1 {status} Stub\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    if method == "HEAD" { "" } else { body }
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{address}")
+    }
+
+    fn checker() -> LinkChecker {
+        LinkChecker::with_client(reqwest::Client::new(), LinkCheckSettings::default())
+    }
+
+    #[tokio::test]
+    async fn missing_listing_is_probably_sold() {
+        let base = stub_server(|_, _| (404, "not here")).await;
+        let outcome = checker().check(&format!("{base}/listing/1"), KIND_LISTING).await;
+        assert_eq!(outcome.status_code, Some(404));
+        assert!(!outcome.ok);
+        assert!(outcome.probably_sold);
+
+        // A missing image is broken, not sold
+        let outcome = checker().check(&format!("{base}/img/1.jpg"), KIND_HERO_IMAGE).await;
+        assert!(!outcome.ok);
+        assert!(!outcome.probably_sold);
+    }
+
+    #[tokio::test]
+    async fn listing_page_with_sold_marker_is_probably_sold() {
+        let base = stub_server(|_, path| match path {
+            "/sold" => (200, "<h1>1958 Flying V</h1><p>This listing has ended.</p>"),
+            _ => (200, "<h1>1958 Flying V</h1><nav><a>Sold</a></nav><p>Other strings: sold out</p>"),
+        })
+        .await;
+        let sold = checker().check(&format!("{base}/sold"), KIND_LISTING).await;
+        assert!(sold.ok);
+        assert!(sold.probably_sold);
+
+        // Generic "sold" wording elsewhere on a live page is not a sale
+        let live = checker().check(&format!("{base}/live"), KIND_LISTING).await;
+        assert!(live.ok);
+        assert!(!live.probably_sold);
+    }
+
+    #[tokio::test]
+    async fn image_falls_back_to_get_when_head_is_refused() {
+        let base = stub_server(|method, _| match method {
+            "HEAD" => (405, ""),
+            _ => (200, "image bytes"),
+        })
+        .await;
+        let outcome = checker().check(&format!("{base}/img/1.jpg"), KIND_GALLERY_IMAGE).await;
+        assert_eq!(outcome.status_code, Some(200));
+        assert!(outcome.ok);
+        assert!(outcome.error.is_none());
+    }
+}
@@ -1,11 +1,34 @@
-use actix_cors::Cors;
-use actix_web::{middleware::Logger, web, App, HttpServer};
+use actix_web::middleware::{from_fn, Logger};
+use actix_web::{web, App, HttpServer};
 use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
 
+mod audit;
+mod auth;
+mod catalog;
 mod config;
+mod duplicates;
+mod image_proxy;
+mod images;
+mod ingest;
+mod lineage;
+mod link_health;
+mod market;
 mod models;
+mod money;
+mod originality;
+mod paywall;
+mod price_history;
+mod rate_limit;
 mod routes;
+mod security;
+mod serial;
+mod similarity;
+mod specs;
+mod storage;
+mod trash;
+mod valuation;
+mod versions;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -32,14 +55,77 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("use ns/db");
 
-    HttpServer::new(move || {
-        let cors = Cors::permissive();
+    let similarity_weights = cfg.similarity_weights.clone();
+    let serial_decoder = match cfg.serial_rules_path.as_deref() {
+        Some(path) => serial::SerialDecoder::from_file(path).unwrap_or_else(|e| {
+            log::warn!("Could not load serial rules from {path}: {e}");
+            serial::SerialDecoder::default()
+        }),
+        None => serial::SerialDecoder::default(),
+    };
+    let exchange_rates = match cfg.exchange_rates_path.as_deref() {
+        Some(path) => money::ExchangeRates::from_file(path).unwrap_or_else(|e| {
+            log::warn!("Could not load exchange rates from {path}: {e}");
+            money::ExchangeRates::default()
+        }),
+        None => money::ExchangeRates::default(),
+    };
+    let paywall_policy = match cfg.paywall_policy_path.as_deref() {
+        Some(path) => paywall::PaywallPolicy::from_file(path).unwrap_or_else(|e| {
+            log::warn!("Could not load paywall policy from {path}: {e}");
+            paywall::PaywallPolicy::default()
+        }),
+        None => paywall::PaywallPolicy::default(),
+    };
+    let image_storage = cfg.image_storage.clone();
+    let ingest_settings = cfg.ingest_settings.clone();
+    let local_media = image_storage
+        .local_root()
+        .map(|(root, base_url)| (root.to_path_buf(), base_url.trim_end_matches('/').to_string()));
+    if let Some((root, _)) = &local_media {
+        std::fs::create_dir_all(root)?;
+    }
+    let image_cache = image_proxy::ImageCache::open(&cfg.proxy_settings)?;
+    let auth_settings = cfg.auth_settings.clone();
+    let rate_limiter = rate_limit::RateLimiter::new(cfg.rate_limit_settings.clone());
+    let cors_settings = cfg.cors_settings.clone();
+    let security_headers = cfg.security_headers.clone();
+    if cfg.profile == config::Profile::Dev {
+        log::warn!("APP_PROFILE=dev: any origin may call write endpoints and HSTS is off");
+    }
+
+    if cfg.link_check_settings.interval_secs > 0 {
+        let checker = link_health::LinkChecker::new(cfg.link_check_settings.clone());
+        actix_web::rt::spawn(link_health::run_periodically(db.clone(), checker));
+    }
 
-        App::new()
+    HttpServer::new(move || {
+        let app = App::new()
+            .wrap(from_fn(rate_limit::limit))
+            .wrap(from_fn(security::cors))
+            .wrap(from_fn(security::security_headers))
             .wrap(Logger::default())
-            .wrap(cors)
             .app_data(web::Data::new(db.clone()))
-            .configure(routes::config)
+            .app_data(web::Data::new(similarity_weights.clone()))
+            .app_data(web::Data::new(serial_decoder.clone()))
+            .app_data(web::Data::new(exchange_rates.clone()))
+            .app_data(web::Data::new(image_storage.clone()))
+            .app_data(web::Data::new(ingest_settings.clone()))
+            .app_data(web::Data::new(image_cache.clone()))
+            .app_data(web::Data::new(auth_settings.clone()))
+            .app_data(web::Data::new(paywall_policy.clone()))
+            .app_data(web::Data::new(rate_limiter.clone()))
+            .app_data(web::Data::new(cors_settings.clone()))
+            .app_data(web::Data::new(security_headers.clone()))
+            .configure(routes::config);
+
+        // Ingested images kept on this server are served from the storage directory
+        match &local_media {
+            Some((root, base_url)) if base_url.starts_with('/') => {
+                app.service(actix_files::Files::new(base_url, root))
+            }
+            _ => app,
+        }
     })
     .bind((cfg.host.as_str(), cfg.port))?
     .run()
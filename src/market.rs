@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{Condition, ConditionBreakdown, Guitar, MarketComparable, MarketStats, PriceRange};
+use crate::money::{ExchangeRates, Money};
+use crate::price_history::MarketTimeline;
+use crate::specs;
+
+/// Which listings count as comparables; empty filters match everything
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub spec_version_year: Option<i32>,
+}
+
+impl MarketFilter {
+    pub fn matches(&self, guitar: &Guitar) -> bool {
+        let same = |wanted: &Option<String>, actual: &str| {
+            wanted.as_deref().is_none_or(|w| w.trim().eq_ignore_ascii_case(actual.trim()))
+        };
+        same(&self.brand, &guitar.brand)
+            && same(&self.model, &guitar.model)
+            && self.spec_version_year.is_none_or(|year| guitar.spec_version_year == Some(year))
+    }
+}
+
+pub fn is_sold(guitar: &Guitar) -> bool {
+    guitar.status.as_deref().is_some_and(|s| s.trim().eq_ignore_ascii_case("sold"))
+}
+
+/// Slug of the guitar's condition on the common scale, or "unknown"
+pub fn condition_slug(guitar: &Guitar) -> String {
+    guitar.get_condition().map_or("unknown", Condition::slug).to_string()
+}
+
+/// Listing price, or the last recorded price for listings that no longer show one (typically sold)
+fn listed_price(guitar: &Guitar, timeline: &MarketTimeline, key: &str) -> Option<Money> {
+    guitar.get_price().or_else(|| {
+        timeline
+            .history
+            .get(key)
+            .and_then(|points| points.iter().rev().find_map(|p| p.price()))
+    })
+}
+
+/// Comparable listings for `filter`, with prices converted to `currency`
+pub fn comparables(
+    guitars: &[Guitar],
+    filter: &MarketFilter,
+    timeline: &MarketTimeline,
+    rates: &ExchangeRates,
+    currency: &str,
+    now: DateTime<Utc>,
+) -> Vec<MarketComparable> {
+    let mut comparables: Vec<MarketComparable> = guitars
+        .iter()
+        .filter(|g| filter.matches(g))
+        .filter_map(|guitar| {
+            let key = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+            let price = listed_price(guitar, timeline, &key)?;
+            let converted_price = rates.convert(&price, currency)?;
+            Some(MarketComparable {
+                id: guitar.id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+                slug: guitar.get_slug(),
+                display_title: guitar.get_display_title(),
+                status: guitar.status.clone(),
+                condition_slug: condition_slug(guitar),
+                price,
+                converted_price,
+                days_on_market: timeline.price_change(&key, guitar, now).days_on_market,
+                sold: is_sold(guitar),
+            })
+        })
+        .collect();
+    comparables.sort_by(|a, b| {
+        a.converted_price
+            .amount_minor
+            .cmp(&b.converted_price.amount_minor)
+            .then_with(|| a.slug.cmp(&b.slug))
+    });
+    comparables
+}
+
+/// Linear-interpolated percentile (0.0..=1.0) of ascending values
+pub fn percentile(sorted: &[i64], p: f64) -> Option<i64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p.clamp(0.0, 1.0) * last as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    let fraction = rank - lower as f64;
+    Some((sorted[lower] as f64 + (sorted[upper] - sorted[lower]) as f64 * fraction).round() as i64)
+}
+
+/// Aggregate comparables (already converted to `currency`) into market statistics
+pub fn summarize(filter: &MarketFilter, currency: &str, comparables: Vec<MarketComparable>) -> MarketStats {
+    let amount = |value: Option<i64>| value.map(|minor| Money::new(minor, currency));
+
+    let mut prices: Vec<i64> = comparables.iter().map(|c| c.converted_price.amount_minor).collect();
+    prices.sort_unstable();
+
+    let days: Vec<i64> = comparables.iter().filter_map(|c| c.days_on_market).collect();
+    let average_days_on_market = (!days.is_empty())
+        .then(|| (days.iter().sum::<i64>() as f64 / days.len() as f64 * 10.0).round() / 10.0);
+
+    let mut by_condition: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    for comparable in &comparables {
+        by_condition
+            .entry(comparable.condition_slug.as_str())
+            .or_default()
+            .push(comparable.converted_price.amount_minor);
+    }
+    let mut conditions: Vec<ConditionBreakdown> = by_condition
+        .into_iter()
+        .filter_map(|(slug, mut values)| {
+            values.sort_unstable();
+            Some(ConditionBreakdown {
+                condition_slug: slug.to_string(),
+                count: values.len(),
+                median: Money::new(percentile(&values, 0.5)?, currency),
+            })
+        })
+        .collect();
+    // Best condition first, unknown last
+    let rank = |slug: &str| Condition::ALL.iter().position(|c| c.slug() == slug).unwrap_or(Condition::ALL.len());
+    conditions.sort_by_key(|c| rank(&c.condition_slug));
+
+    MarketStats {
+        brand: filter.brand.clone(),
+        model: filter.model.clone(),
+        spec_version_year: filter.spec_version_year,
+        currency: currency.to_string(),
+        count: comparables.len(),
+        sold_count: comparables.iter().filter(|c| c.sold).count(),
+        median: amount(percentile(&prices, 0.5)),
+        p25: amount(percentile(&prices, 0.25)),
+        p75: amount(percentile(&prices, 0.75)),
+        average_days_on_market,
+        conditions,
+        comparables,
+        paywall_applied: false,
+    }
+}
+
+/// Lowest and highest listing price of `guitars` in `currency`; None when none are priced
+pub fn price_range<'a>(
+    guitars: impl IntoIterator<Item = &'a Guitar>,
+    rates: &ExchangeRates,
+    currency: &str,
+) -> Option<PriceRange> {
+    let prices: Vec<i64> = guitars
+        .into_iter()
+        .filter_map(|g| g.get_price().and_then(|price| rates.convert(&price, currency)))
+        .map(|price| price.amount_minor)
+        .collect();
+    Some(PriceRange {
+        count: prices.len(),
+        low: Money::new(*prices.iter().min()?, currency),
+        high: Money::new(*prices.iter().max()?, currency),
+    })
+}
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use surrealdb::sql::Thing; // record id type "table:id"
+use surrealdb::sql::{Datetime, Thing}; // record id type "table:id"
 use utoipa::ToSchema;
 
+use crate::money::Money;
+
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[schema(example = json!({
     "id": "guitars:123",
@@ -64,7 +65,19 @@ pub struct Guitar {
     #[serde(default)]
     pub condition: Option<String>,
     #[serde(default)]
+    pub condition_slug: Option<String>,
+    #[serde(default)]
+    pub condition_display: Option<String>,
+    // Condition slug on the common scale, computed in the database by fn::condition_grade
+    #[serde(default)]
+    pub condition_grade: Option<String>,
+    #[serde(default)]
     pub status: Option<String>,
+    // Set by the status transition endpoint
+    #[serde(default)]
+    pub status_changed_at: Option<Datetime>,
+    #[serde(default)]
+    pub sold_at: Option<Datetime>,
     #[serde(default)]
     pub production_year: Option<i32>,
     #[serde(default)]
@@ -83,6 +96,25 @@ pub struct Guitar {
     // Legacy image field
     #[serde(default)]
     pub hero_url: Option<String>,
+    // Catalog model this instance was built to; spec tables only hold overrides
+    #[serde(default)]
+    pub model_ref: Option<Thing>,
+    // Brand and shop profiles; shop_name/shop_slug are only kept for unlinked legacy rows
+    #[serde(default)]
+    pub brand_ref: Option<Thing>,
+    #[serde(default)]
+    pub shop_ref: Option<Thing>,
+    // Share of assessed components that are original (0-100), stored by POST /api/guitars/{id}/originality
+    #[serde(default)]
+    pub originality_score: Option<f64>,
+    // Set by the link checker when the listing page is gone or says sold
+    #[serde(default)]
+    pub probably_sold: Option<bool>,
+    #[serde(default)]
+    pub probably_sold_at: Option<Datetime>,
+    // Set when the guitar is moved to the trash; trashed guitars are left out of every read
+    #[serde(default)]
+    pub deleted_at: Option<Datetime>,
 }
 
 impl Guitar {
@@ -92,7 +124,7 @@ impl Guitar {
             let brand = self.brand.to_lowercase().replace(" ", "-");
             let model = self.model.to_lowercase().replace(" ", "-");
             let year = self.year_reference.to_lowercase();
-            format!("{}-{}-{}", brand, model, year)
+            format!("{brand}-{model}-{year}")
         })
     }
 
@@ -103,14 +135,16 @@ impl Guitar {
             .or_else(|| self.hero_url.clone())
     }
 
-    /// Get formatted price for display
+    /// Get the listing price; `price_cents` holds minor units of `price_currency`
+    pub fn get_price(&self) -> Option<Money> {
+        (self.price_cents > 0).then(|| Money::new(self.price_cents, &self.price_currency))
+    }
+
+    /// Get formatted price for display in the listing currency's locale
     pub fn get_formatted_price(&self) -> String {
-        if self.price_cents > 0 {
-            let dollars = self.price_cents as f64 / 100.0;
-            format!("${:.2}", dollars)
-        } else {
-            "Price on request".to_string()
-        }
+        self.get_price()
+            .map(|price| price.formatted)
+            .unwrap_or_else(|| "Price on request".to_string())
     }
 
     /// Get display title for the guitar
@@ -122,7 +156,7 @@ impl Guitar {
     pub fn has_images(&self) -> bool {
         self.hero_image_url.is_some()
             || self.hero_url.is_some()
-            || self.image_gallery.as_ref().map_or(false, |gallery| !gallery.is_empty())
+            || self.image_gallery.as_ref().is_some_and(|gallery| !gallery.is_empty())
     }
 
     /// Get image gallery count
@@ -132,22 +166,1103 @@ impl Guitar {
 
     /// Get condition color for frontend styling
     pub fn get_condition_color(&self) -> &'static str {
-        match self.condition.as_deref() {
-            Some("Excellent +") | Some("Excellent") => "green",
-            Some("Good") => "yellow",
-            Some("Fair") | Some("Poor") => "red",
-            _ => "gray",
-        }
+        self.get_condition().map_or("gray", Condition::color)
+    }
+
+    /// Condition on the common grading scale, mapped from whichever source graded it
+    pub fn get_condition(&self) -> Option<Condition> {
+        self.condition_grade.as_deref().and_then(Condition::parse).or_else(|| {
+            [&self.condition_slug, &self.condition_display, &self.condition]
+                .into_iter()
+                .flatten()
+                .find_map(|raw| Condition::from_source(self.external_source.as_deref(), raw))
+        })
     }
 
     /// Get status color for frontend styling
     pub fn get_status_color(&self) -> &'static str {
-        match self.status.as_deref() {
-            Some("Available") => "success",
-            Some("Sold") => "danger",
-            Some("On Hold") => "warning",
-            Some("Pending") => "info",
-            _ => "secondary",
+        self.listing_status().map_or("secondary", ListingStatus::color)
+    }
+
+    /// Parsed listing status; None when unset or not a known status
+    pub fn listing_status(&self) -> Option<ListingStatus> {
+        self.status.as_deref().and_then(ListingStatus::parse)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "guitar_models:v_custom",
+    "brand": "Banker",
+    "model": "V Custom",
+    "slug": "banker-v-custom",
+    "line": "Custom",
+    "body_style": "V",
+    "year_reference": "1958",
+    "spec_version_year": 1958,
+    "specs": {
+        "dimensions": {"scale_length": "24 3/4", "nut_width": "1 11/16", "neck_profile": "'58 D Shape"},
+        "pickups": [{"position": "Bridge", "type": "Humbucker", "model": "Banker PAF"}]
+    }
+}))]
+pub struct GuitarModel {
+    pub id: Option<Thing>, // Surreal record id, e.g. guitar_models:abc...
+    pub brand: String,
+    pub model: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub line: Option<String>,
+    #[serde(default)]
+    pub variant: Option<String>,
+    #[serde(default)]
+    pub body_style: Option<String>,
+    #[serde(default)]
+    pub year_reference: Option<String>,
+    #[serde(default)]
+    pub spec_version_year: Option<i32>,
+    // Factory spec sections, keyed like the guitar detail response (dimensions, woods, pickups, ...)
+    #[serde(default)]
+    pub specs: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl GuitarModel {
+    /// Generate a slug for this catalog model if one doesn't exist
+    pub fn get_slug(&self) -> String {
+        self.slug.clone().unwrap_or_else(|| {
+            let brand = self.brand.to_lowercase().replace(" ", "-");
+            let model = self.model.to_lowercase().replace(" ", "-");
+            format!("{brand}-{model}")
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "shops:carter-vintage",
+    "name": "Carter Vintage Guitars",
+    "slug": "carter-vintage",
+    "location": "Nashville, TN, US",
+    "website": "https://www.cartervintage.com",
+    "currency": "USD",
+    "specialties": ["vintage Gibson", "vintage Fender"],
+    "logo_url": "https://example.com/carter-vintage.png"
+}))]
+pub struct Shop {
+    pub id: Option<Thing>, // Surreal record id, keyed by slug, e.g. shops:carter-vintage
+    pub name: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub website: Option<String>,
+    /// Currency the shop lists in
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub specialties: Vec<String>,
+    #[serde(default)]
+    pub logo_url: Option<String>,
+    // Marketplace shop id (e.g. Reverb shop_id)
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+impl Shop {
+    /// Generate a slug for this shop if one doesn't exist
+    pub fn get_slug(&self) -> String {
+        self.slug.clone().unwrap_or_else(|| self.name.to_lowercase().replace(" ", "-"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "brands:nik-huber",
+    "name": "Nik Huber",
+    "slug": "nik-huber",
+    "location": "Rodgau, DE",
+    "website": "https://www.nikhuber-guitars.com",
+    "currency": "EUR",
+    "specialties": ["set-neck solid bodies", "boutique builds"],
+    "logo_url": "https://example.com/nik-huber.png"
+}))]
+pub struct Brand {
+    pub id: Option<Thing>, // Surreal record id, keyed by slug, e.g. brands:nik-huber
+    pub name: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub website: Option<String>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub specialties: Vec<String>,
+    #[serde(default)]
+    pub logo_url: Option<String>,
+}
+
+impl Brand {
+    /// Generate a slug for this brand if one doesn't exist
+    pub fn get_slug(&self) -> String {
+        self.slug.clone().unwrap_or_else(|| self.name.to_lowercase().replace(" ", "-"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "count": 3,
+    "low": {"amount_minor": 999900, "currency": "USD", "formatted": "$9,999.00"},
+    "high": {"amount_minor": 1381412, "currency": "USD", "formatted": "$13,814.12"}
+}))]
+pub struct PriceRange {
+    /// Priced listings the range covers
+    pub count: usize,
+    pub low: Money,
+    pub high: Money,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "field": "dimensions.nut_width",
+    "factory": "1.687",
+    "instance": "1.71"
+}))]
+pub struct SpecDeviation {
+    pub field: String,
+    pub factory: serde_json::Value,
+    pub instance: serde_json::Value,
+}
+
+/// Condition on a common grading scale, ordered from worst to best so `Mint > ExcellentPlus > ... > NonFunctioning`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Condition {
+    NonFunctioning,
+    Poor,
+    Fair,
+    Good,
+    VeryGood,
+    Excellent,
+    ExcellentPlus,
+    Mint,
+}
+
+impl Condition {
+    pub const ALL: [Condition; 8] = [
+        Condition::Mint,
+        Condition::ExcellentPlus,
+        Condition::Excellent,
+        Condition::VeryGood,
+        Condition::Good,
+        Condition::Fair,
+        Condition::Poor,
+        Condition::NonFunctioning,
+    ];
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Condition::Mint => "mint",
+            Condition::ExcellentPlus => "excellent-plus",
+            Condition::Excellent => "excellent",
+            Condition::VeryGood => "very-good",
+            Condition::Good => "good",
+            Condition::Fair => "fair",
+            Condition::Poor => "poor",
+            Condition::NonFunctioning => "non-functioning",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Condition::Mint => "Mint",
+            Condition::ExcellentPlus => "Excellent +",
+            Condition::Excellent => "Excellent",
+            Condition::VeryGood => "Very Good",
+            Condition::Good => "Good",
+            Condition::Fair => "Fair",
+            Condition::Poor => "Poor",
+            Condition::NonFunctioning => "Non Functioning",
+        }
+    }
+
+    /// Parse a label or slug on the common scale ("Excellent +", "very-good", "VG+")
+    pub fn parse(raw: &str) -> Option<Self> {
+        let slug = raw
+            .trim()
+            .to_lowercase()
+            .replace('+', " plus")
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        match slug.as_str() {
+            "mint" | "new" | "brand-new" => Some(Condition::Mint),
+            "excellent-plus" | "ex-plus" | "near-mint" | "nm" => Some(Condition::ExcellentPlus),
+            "excellent" | "ex" | "b-stock" => Some(Condition::Excellent),
+            "very-good" | "very-good-plus" | "vg" | "vg-plus" => Some(Condition::VeryGood),
+            "good" | "good-plus" | "g" => Some(Condition::Good),
+            "fair" => Some(Condition::Fair),
+            "poor" => Some(Condition::Poor),
+            "non-functioning" | "not-functioning" | "for-parts" | "for-parts-or-not-working" => {
+                Some(Condition::NonFunctioning)
+            }
+            _ => None,
+        }
+    }
+
+    /// Map an external source's grading onto the common scale.
+    /// Reverb slugs and shop labels share the common vocabulary; eBay grades by item state.
+    pub fn from_source(source: Option<&str>, raw: &str) -> Option<Self> {
+        match source.map(str::to_lowercase).as_deref() {
+            Some("ebay") => match raw.trim().to_lowercase().as_str() {
+                "new" | "new other (see details)" | "open box" => Some(Condition::Mint),
+                "used" | "pre-owned" => Some(Condition::VeryGood),
+                "for parts or not working" => Some(Condition::NonFunctioning),
+                _ => Self::parse(raw),
+            },
+            _ => Self::parse(raw),
+        }
+    }
+
+    /// Grade on a 0 (non-functioning) to 6 (mint) scale; Excellent + sits half a grade above Excellent
+    pub fn grade(self) -> f64 {
+        match self {
+            Condition::Mint => 6.0,
+            Condition::ExcellentPlus => 5.5,
+            Condition::Excellent => 5.0,
+            Condition::VeryGood => 4.0,
+            Condition::Good => 3.0,
+            Condition::Fair => 2.0,
+            Condition::Poor => 1.0,
+            Condition::NonFunctioning => 0.0,
+        }
+    }
+
+    /// Color for frontend styling
+    pub fn color(self) -> &'static str {
+        match self {
+            Condition::Mint | Condition::ExcellentPlus | Condition::Excellent => "green",
+            Condition::VeryGood | Condition::Good => "yellow",
+            Condition::Fair | Condition::Poor | Condition::NonFunctioning => "red",
+        }
+    }
+}
+
+/// Listing lifecycle: Draft -> Available -> On Hold/Pending -> Sold/Withdrawn
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, ToSchema)]
+pub enum ListingStatus {
+    Draft,
+    Available,
+    #[serde(rename = "On Hold")]
+    OnHold,
+    Pending,
+    Sold,
+    Withdrawn,
+}
+
+impl ListingStatus {
+    pub const ALL: [ListingStatus; 6] = [
+        ListingStatus::Draft,
+        ListingStatus::Available,
+        ListingStatus::OnHold,
+        ListingStatus::Pending,
+        ListingStatus::Sold,
+        ListingStatus::Withdrawn,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ListingStatus::Draft => "Draft",
+            ListingStatus::Available => "Available",
+            ListingStatus::OnHold => "On Hold",
+            ListingStatus::Pending => "Pending",
+            ListingStatus::Sold => "Sold",
+            ListingStatus::Withdrawn => "Withdrawn",
+        }
+    }
+
+    /// Case-, space- and dash-insensitive parse ("on hold", "On-Hold", "ON_HOLD")
+    pub fn parse(raw: &str) -> Option<Self> {
+        let key: String = raw.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().replace(' ', "").to_lowercase() == key)
+    }
+
+    /// Statuses this one may move to. Sold is final; a withdrawn guitar can be relisted.
+    pub fn next(self) -> &'static [ListingStatus] {
+        use ListingStatus::*;
+        match self {
+            Draft => &[Available, Withdrawn],
+            Available => &[OnHold, Pending, Sold, Withdrawn],
+            OnHold => &[Available, Pending, Sold, Withdrawn],
+            Pending => &[Available, OnHold, Sold, Withdrawn],
+            Sold => &[],
+            Withdrawn => &[Draft, Available],
+        }
+    }
+
+    pub fn can_transition_to(self, next: ListingStatus) -> bool {
+        self.next().contains(&next)
+    }
+
+    /// Color for frontend styling
+    pub fn color(self) -> &'static str {
+        match self {
+            ListingStatus::Available => "success",
+            ListingStatus::Sold => "danger",
+            ListingStatus::OnHold => "warning",
+            ListingStatus::Pending => "info",
+            ListingStatus::Draft | ListingStatus::Withdrawn => "secondary",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({"status": "Sold"}))]
+pub struct StatusTransitionRequest {
+    /// Target status; matched case-insensitively ("on hold" works)
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "guitars:123",
+    "from": "Pending",
+    "to": "Sold",
+    "changed_at": "2026-10-19T14:03:00Z",
+    "allowed_next": []
+}))]
+pub struct StatusTransitionResponse {
+    pub id: String,
+    /// Previous status; None for guitars that never had one
+    pub from: Option<String>,
+    pub to: ListingStatus,
+    pub changed_at: String,
+    pub allowed_next: Vec<ListingStatus>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Original,
+    Replaced,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "section": "hardware",
+    "field": "tuners",
+    "status": "replaced",
+    "reference": "Kluson Deluxe (single-line)",
+    "instance": "Grover Rotomatic"
+}))]
+pub struct ComponentCheck {
+    pub section: String,
+    pub field: String,
+    pub status: ComponentStatus,
+    pub reference: serde_json::Value,
+    pub instance: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "guitar_id": "guitars:123",
+    "reference": {"kind": "guitar", "id": "guitars:1958v", "label": "Gibson 1958 Flying V Original"},
+    "originality_score": 87.5,
+    "original": 7,
+    "replaced": 1,
+    "unknown": 4,
+    "components": [
+        {"section": "hardware", "field": "tuners", "status": "replaced", "reference": "Kluson Deluxe (single-line)", "instance": "Grover Rotomatic"}
+    ]
+}))]
+pub struct OriginalityReport {
+    pub guitar_id: String,
+    pub reference: serde_json::Value,
+    /// Weighted share of assessed components that are original, 0-100; None when nothing could be assessed
+    pub originality_score: Option<f64>,
+    pub original: usize,
+    pub replaced: usize,
+    pub unknown: usize,
+    pub components: Vec<ComponentCheck>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "field": "dimensions.neck_profile",
+    "from": "Thick 1950s rounded C",
+    "to": "1958 rounded C"
+}))]
+pub struct SpecChange {
+    pub field: String,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "guitars:1958v",
+    "kind": "guitar",
+    "label": "Gibson 1958 Flying V Original",
+    "slug": "gibson-1958-flying-v-original",
+    "production_year": 1958,
+    "spec_version_year": 1958
+}))]
+pub struct LineageNode {
+    pub id: String,
+    /// "guitar" or "model"
+    pub kind: String,
+    pub label: String,
+    pub slug: String,
+    pub production_year: Option<i32>,
+    pub spec_version_year: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "relation": "reissue_of",
+    "from": "guitars:2008v",
+    "to": "guitars:1958v",
+    "note": "50th anniversary reissue",
+    "spec_delta": [
+        {"field": "dimensions.neck_profile", "from": "Thick 1950s rounded C", "to": "1958 rounded C"}
+    ]
+}))]
+pub struct LineageEdge {
+    pub relation: String,
+    pub from: String,
+    pub to: String,
+    pub note: Option<String>,
+    /// Spec fields that changed from the `to` node (the older spec) to the `from` node
+    pub spec_delta: Vec<SpecChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LineageResponse {
+    pub root: String,
+    pub nodes: Vec<LineageNode>,
+    pub edges: Vec<LineageEdge>,
+    /// Whether restricted spec fields were left out of the deltas
+    pub paywall_applied: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "from": "guitars:2008v",
+    "to": "guitars:1958v",
+    "relation": "reissue_of",
+    "note": "50th anniversary reissue"
+}))]
+pub struct LineageLinkRequest {
+    /// Guitar or catalog model record id, e.g. `guitars:abc` or `guitar_models:xyz`
+    pub from: String,
+    pub to: String,
+    /// One of `reissue_of`, `successor_of`, `signature_of`
+    pub relation: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "serial": "82345678",
+    "brand": "Gibson",
+    "guitar_id": "guitars:123"
+}))]
+pub struct SerialDecodeRequest {
+    /// Serial to decode; defaults to the guitar's stored serial_number when guitar_id is given
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// Brand whose rules to apply; defaults to the guitar's brand
+    #[serde(default)]
+    pub brand: Option<String>,
+    /// Country of manufacture ("US", "MX", "JP"), needed for some Fender Japan prefixes
+    #[serde(default)]
+    pub country: Option<String>,
+    /// Guitar to check the decoded year against
+    #[serde(default)]
+    pub guitar_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "guitar_id": "guitars:123",
+    "production_year": 1982,
+    "consistent": false,
+    "message": "Serial decodes to 1985 but production_year is 1982"
+}))]
+pub struct SerialConsistency {
+    pub guitar_id: String,
+    pub production_year: Option<i32>,
+    pub consistent: bool,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "serial": "82345678",
+    "brand": "Gibson",
+    "decoded": true,
+    "rule": "gibson_ydddyrrr_1977_2005",
+    "years": [1985],
+    "year": 1985,
+    "factory": "Nashville, Tennessee",
+    "ranking": 678,
+    "day_of_year": 234,
+    "batch": null,
+    "confidence": "high",
+    "notes": ["Ink-stamped YDDDYRRR: digits 1 and 5 are the year, 2-4 the day of the year"],
+    "consistency": null
+}))]
+pub struct SerialDecodeResponse {
+    pub serial: String,
+    pub brand: Option<String>,
+    pub decoded: bool,
+    pub rule: Option<String>,
+    /// Candidate production years
+    pub years: Vec<i32>,
+    /// Production year, when the serial pins it down to one
+    pub year: Option<i32>,
+    pub factory: Option<String>,
+    pub ranking: Option<u32>,
+    pub day_of_year: Option<u32>,
+    pub batch: Option<u32>,
+    /// "high" (one year), "medium" (a few candidates), "low" (a wide range) or "none"
+    pub confidence: String,
+    pub notes: Vec<String>,
+    pub consistency: Option<SerialConsistency>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "price": {"amount_minor": 469900, "currency": "USD", "formatted": "$4,699.00"},
+    "status": "Available",
+    "event": "UPDATE",
+    "changed_at": "2026-09-01T12:00:00Z"
+}))]
+pub struct PriceHistoryEntry {
+    pub price: Option<Money>,
+    pub status: Option<String>,
+    /// CREATE, UPDATE or BACKFILL
+    pub event: String,
+    pub changed_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
+#[schema(example = json!({
+    "last_changed_at": "2026-09-01T12:00:00Z",
+    "previous_price": {"amount_minor": 499900, "currency": "USD", "formatted": "$4,999.00"},
+    "current_price": {"amount_minor": 469900, "currency": "USD", "formatted": "$4,699.00"},
+    "change_minor": -30000,
+    "change_percent": -6.0,
+    "price_drop": true,
+    "days_on_market": 42
+}))]
+pub struct PriceChange {
+    /// When the price last changed; None if it never has
+    pub last_changed_at: Option<String>,
+    pub previous_price: Option<Money>,
+    pub current_price: Option<Money>,
+    /// Difference in minor units; None when the currency changed too
+    pub change_minor: Option<i64>,
+    pub change_percent: Option<f64>,
+    pub price_drop: bool,
+    /// Days since published_at, or until the guitar sold
+    pub days_on_market: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PriceHistoryResponse {
+    pub guitar_id: String,
+    pub slug: String,
+    pub entries: Vec<PriceHistoryEntry>,
+    pub price_change: PriceChange,
+    /// True when entries were cut for the caller's subscription tier
+    #[serde(default)]
+    pub paywall_applied: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "guitars:123",
+    "slug": "gibson-heritage-reissue-flying-v",
+    "display_title": "Gibson Heritage Reissue Flying V",
+    "status": "Sold",
+    "condition_slug": "very-good",
+    "price": {"amount_minor": 999900, "currency": "USD", "formatted": "$9,999.00"},
+    "converted_price": {"amount_minor": 859914, "currency": "EUR", "formatted": "8.599,14 €"},
+    "days_on_market": 41,
+    "sold": true
+}))]
+pub struct MarketComparable {
+    pub id: String,
+    pub slug: String,
+    pub display_title: String,
+    pub status: Option<String>,
+    pub condition_slug: String,
+    /// Listing price, or the last recorded price once the listing stopped showing one
+    pub price: Money,
+    pub converted_price: Money,
+    pub days_on_market: Option<i64>,
+    pub sold: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ConditionBreakdown {
+    pub condition_slug: String,
+    pub count: usize,
+    pub median: Money,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "brand": "Gibson",
+    "model": "Heritage Reissue Flying V",
+    "spec_version_year": null,
+    "currency": "USD",
+    "count": 3,
+    "sold_count": 1,
+    "median": {"amount_minor": 1251413, "currency": "USD", "formatted": "$12,514.13"},
+    "p25": {"amount_minor": 1125657, "currency": "USD", "formatted": "$11,256.57"},
+    "p75": {"amount_minor": 1316413, "currency": "USD", "formatted": "$13,164.13"},
+    "average_days_on_market": 212.3,
+    "conditions": [{"condition_slug": "very-good", "count": 3, "median": {"amount_minor": 1251413, "currency": "USD", "formatted": "$12,514.13"}}],
+    "comparables": []
+}))]
+pub struct MarketStats {
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub spec_version_year: Option<i32>,
+    /// Currency every price below was converted to
+    pub currency: String,
+    pub count: usize,
+    pub sold_count: usize,
+    pub median: Option<Money>,
+    pub p25: Option<Money>,
+    pub p75: Option<Money>,
+    pub average_days_on_market: Option<f64>,
+    pub conditions: Vec<ConditionBreakdown>,
+    pub comparables: Vec<MarketComparable>,
+    /// True when fields were withheld for the caller's subscription tier
+    #[serde(default)]
+    pub paywall_applied: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "brand": "Gibson",
+    "model": "Heritage Reissue Flying V",
+    "year": 1982,
+    "condition": "excellent",
+    "originality": 100.0,
+    "currency": "USD"
+}))]
+pub struct EstimateRequest {
+    pub brand: String,
+    pub model: String,
+    /// Production year
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// Condition slug or label ("very-good", "Excellent +")
+    #[serde(default)]
+    pub condition: Option<String>,
+    /// Share of original components (0-100), as reported by the originality endpoint
+    #[serde(default)]
+    pub originality: Option<f64>,
+    /// Currency of the estimate; defaults to the exchange-rate base
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EstimateComparable {
+    pub id: String,
+    pub slug: String,
+    pub display_title: String,
+    pub production_year: Option<i32>,
+    pub condition_slug: String,
+    pub price: Money,
+    /// Price after condition, age and originality adjustments towards the described guitar
+    pub adjusted_price: Money,
+    /// Lower is closer; combines model, year and condition differences
+    pub distance: f64,
+    /// Share of the estimate contributed by this comparable (0-1)
+    pub weight: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "currency": "USD",
+    "low": {"amount_minor": 1040000, "currency": "USD", "formatted": "$10,400.00"},
+    "estimate": {"amount_minor": 1150000, "currency": "USD", "formatted": "$11,500.00"},
+    "high": {"amount_minor": 1260000, "currency": "USD", "formatted": "$12,600.00"},
+    "confidence": "medium",
+    "comparables": [],
+    "notes": ["only 2 comparable listing(s) found"]
+}))]
+pub struct EstimateResponse {
+    pub currency: String,
+    pub low: Money,
+    pub estimate: Money,
+    pub high: Money,
+    /// "high", "medium" or "low", from the number and closeness of comparables
+    pub confidence: String,
+    pub comparables: Vec<EstimateComparable>,
+    pub notes: Vec<String>,
+}
+
+/// Access level of an API key or editor session; each role includes the ones below it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+/// Subscription tier of a user or API key; premium spec data is limited below `subscriber`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    #[default]
+    Free,
+    Subscriber,
+}
+
+/// Who is calling, as established from an API key or session token
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Identity {
+    /// Username for sessions, key name for API keys
+    pub subject: String,
+    pub role: Role,
+    pub tier: Tier,
+    /// "session" or "api_key"
+    pub method: String,
+}
+
+/// An editor account that can log in for a session token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct User {
+    pub id: Option<Thing>,
+    pub username: String,
+    /// Argon2 PHC string
+    pub password_hash: String,
+    pub role: Role,
+    #[serde(default)]
+    pub tier: Tier,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({"username": "alex", "role": "editor", "password": "correct horse battery staple"}))]
+pub struct UserCreateRequest {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+    /// Defaults to free
+    #[serde(default)]
+    pub tier: Tier,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({"tier": "subscriber"}))]
+pub struct TierUpdateRequest {
+    pub tier: Tier,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({"username": "alex", "password": "correct horse battery staple"}))]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LoginResponse {
+    /// Signed JWT; send as `Authorization: Bearer <token>`
+    pub token: String,
+    pub token_type: String,
+    pub expires_at: String,
+    pub username: String,
+    pub role: Role,
+    pub tier: Tier,
+}
+
+/// A service API key; only its SHA-256 hash is stored
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ApiKey {
+    pub id: Option<Thing>,
+    pub name: String,
+    pub role: Role,
+    #[serde(default)]
+    pub tier: Tier,
+    /// First characters of the key, to tell keys apart
+    pub prefix: String,
+    #[serde(default)]
+    pub revoked: bool,
+    #[serde(default)]
+    pub created_at: Option<Datetime>,
+    /// Updated at most once a minute
+    #[serde(default)]
+    pub last_used_at: Option<Datetime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({"name": "importer", "role": "editor"}))]
+pub struct ApiKeyCreateRequest {
+    pub name: String,
+    pub role: Role,
+    /// Defaults to free
+    #[serde(default)]
+    pub tier: Tier,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ApiKeyCreated {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    pub tier: Tier,
+    pub prefix: String,
+    /// The key itself; shown only once, send it as `X-API-Key`
+    pub key: String,
+}
+
+/// Last check of one listing or image URL of a guitar
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LinkCheck {
+    pub id: Option<Thing>,
+    pub guitar_id: Option<Thing>,
+    pub url: String,
+    /// "listing", "hero_image" or "gallery_image"
+    pub kind: String,
+    /// HTTP status; None when the request failed (DNS, timeout, TLS)
+    #[serde(default)]
+    pub status_code: Option<u16>,
+    #[serde(default)]
+    pub ok: bool,
+    /// The listing page is gone (404/410) or shows a sold marker
+    #[serde(default)]
+    pub probably_sold: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub checked_at: Option<Datetime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
+pub struct LinkHealthSummary {
+    pub checked: usize,
+    pub ok: usize,
+    pub broken: usize,
+    pub probably_sold: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LinkReportEntry {
+    pub guitar_id: String,
+    pub slug: String,
+    pub display_title: String,
+    pub url: String,
+    pub kind: String,
+    pub status_code: Option<u16>,
+    pub ok: bool,
+    pub probably_sold: bool,
+    pub error: Option<String>,
+    pub checked_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "summary": {"checked": 3, "ok": 1, "broken": 1, "probably_sold": 1},
+    "last_checked_at": "2026-10-19T06:00:00Z",
+    "probably_sold": ["gibson-flying-v-1958"],
+    "links": [{
+        "guitar_id": "guitars:abc",
+        "slug": "gibson-flying-v-1958",
+        "display_title": "1958 Gibson Flying V",
+        "url": "https://dealer.example/flying-v",
+        "kind": "listing",
+        "status_code": 404,
+        "ok": false,
+        "probably_sold": true,
+        "error": null,
+        "checked_at": "2026-10-19T06:00:00Z"
+    }]
+}))]
+pub struct LinkHealthReport {
+    /// Counts over every recorded check, before filtering
+    pub summary: LinkHealthSummary,
+    pub last_checked_at: Option<String>,
+    /// Slugs of guitars currently flagged as probably sold
+    pub probably_sold: Vec<String>,
+    pub links: Vec<LinkReportEntry>,
+}
+
+/// Token-bucket budget: `burst` requests at once, refilled at `per_minute`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, ToSchema)]
+pub struct RateBudget {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+/// Requests seen from one client (API key, session user or IP) since the server started
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ClientUsage {
+    /// "api_key:<name>", "user:<username>" or "ip:<address>"
+    pub client: String,
+    pub requests: u64,
+    /// Requests to endpoints with the expensive budget
+    pub expensive_requests: u64,
+    /// Requests rejected with 429
+    pub throttled: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RateLimitReport {
+    pub enabled: bool,
+    pub default_budget: RateBudget,
+    pub expensive_budget: RateBudget,
+    pub expensive_paths: Vec<String>,
+    /// Busiest clients first
+    pub clients: Vec<ClientUsage>,
+}
+
+/// One field changed by an audited write
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AuditChange {
+    pub field: String,
+    /// Value before the write (null for creates)
+    pub before: serde_json::Value,
+    /// Value after the write (null for deletes)
+    pub after: serde_json::Value,
+}
+
+/// A create, update or delete of a guitar, catalog model or guitar_* detail row
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "audit_log:k3v9x0",
+    "table": "guitars",
+    "record_id": "guitars:abc",
+    "guitar_id": "guitars:abc",
+    "event": "UPDATE",
+    "actor": "user:alice",
+    "source": "POST /api/guitars/{id}/status",
+    "at": "2026-10-19T09:30:00Z",
+    "changes": [
+        {"field": "status", "before": "Available", "after": "Sold"},
+        {"field": "sold_at", "before": null, "after": "2026-10-19T09:30:00Z"}
+    ]
+}))]
+pub struct AuditEntry {
+    pub id: String,
+    pub table: String,
+    pub record_id: String,
+    /// The guitar the row belongs to (none for catalog models)
+    pub guitar_id: Option<String>,
+    /// CREATE, UPDATE or DELETE
+    pub event: String,
+    /// "user:<username>", "api_key:<name>", "system", or "database" for writes made outside the API
+    pub actor: String,
+    /// Route or job that made the write; "surql" for scripts that don't set `$audit`
+    pub source: String,
+    pub at: String,
+    pub changes: Vec<AuditChange>,
+}
+
+/// A stored version of a guitar and its spec rows
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "version": 4,
+    "deleted": false,
+    "actor": "user:alice",
+    "source": "POST /api/guitars/{id}/status",
+    "created_at": "2026-10-19T09:30:00Z"
+}))]
+pub struct GuitarVersionSummary {
+    pub version: i64,
+    /// The guitar had been deleted in this version
+    #[serde(default)]
+    pub deleted: bool,
+    pub actor: String,
+    pub source: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "guitar_id": "guitars:abc",
+    "from": 3,
+    "to": 4,
+    "changes": [
+        {"field": "price", "before": 8500, "after": 7900},
+        {"field": "dimensions.nut_width", "before": "1 11/16\"", "after": "1 3/4\""}
+    ]
+}))]
+pub struct VersionDiff {
+    pub guitar_id: String,
+    pub from: i64,
+    pub to: i64,
+    /// Guitar fields by name, single-row spec fields as `section.field`, lists as a whole
+    pub changes: Vec<AuditChange>,
+}
+
+/// A guitar in the trash
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "guitars:abc",
+    "slug": "gibson-flying-v-1958",
+    "display_title": "Gibson Flying V",
+    "deleted_at": "2026-10-19T09:30:00Z"
+}))]
+pub struct TrashedGuitar {
+    pub id: String,
+    pub slug: Option<String>,
+    pub display_title: String,
+    pub deleted_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "guitar_id": "guitars:abc",
+    "removed": {"guitar_dimensions": 1, "guitar_pickups": 2, "images": 4, "price_history": 3}
+}))]
+pub struct PurgeResponse {
+    pub guitar_id: String,
+    /// Rows removed from each child table
+    pub removed: std::collections::BTreeMap<String, usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RevertResponse {
+    pub guitar_id: String,
+    pub reverted_to: i64,
+    /// The version the revert was recorded as
+    pub version: GuitarVersionSummary,
+}
+
+/// What an image is used for on the guitar page
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageRole {
+    Hero,
+    #[default]
+    Gallery,
+    Detail,
+}
+
+impl ImageRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImageRole::Hero => "hero",
+            ImageRole::Gallery => "gallery",
+            ImageRole::Detail => "detail",
         }
     }
 }
@@ -157,10 +1272,124 @@ pub struct Image {
     pub id: Option<Thing>, // Surreal record id, e.g. images:abc...
     pub src: String,
     pub alt: Option<String>,
-    pub w: i32,
-    pub h: i32,
     #[serde(default)]
-    pub guitar_id: Option<String>, // Reference to guitar if applicable
+    pub w: Option<i32>,
+    #[serde(default)]
+    pub h: Option<i32>,
+    #[serde(default)]
+    pub guitar_id: Option<Thing>, // Guitar the image belongs to
+    /// Display order within the guitar, starting at 0
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default)]
+    pub role: ImageRole,
+    /// Where the image came from ("scraped", "upload", a dealer name, ...)
+    #[serde(default)]
+    pub source: Option<String>,
+    /// 64-bit perceptual hash (hex) of ingested images, used to reject duplicates
+    #[serde(default)]
+    pub phash: Option<String>,
+    /// Storage prefix of the original and its thumbnails, for images stored by the API
+    #[serde(default)]
+    pub storage_key: Option<String>,
+    /// Resized renditions of ingested images, smallest first
+    #[serde(default)]
+    pub variants: Vec<ImageVariant>,
+}
+
+/// A resized rendition (thumbnail) of an ingested image
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ImageVariant {
+    pub src: String,
+    pub width: i32,
+    pub height: i32,
+    /// "webp" or "avif"
+    pub format: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "src": "https://example.com/headstock.jpg",
+    "alt": "Headstock front",
+    "w": 1600,
+    "h": 2400,
+    "role": "detail",
+    "source": "scraped"
+}))]
+pub struct ImageCreateRequest {
+    pub src: String,
+    #[serde(default)]
+    pub alt: Option<String>,
+    #[serde(default)]
+    pub w: Option<i32>,
+    #[serde(default)]
+    pub h: Option<i32>,
+    /// Defaults to gallery; a new hero demotes the previous one to gallery
+    #[serde(default)]
+    pub role: Option<ImageRole>,
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Insert at this position; appended when omitted
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "url": "https://example.com/uploads/back.jpg",
+    "alt": "Back of the body",
+    "role": "gallery",
+    "source": "dealer"
+}))]
+pub struct ImageIngestRequest {
+    /// Image to download; stored by the API with EXIF removed and thumbnails generated
+    pub url: String,
+    #[serde(default)]
+    pub alt: Option<String>,
+    #[serde(default)]
+    pub role: Option<ImageRole>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+/// multipart/form-data body of an image upload (documentation only)
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ImageUploadForm {
+    /// One or more image files (JPEG, PNG, WebP or GIF); repeat the field for several files
+    #[schema(value_type = Vec<String>, format = Binary)]
+    pub file: Vec<String>,
+    /// Alt text applied to every uploaded file
+    pub alt: Option<String>,
+    /// Role of the first file ("hero", "gallery", "detail"); later files are gallery images
+    pub role: Option<ImageRole>,
+    /// Defaults to "upload"
+    pub source: Option<String>,
+}
+
+/// An uploaded file that was not stored
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct UploadRejection {
+    pub filename: Option<String>,
+    pub error: String,
+    /// The already stored image this file duplicates
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ImageUploadResponse {
+    /// Created image records, in upload order
+    pub images: Vec<Image>,
+    /// Files skipped as duplicates or unreadable images
+    pub rejected: Vec<UploadRejection>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({"image_ids": ["images:b2", "images:a1", "images:c3"]}))]
+pub struct ImageReorderRequest {
+    /// Every image of the guitar, in the new display order
+    pub image_ids: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -171,15 +1400,15 @@ pub struct Image {
         "https://example.com/image2.jpg"
     ],
     "image_source": "scraped",
-    "condition": "Excellent +",
-    "status": "Available"
+    "condition": "Excellent +"
 }))]
 pub struct ImageUpdateRequest {
+    /// Replaces the hero image only; null removes it
     pub hero_image_url: Option<String>,
+    /// Replaces the gallery images only; the hero and detail images are kept
     pub image_gallery: Option<Vec<String>>,
     pub image_source: Option<String>,
     pub condition: Option<String>,
-    pub status: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -191,3 +1420,113 @@ pub struct ErrorResponse {
     pub error: String,
     pub status: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "dimension": "scale_length",
+    "weight": 2.0,
+    "score": 1.0,
+    "compared": true,
+    "reason": "Same scale length: 24.75\""
+}))]
+pub struct SimilarityDimension {
+    pub dimension: String,
+    pub weight: f64,
+    pub score: f64,
+    /// False when either guitar has no data for this dimension
+    pub compared: bool,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "guitars:456",
+    "slug": "gibson-1958-flying-v-reissue-2008",
+    "display_title": "Gibson 1958 Flying V Reissue",
+    "brand": "Gibson",
+    "model": "1958 Flying V Reissue",
+    "formatted_price": "$8,500.00",
+    "main_image": "https://example.com/hero.jpg",
+    "score": 0.87,
+    "explanation": [
+        {"dimension": "body_style", "weight": 3.0, "score": 1.0, "compared": true, "reason": "Same body style: v"}
+    ]
+}))]
+pub struct SimilarGuitar {
+    pub id: Option<String>,
+    pub slug: String,
+    pub display_title: String,
+    pub brand: String,
+    pub model: String,
+    pub formatted_price: String,
+    pub main_image: Option<String>,
+    pub score: f64,
+    pub explanation: Vec<SimilarityDimension>,
+}
+
+/// One guitar of a candidate duplicate pair
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DuplicateGuitar {
+    pub id: String,
+    pub slug: String,
+    pub display_title: String,
+    pub shop_slug: Option<String>,
+    pub listing_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "score": 0.94,
+    "first": {"id": "guitars:abc", "slug": "gibson-flying-v-1958", "display_title": "Gibson Flying V", "shop_slug": "carter-vintage", "listing_url": "https://cartervintage.com/flying-v"},
+    "second": {"id": "guitars:def", "slug": "gibson-flying-v-1958-2", "display_title": "Gibson Flying V", "shop_slug": "gruhn", "listing_url": "https://guitars.com/flying-v"},
+    "signals": [
+        {"dimension": "serial_number", "weight": 4.0, "score": 1.0, "compared": true, "reason": "Same serial number: 81234"},
+        {"dimension": "listing_url", "weight": 3.0, "score": 0.0, "compared": false, "reason": "Different listing URLs"}
+    ]
+}))]
+pub struct DuplicatePair {
+    /// Weighted score of the compared signals, 0.0..=1.0
+    pub score: f64,
+    pub first: DuplicateGuitar,
+    pub second: DuplicateGuitar,
+    pub signals: Vec<SimilarityDimension>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "keep": "guitars:abc",
+    "merge": "guitars:def",
+    "take_from_merged": ["price_cents", "dimensions.nut_width", "pickups"]
+}))]
+pub struct MergeRequest {
+    /// The guitar that stays
+    pub keep: String,
+    /// The guitar merged into it and removed
+    pub merge: String,
+    /// Fields to take from the merged guitar even where the kept one has a value: guitar fields,
+    /// `section.field` of one-row spec sections, or a multi-row section (`pickups`) to take its rows
+    #[serde(default)]
+    pub take_from_merged: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "guitar_id": "guitars:abc",
+    "merged_id": "guitars:def",
+    "redirect_slug": "gibson-flying-v-1958-2",
+    "fields_from_merged": ["serial_number", "dimensions.nut_width"],
+    "moved": {"guitar_case": 1, "images": 3, "price_history": 2},
+    "discarded": {"guitar_pickups": 2, "images": 1, "link_checks": 2}
+}))]
+pub struct MergeResponse {
+    pub guitar_id: String,
+    pub merged_id: String,
+    /// Old slug of the merged guitar, now redirecting to the kept one
+    pub redirect_slug: Option<String>,
+    /// Fields copied from the merged guitar
+    pub fields_from_merged: Vec<String>,
+    /// Rows re-pointed to the kept guitar, per table
+    pub moved: std::collections::BTreeMap<String, usize>,
+    /// Rows dropped in favour of the other guitar's, per table
+    pub discarded: std::collections::BTreeMap<String, usize>,
+}
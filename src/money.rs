@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// An amount in minor units (cents, or whole yen for JPY) of an ISO 4217 currency
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+#[schema(example = json!({
+    "amount_minor": 469900,
+    "currency": "USD",
+    "formatted": "$4,699.00"
+}))]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: String,
+    /// Display string in the currency's conventional locale
+    #[serde(default, skip_deserializing)]
+    pub formatted: String,
+}
+
+/// Currency used when a listing doesn't record one
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// Number formatting conventions of a locale
+struct LocaleFormat {
+    thousands: &'static str,
+    decimal: &'static str,
+    symbol_first: bool,
+    symbol_space: bool,
+}
+
+fn locale_format(locale: &str) -> LocaleFormat {
+    let language = locale.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    let region = locale.split(['-', '_']).nth(1).unwrap_or_default().to_uppercase();
+    match (language.as_str(), region.as_str()) {
+        ("de", "CH") => LocaleFormat { thousands: "'", decimal: ".", symbol_first: true, symbol_space: true },
+        ("de" | "nl" | "it" | "es" | "pt", _) => LocaleFormat { thousands: ".", decimal: ",", symbol_first: false, symbol_space: true },
+        ("fr" | "sv" | "nb" | "da" | "fi", _) => LocaleFormat { thousands: "\u{202f}", decimal: ",", symbol_first: false, symbol_space: true },
+        _ => LocaleFormat { thousands: ",", decimal: ".", symbol_first: true, symbol_space: false },
+    }
+}
+
+/// Number of minor-unit digits of an ISO 4217 currency
+pub fn minor_digits(currency: &str) -> u32 {
+    match currency {
+        "JPY" | "KRW" | "ISK" | "HUF" => 0,
+        _ => 2,
+    }
+}
+
+fn symbol(currency: &str) -> &str {
+    match currency {
+        "USD" => "$",
+        "EUR" => "€",
+        "GBP" => "£",
+        "JPY" => "¥",
+        "CAD" => "CA$",
+        "AUD" => "A$",
+        "SEK" | "NOK" | "DKK" => "kr",
+        other => other,
+    }
+}
+
+/// Locale a currency is formatted in
+fn default_locale(currency: &str) -> &'static str {
+    match currency {
+        "EUR" => "de-DE",
+        "GBP" => "en-GB",
+        "JPY" => "ja-JP",
+        "CHF" => "de-CH",
+        "SEK" => "sv-SE",
+        _ => "en-US",
+    }
+}
+
+fn group_thousands(digits: &str, separator: &str) -> String {
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: &str) -> Self {
+        let currency = normalize_currency(currency);
+        let mut money = Self {
+            amount_minor,
+            currency,
+            formatted: String::new(),
+        };
+        money.formatted = money.format();
+        money
+    }
+
+    /// Format with the separators and symbol placement of the currency's conventional locale,
+    /// e.g. `$4,699.00` or `4.699,00 €`
+    pub fn format(&self) -> String {
+        let conventions = locale_format(default_locale(&self.currency));
+        let digits = minor_digits(&self.currency);
+        let divisor = 10_i64.pow(digits);
+        let major = (self.amount_minor / divisor).abs();
+        let minor = (self.amount_minor % divisor).abs();
+
+        let mut number = group_thousands(&major.to_string(), conventions.thousands);
+        if digits > 0 {
+            number.push_str(conventions.decimal);
+            number.push_str(&format!("{minor:0width$}", width = digits as usize));
+        }
+
+        let symbol = symbol(&self.currency);
+        let space = if conventions.symbol_space || (symbol.len() > 1 && symbol.chars().all(|c| c.is_ascii_uppercase())) {
+            " "
+        } else {
+            ""
+        };
+        let sign = if self.amount_minor < 0 { "-" } else { "" };
+        if conventions.symbol_first {
+            format!("{sign}{symbol}{space}{number}")
+        } else {
+            format!("{sign}{number}{space}{symbol}")
+        }
+    }
+}
+
+/// Uppercase a currency code, falling back to the default currency when empty
+pub fn normalize_currency(code: &str) -> String {
+    let code = code.trim().to_uppercase();
+    if code.is_empty() {
+        DEFAULT_CURRENCY.to_string()
+    } else {
+        code
+    }
+}
+
+/// Locally maintained exchange rates, expressed as units of each currency per one `base`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExchangeRates {
+    pub base: String,
+    #[serde(default)]
+    pub as_of: Option<String>,
+    pub rates: HashMap<String, f64>,
+}
+
+/// Rates shipped with the API, used unless `EXCHANGE_RATES_PATH` points elsewhere
+const BUNDLED_RATES: &str = include_str!("../data/exchange_rates.json");
+
+impl Default for ExchangeRates {
+    fn default() -> Self {
+        serde_json::from_str(BUNDLED_RATES).expect("bundled exchange rates are valid JSON")
+    }
+}
+
+impl ExchangeRates {
+    /// Load rates from a JSON file with the same shape as `data/exchange_rates.json`
+    pub fn from_file(path: &str) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn rate(&self, currency: &str) -> Option<f64> {
+        if currency == self.base {
+            Some(1.0)
+        } else {
+            self.rates.get(currency).copied().filter(|r| *r > 0.0)
+        }
+    }
+
+    pub fn supports(&self, currency: &str) -> bool {
+        self.rate(&normalize_currency(currency)).is_some()
+    }
+
+    /// Convert an amount into `currency`; None if either currency has no rate
+    pub fn convert(&self, money: &Money, currency: &str) -> Option<Money> {
+        let target = normalize_currency(currency);
+        if money.currency == target {
+            return Some(money.clone());
+        }
+        let from_rate = self.rate(&money.currency)?;
+        let to_rate = self.rate(&target)?;
+        let major = money.amount_minor as f64 / 10_f64.powi(minor_digits(&money.currency) as i32);
+        let converted = major / from_rate * to_rate;
+        let minor = (converted * 10_f64.powi(minor_digits(&target) as i32)).round() as i64;
+        Some(Money::new(minor, &target))
+    }
+}
@@ -0,0 +1,133 @@
+use serde_json::{Map, Value};
+
+use crate::models::{ComponentCheck, ComponentStatus};
+
+/// Single-row sections checked for originality, with the fields compared and their weight
+const CHECKED_SECTIONS: [(&str, &[&str], f64); 2] = [
+    ("hardware", &["bridge", "tailpiece", "tuners", "pickguard", "pickup_rings", "material"], 2.0),
+    ("finish", &["color", "type", "aging"], 3.0),
+];
+
+const PICKUP_FIELDS: [&str; 3] = ["type", "model", "winding"];
+const PICKUP_WEIGHT: f64 = 3.0;
+const CONTROL_WEIGHT: f64 = 1.0;
+
+fn text(row: Option<&Value>, field: &str) -> Option<String> {
+    row.and_then(|r| r.get(field))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Compare loosely: case, punctuation and spacing differences don't count as a replacement
+fn same_part(a: &str, b: &str) -> bool {
+    let canonical = |s: &str| -> String {
+        s.to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect()
+    };
+    canonical(a) == canonical(b)
+}
+
+fn check(section: &str, field: String, reference: Option<String>, instance: Option<String>) -> ComponentCheck {
+    let status = match (&reference, &instance) {
+        (Some(r), Some(i)) if same_part(r, i) => ComponentStatus::Original,
+        (Some(_), Some(_)) => ComponentStatus::Replaced,
+        _ => ComponentStatus::Unknown,
+    };
+    ComponentCheck {
+        section: section.to_string(),
+        field,
+        status,
+        reference: reference.map(Value::String).unwrap_or(Value::Null),
+        instance: instance.map(Value::String).unwrap_or(Value::Null),
+    }
+}
+
+/// Find a row in a multi-row section by a key field such as pickup position or control name
+fn find_row<'a>(rows: Option<&'a Value>, key_field: &str, key: &str) -> Option<&'a Value> {
+    rows.and_then(Value::as_array)?
+        .iter()
+        .find(|row| text(Some(row), key_field).is_some_and(|k| same_part(&k, key)))
+}
+
+/// Check hardware, finish, pickups and controls of an instance against a reference spec.
+///
+/// Both maps are keyed like the guitar detail response. A field is "unknown" when
+/// either side doesn't record it, since listings often leave parts unmentioned.
+pub fn assess(instance: &Map<String, Value>, reference: &Map<String, Value>) -> Vec<ComponentCheck> {
+    let mut checks = Vec::new();
+
+    for (section, fields, _) in CHECKED_SECTIONS {
+        for field in fields {
+            checks.push(check(
+                section,
+                field.to_string(),
+                text(reference.get(section), field),
+                text(instance.get(section), field),
+            ));
+        }
+    }
+
+    // Pickups are matched by position so a swapped bridge pickup is reported on its own
+    let reference_pickups = reference.get("pickups").and_then(Value::as_array);
+    for pickup in reference_pickups.into_iter().flatten() {
+        let position = text(Some(pickup), "position").unwrap_or_else(|| "unknown".to_string());
+        let installed = find_row(instance.get("pickups"), "position", &position);
+        for field in PICKUP_FIELDS {
+            if text(Some(pickup), field).is_none() {
+                continue;
+            }
+            checks.push(check(
+                "pickups",
+                format!("{}.{field}", position.to_lowercase()),
+                text(Some(pickup), field),
+                text(installed, field),
+            ));
+        }
+    }
+
+    let reference_controls = reference.get("controls").and_then(Value::as_array);
+    for control in reference_controls.into_iter().flatten() {
+        let Some(name) = text(Some(control), "name") else {
+            continue;
+        };
+        let installed = find_row(instance.get("controls"), "name", &name);
+        checks.push(check(
+            "controls",
+            name.to_lowercase(),
+            text(Some(control), "detail").or_else(|| Some(name.clone())),
+            installed.and_then(|row| text(Some(row), "detail").or_else(|| text(Some(row), "name"))),
+        ));
+    }
+
+    checks
+}
+
+fn section_weight(section: &str) -> f64 {
+    match section {
+        "pickups" => PICKUP_WEIGHT,
+        "controls" => CONTROL_WEIGHT,
+        other => CHECKED_SECTIONS
+            .iter()
+            .find(|(name, _, _)| *name == other)
+            .map_or(1.0, |(_, _, weight)| *weight),
+    }
+}
+
+/// Weighted share of assessed components that are original, as a 0-100 score.
+///
+/// Unknown components are ignored; returns None when nothing could be assessed.
+pub fn originality_score(checks: &[ComponentCheck]) -> Option<f64> {
+    let (original, assessed) = checks
+        .iter()
+        .filter(|c| c.status != ComponentStatus::Unknown)
+        .fold((0.0, 0.0), |(original, assessed), c| {
+            let weight = section_weight(&c.section);
+            let original = if c.status == ComponentStatus::Original { original + weight } else { original };
+            (original, assessed + weight)
+        });
+    (assessed > 0.0).then(|| (original / assessed * 1000.0).round() / 10.0)
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::{Identity, Role, Tier};
+
+/// Responses a policy can restrict, by their key in the policy file
+pub const GUITAR_DETAIL: &str = "guitar_detail";
+/// Applied to each item of the guitar list
+pub const GUITAR_LIST: &str = "guitar_list";
+/// Applied to each catalog model, in the model list and detail and in lineage spec deltas
+pub const GUITAR_MODEL: &str = "guitar_model";
+pub const PRICE_HISTORY: &str = "price_history";
+pub const MARKET_STATS: &str = "market_stats";
+
+/// How one field is limited for callers below `min_tier`.
+///
+/// With `keep` an object is cut down to those keys; with `limit` an array keeps that many
+/// items (the last ones when `from_end`) and a string that many characters. Otherwise the
+/// field is set to null.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldRule {
+    /// Dotted path into the response; `*` steps into every item of an array
+    pub path: String,
+    #[serde(default = "subscriber")]
+    pub min_tier: Tier,
+    #[serde(default)]
+    pub keep: Vec<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub from_end: bool,
+}
+
+fn subscriber() -> Tier {
+    Tier::Subscriber
+}
+
+/// Field-level visibility rules per response, loaded from `data/paywall_policy.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaywallPolicy {
+    pub responses: HashMap<String, Vec<FieldRule>>,
+}
+
+/// Policy shipped with the API, used unless `PAYWALL_POLICY_PATH` points elsewhere
+const BUNDLED_POLICY: &str = include_str!("../data/paywall_policy.json");
+
+impl Default for PaywallPolicy {
+    fn default() -> Self {
+        serde_json::from_str(BUNDLED_POLICY).expect("bundled paywall policy is valid JSON")
+    }
+}
+
+/// Tier the policy is applied for: anonymous callers are free, editors and admins see everything
+pub fn tier_of(identity: Option<&Identity>) -> Tier {
+    match identity {
+        Some(identity) if identity.role >= Role::Editor => Tier::Subscriber,
+        Some(identity) => identity.tier,
+        None => Tier::Free,
+    }
+}
+
+enum Outcome {
+    Unchanged,
+    Changed,
+    Cleared,
+}
+
+fn limit_field(target: &mut Value, rule: &FieldRule) -> Outcome {
+    match (target, rule.limit) {
+        (Value::Object(fields), _) if !rule.keep.is_empty() => {
+            let before = fields.len();
+            fields.retain(|key, _| rule.keep.contains(key));
+            if fields.len() < before { Outcome::Changed } else { Outcome::Unchanged }
+        }
+        (Value::Array(items), Some(limit)) => {
+            if items.len() <= limit {
+                return Outcome::Unchanged;
+            }
+            if rule.from_end {
+                items.drain(..items.len() - limit);
+            } else {
+                items.truncate(limit);
+            }
+            Outcome::Changed
+        }
+        (Value::String(text), Some(limit)) => match text.char_indices().nth(limit) {
+            Some((end, _)) => {
+                text.truncate(end);
+                text.push('…');
+                Outcome::Changed
+            }
+            None => Outcome::Unchanged,
+        },
+        _ => Outcome::Cleared,
+    }
+}
+
+fn restrict(value: &mut Value, path: &[&str], rule: &FieldRule) -> bool {
+    let Some((segment, rest)) = path.split_first() else {
+        return false;
+    };
+    if *segment == "*" {
+        let Value::Array(items) = value else {
+            return false;
+        };
+        return items.iter_mut().fold(false, |applied, item| restrict(item, rest, rule) || applied);
+    }
+    let Value::Object(map) = value else {
+        return false;
+    };
+    let Some(child) = map.get_mut(*segment).filter(|child| !child.is_null()) else {
+        return false;
+    };
+    if !rest.is_empty() {
+        return restrict(child, rest, rule);
+    }
+    match limit_field(child, rule) {
+        Outcome::Unchanged => false,
+        Outcome::Changed => true,
+        Outcome::Cleared => {
+            *child = Value::Null;
+            true
+        }
+    }
+}
+
+impl PaywallPolicy {
+    /// Load a policy from a JSON file with the same shape as `data/paywall_policy.json`
+    pub fn from_file(path: &str) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Redact or truncate the fields of `value` (a `response` document) that `tier` may not see,
+    /// and set its top-level `paywall_applied` flag. Returns whether anything was withheld.
+    pub fn apply_paywall_restrictions(&self, response: &str, tier: Tier, value: &mut Value) -> bool {
+        let applied = self
+            .responses
+            .get(response)
+            .into_iter()
+            .flatten()
+            .filter(|rule| tier < rule.min_tier)
+            .fold(false, |applied, rule| {
+                let path: Vec<&str> = rule.path.split('.').collect();
+                restrict(value, &path, rule) || applied
+            });
+        if let Value::Object(map) = value {
+            map.insert("paywall_applied".to_string(), Value::Bool(applied));
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn price_change() -> Value {
+        json!({"previous_price": 550_000, "percent": -9.1, "price_drop": true, "days_on_market": 41})
+    }
+
+    #[test]
+    fn free_tier_gets_no_price_change() {
+        let policy = PaywallPolicy::default();
+        for response in [GUITAR_DETAIL, GUITAR_LIST, PRICE_HISTORY] {
+            let mut document = json!({"slug": "banker-58-spec-v", "price_change": price_change(), "entries": []});
+            assert!(policy.apply_paywall_restrictions(response, Tier::Free, &mut document), "{response}");
+            assert!(document["price_change"].is_null(), "{response}");
+            assert_eq!(document["paywall_applied"], json!(true), "{response}");
+
+            let mut document = json!({"slug": "banker-58-spec-v", "price_change": price_change(), "entries": []});
+            assert!(!policy.apply_paywall_restrictions(response, Tier::Subscriber, &mut document), "{response}");
+            assert_eq!(document["price_change"], price_change(), "{response}");
+        }
+    }
+
+    #[test]
+    fn free_tier_gets_basic_model_dimensions() {
+        let mut model = json!({
+            "brand": "Gibson",
+            "specs": {"dimensions": {"scale_length": "24.75\"", "nut_width": "1.687\"", "neck_depth_1st": "0.88\""}}
+        });
+        assert!(PaywallPolicy::default().apply_paywall_restrictions(GUITAR_MODEL, Tier::Free, &mut model));
+        assert_eq!(model["specs"]["dimensions"], json!({"scale_length": "24.75\"", "nut_width": "1.687\""}));
+    }
+}
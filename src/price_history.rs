@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::Surreal;
+
+use crate::models::{Guitar, PriceChange, PriceHistoryEntry};
+use crate::money::Money;
+
+/// One `price_history` row as loaded for a guitar
+#[derive(Deserialize, Debug, Clone)]
+pub struct PricePoint {
+    pub guitar_key: String,
+    #[serde(default)]
+    pub price_cents: Option<i64>,
+    #[serde(default)]
+    pub price_currency: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    pub event: String,
+    pub changed_at: String,
+}
+
+impl PricePoint {
+    pub fn price(&self) -> Option<Money> {
+        self.price_cents
+            .filter(|cents| *cents > 0)
+            .map(|cents| Money::new(cents, self.price_currency.as_deref().unwrap_or_default()))
+    }
+
+    pub fn changed_at(&self) -> Option<DateTime<Utc>> {
+        parse_datetime(&self.changed_at)
+    }
+
+    pub fn to_entry(&self) -> PriceHistoryEntry {
+        PriceHistoryEntry {
+            price: self.price(),
+            status: self.status.clone(),
+            event: self.event.clone(),
+            changed_at: self.changed_at.clone(),
+        }
+    }
+}
+
+fn parse_datetime(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw.trim_start_matches("d'").trim_end_matches('\''))
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Price history and publish dates, keyed by the id part of the guitar record
+#[derive(Debug, Default)]
+pub struct MarketTimeline {
+    pub history: HashMap<String, Vec<PricePoint>>,
+    pub published_at: HashMap<String, DateTime<Utc>>,
+}
+
+impl MarketTimeline {
+    /// Load the timeline of one guitar (`Some(key)`) or of every guitar (`None`)
+    pub async fn load(
+        db: &Surreal<surrealdb::engine::remote::http::Client>,
+        guitar_key: Option<&str>,
+    ) -> surrealdb::Result<Self> {
+        let (history_filter, guitar_filter) = match guitar_key {
+            Some(_) => (
+                "WHERE guitar_id = type::thing('guitars', $key)",
+                "WHERE id = type::thing('guitars', $key) AND published_at != NONE",
+            ),
+            None => ("", "WHERE published_at != NONE"),
+        };
+        Self::query(db, history_filter, guitar_filter, ("key", guitar_key.unwrap_or_default().to_string())).await
+    }
+
+    /// Load the timelines of the given guitars only
+    pub async fn load_for(
+        db: &Surreal<surrealdb::engine::remote::http::Client>,
+        guitar_keys: &[String],
+    ) -> surrealdb::Result<Self> {
+        if guitar_keys.is_empty() {
+            return Ok(Self::default());
+        }
+        Self::query(
+            db,
+            "WHERE guitar_id IN array::map($keys, |$key| type::thing('guitars', $key))",
+            "WHERE id IN array::map($keys, |$key| type::thing('guitars', $key)) AND published_at != NONE",
+            ("keys", guitar_keys.to_vec()),
+        )
+        .await
+    }
+
+    async fn query(
+        db: &Surreal<surrealdb::engine::remote::http::Client>,
+        history_filter: &str,
+        guitar_filter: &str,
+        binding: (&'static str, impl serde::Serialize + 'static),
+    ) -> surrealdb::Result<Self> {
+        let mut response = db
+            .query(format!(
+                "SELECT record::id(guitar_id) AS guitar_key, price_cents, price_currency, status, event, <string> changed_at AS changed_at
+                 FROM price_history {history_filter} ORDER BY changed_at ASC;
+                 SELECT record::id(id) AS guitar_key, <string> published_at AS published_at FROM guitars {guitar_filter};"
+            ))
+            .bind(binding)
+            .await?;
+
+        let points: Vec<PricePoint> = response.take(0)?;
+        let published: Vec<serde_json::Value> = response.take(1)?;
+
+        let mut timeline = Self::default();
+        for point in points {
+            timeline.history.entry(point.guitar_key.clone()).or_default().push(point);
+        }
+        for row in published {
+            let key = row.get("guitar_key").and_then(|v| v.as_str());
+            let published_at = row.get("published_at").and_then(|v| v.as_str()).and_then(parse_datetime);
+            if let (Some(key), Some(published_at)) = (key, published_at) {
+                timeline.published_at.insert(key.to_string(), published_at);
+            }
+        }
+        Ok(timeline)
+    }
+
+    pub fn price_change(&self, guitar_key: &str, guitar: &Guitar, now: DateTime<Utc>) -> PriceChange {
+        price_change(
+            self.history.get(guitar_key).map(Vec::as_slice).unwrap_or_default(),
+            guitar,
+            self.published_at.get(guitar_key).copied(),
+            now,
+        )
+    }
+}
+
+/// Summarize the most recent price change of a guitar from its (oldest-first) history
+pub fn price_change(
+    history: &[PricePoint],
+    guitar: &Guitar,
+    published_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> PriceChange {
+    let current_price = guitar.get_price();
+
+    // The latest point whose price differs from the one recorded before it
+    let last_change = history
+        .windows(2)
+        .rev()
+        .find(|pair| pair[0].price_cents != pair[1].price_cents || pair[0].price_currency != pair[1].price_currency);
+
+    let mut change = PriceChange {
+        current_price: current_price.clone(),
+        ..Default::default()
+    };
+    if let Some([before, after]) = last_change.map(|pair| [&pair[0], &pair[1]]) {
+        change.last_changed_at = Some(after.changed_at.clone());
+        change.previous_price = before.price();
+        if let (Some(previous), Some(current)) = (before.price(), after.price()) {
+            if previous.currency == current.currency {
+                let delta = current.amount_minor - previous.amount_minor;
+                change.change_minor = Some(delta);
+                change.change_percent =
+                    Some((delta as f64 / previous.amount_minor as f64 * 1000.0).round() / 10.0);
+                change.price_drop = delta < 0;
+            }
+        }
+    }
+
+    // Time on market stops counting once the guitar sold
+    let sold_at = history
+        .iter()
+        .find(|p| p.status.as_deref().is_some_and(|s| s.eq_ignore_ascii_case("sold")))
+        .and_then(PricePoint::changed_at);
+    change.days_on_market = published_at.map(|published| (sold_at.unwrap_or(now) - published).num_days().max(0));
+
+    change
+}
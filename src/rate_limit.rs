@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use surrealdb::Surreal;
+
+use crate::auth::{self, AuthSettings, API_KEY_PREFIX};
+use crate::models::{ClientUsage, RateBudget, RateLimitReport};
+
+/// Request budgets per client and which endpoints count as expensive
+#[derive(Debug, Clone)]
+pub struct RateLimitSettings {
+    /// Budget of every other `/api` and `/img` request; `per_minute` 0 turns limiting off
+    pub default_budget: RateBudget,
+    pub expensive_budget: RateBudget,
+    /// Paths with the expensive budget; `*` matches one path segment
+    pub expensive_paths: Vec<String>,
+    /// Header holding the client address when behind a proxy (e.g. `Fly-Client-IP`);
+    /// without it the peer address is used
+    pub client_ip_header: Option<String>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            default_budget: RateBudget { per_minute: 120, burst: 60 },
+            expensive_budget: RateBudget { per_minute: 20, burst: 10 },
+            expensive_paths: [
+                "/api/guitars",
+                "/api/market/stats",
+                "/api/tools/estimate",
+                "/api/guitars/*/similar",
+                "/img/*",
+            ]
+            .iter()
+            .map(|p| p.to_string())
+            .collect(),
+            client_ip_header: None,
+        }
+    }
+}
+
+impl RateLimitSettings {
+    /// Read the `RATE_LIMIT_*` environment variables, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str| env::var(name).ok().and_then(|v| v.trim().parse::<u32>().ok());
+        let paths: Vec<String> = env::var("RATE_LIMIT_EXPENSIVE_PATHS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|p| p.trim().trim_end_matches('/').to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        Self {
+            default_budget: RateBudget {
+                per_minute: number("RATE_LIMIT_PER_MINUTE").unwrap_or(defaults.default_budget.per_minute),
+                burst: number("RATE_LIMIT_BURST").filter(|n| *n > 0).unwrap_or(defaults.default_budget.burst),
+            },
+            expensive_budget: RateBudget {
+                per_minute: number("RATE_LIMIT_EXPENSIVE_PER_MINUTE")
+                    .filter(|n| *n > 0)
+                    .unwrap_or(defaults.expensive_budget.per_minute),
+                burst: number("RATE_LIMIT_EXPENSIVE_BURST")
+                    .filter(|n| *n > 0)
+                    .unwrap_or(defaults.expensive_budget.burst),
+            },
+            expensive_paths: if paths.is_empty() { defaults.expensive_paths } else { paths },
+            client_ip_header: env::var("RATE_LIMIT_CLIENT_IP_HEADER").ok().filter(|h| !h.trim().is_empty()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.default_budget.per_minute > 0
+    }
+
+    fn is_expensive(&self, path: &str) -> bool {
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        self.expensive_paths.iter().any(|pattern| {
+            let pattern: Vec<&str> = pattern.split('/').collect();
+            pattern.len() == segments.len() && pattern.iter().zip(&segments).all(|(p, s)| *p == "*" || p == s)
+        })
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Result of taking a token: allowed or not, tokens left and seconds until the bucket is full
+/// (or, when rejected, until the next token)
+struct Decision {
+    allowed: bool,
+    remaining: u32,
+    reset_secs: u64,
+}
+
+impl Bucket {
+    fn take(&mut self, budget: RateBudget, now: Instant) -> Decision {
+        let per_second = f64::from(budget.per_minute) / 60.0;
+        let capacity = f64::from(budget.burst);
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.refilled_at = now;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        let missing = if allowed { capacity - self.tokens } else { 1.0 - self.tokens };
+        Decision {
+            allowed,
+            remaining: self.tokens.floor() as u32,
+            reset_secs: (missing / per_second).ceil() as u64,
+        }
+    }
+}
+
+/// Clients tracked before idle ones are dropped
+const MAX_CLIENTS: usize = 10_000;
+/// How long a looked-up API key is trusted before it is checked again
+const KEY_CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Default)]
+struct LimiterState {
+    buckets: HashMap<(String, bool), Bucket>,
+    usage: HashMap<String, ClientUsage>,
+    /// API key hash -> key name (None for unknown keys) and when it was looked up
+    keys: HashMap<String, (Option<String>, Instant)>,
+}
+
+/// In-memory token buckets and usage counters, shared by all workers
+#[derive(Clone)]
+pub struct RateLimiter {
+    pub settings: RateLimitSettings,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            settings,
+            state: Arc::new(Mutex::new(LimiterState::default())),
+        }
+    }
+
+    fn check(&self, client: &str, expensive: bool) -> Decision {
+        let budget = if expensive { self.settings.expensive_budget } else { self.settings.default_budget };
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("rate limiter lock");
+        let decision = state
+            .buckets
+            .entry((client.to_string(), expensive))
+            .or_insert_with(|| Bucket {
+                tokens: f64::from(budget.burst),
+                refilled_at: now,
+            })
+            .take(budget, now);
+
+        let timestamp = Utc::now().to_rfc3339();
+        let usage = state.usage.entry(client.to_string()).or_insert_with(|| ClientUsage {
+            client: client.to_string(),
+            requests: 0,
+            expensive_requests: 0,
+            throttled: 0,
+            first_seen: timestamp.clone(),
+            last_seen: timestamp.clone(),
+        });
+        usage.requests += 1;
+        usage.expensive_requests += u64::from(expensive);
+        usage.throttled += u64::from(!decision.allowed);
+        usage.last_seen = timestamp;
+
+        if state.usage.len() > MAX_CLIENTS {
+            Self::forget_idle(&mut state);
+        }
+        decision
+    }
+
+    /// Drop the least recently seen half of the clients
+    fn forget_idle(state: &mut LimiterState) {
+        let mut last_seen: Vec<(String, DateTime<Utc>)> = state
+            .usage
+            .values()
+            .map(|u| (u.client.clone(), DateTime::parse_from_rfc3339(&u.last_seen).map(|t| t.to_utc()).unwrap_or_default()))
+            .collect();
+        last_seen.sort_by_key(|(_, seen)| *seen);
+        for (client, _) in last_seen.into_iter().take(MAX_CLIENTS / 2) {
+            state.usage.remove(&client);
+            state.buckets.remove(&(client.clone(), false));
+            state.buckets.remove(&(client, true));
+        }
+    }
+
+    /// Usage counters, busiest clients first
+    pub fn report(&self) -> RateLimitReport {
+        let state = self.state.lock().expect("rate limiter lock");
+        let mut clients: Vec<ClientUsage> = state.usage.values().cloned().collect();
+        clients.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.client.cmp(&b.client)));
+        RateLimitReport {
+            enabled: self.settings.enabled(),
+            default_budget: self.settings.default_budget,
+            expensive_budget: self.settings.expensive_budget,
+            expensive_paths: self.settings.expensive_paths.clone(),
+            clients,
+        }
+    }
+
+    /// Name of the active API key with this hash when it was looked up within `KEY_CACHE_TTL`
+    /// (`Some(None)` for a key known to be invalid)
+    fn cached_key_name(&self, hash: &str) -> Option<Option<String>> {
+        let state = self.state.lock().expect("rate limiter lock");
+        let (name, checked_at) = state.keys.get(hash)?;
+        (checked_at.elapsed() < KEY_CACHE_TTL).then(|| name.clone())
+    }
+
+    /// Look up the name of the active API key with this hash and remember it for `KEY_CACHE_TTL`
+    async fn lookup_key_name(&self, req: &ServiceRequest, key: &str, hash: String) -> Option<String> {
+        let db = req.app_data::<web::Data<Surreal<surrealdb::engine::remote::http::Client>>>()?;
+        // A failed lookup isn't cached, so a database hiccup doesn't demote the key for long
+        let identity = auth::verify_api_key(db, key).await.ok()?;
+        let name = identity.map(|identity| identity.subject);
+        let mut state = self.state.lock().expect("rate limiter lock");
+        if state.keys.len() > MAX_CLIENTS {
+            state.keys.clear();
+        }
+        state.keys.insert(hash, (name.clone(), Instant::now()));
+        name
+    }
+
+    /// Charge the request to its client: a valid API key, a valid session user or else the client IP.
+    /// Unknown keys and bad tokens fall back to the IP, so inventing credentials doesn't buy a budget.
+    /// Looking up a key that isn't cached costs the IP one default token, so random keys can't
+    /// turn into unthrottled database queries; the request itself is charged only once.
+    async fn charge(&self, req: &ServiceRequest, expensive: bool) -> Decision {
+        let headers = req.headers();
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        let api_key = headers
+            .get("X-API-Key")
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .or(bearer.filter(|token| token.starts_with(API_KEY_PREFIX)));
+
+        if let Some(key) = api_key {
+            let hash = auth::hash_api_key(key);
+            let name = match self.cached_key_name(&hash) {
+                Some(name) => name,
+                None => {
+                    // For an unknown key the lookup token already pays for a default request
+                    let lookup = self.check(&self.client_ip(req), false);
+                    if !lookup.allowed {
+                        return lookup;
+                    }
+                    match self.lookup_key_name(req, key, hash).await {
+                        Some(name) => Some(name),
+                        None if !expensive => return lookup,
+                        None => None,
+                    }
+                }
+            };
+            if let Some(name) = name {
+                return self.check(&format!("api_key:{name}"), expensive);
+            }
+        } else if let Some(token) = bearer {
+            let identity = req
+                .app_data::<web::Data<AuthSettings>>()
+                .and_then(|settings| auth::verify_session(settings, token));
+            if let Some(identity) = identity {
+                return self.check(&format!("user:{}", identity.subject), expensive);
+            }
+        }
+        self.check(&self.client_ip(req), expensive)
+    }
+
+    fn client_ip(&self, req: &ServiceRequest) -> String {
+        let forwarded = self
+            .settings
+            .client_ip_header
+            .as_deref()
+            .and_then(|name| req.headers().get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty());
+        let ip = forwarded
+            .or_else(|| req.peer_addr().map(|addr| addr.ip().to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        format!("ip:{ip}")
+    }
+}
+
+fn insert_header(headers: &mut header::HeaderMap, name: &'static str, value: impl ToString) {
+    if let Ok(value) = HeaderValue::from_str(&value.to_string()) {
+        headers.insert(HeaderName::from_static(name), value);
+    }
+}
+
+/// Middleware charging each `/api` and `/img` request to its client's bucket; over-budget
+/// requests get 429 with `Retry-After`, and every limited response carries `RateLimit-*` headers.
+pub async fn limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let limiter = req.app_data::<web::Data<RateLimiter>>().cloned();
+    let path = req.path().to_string();
+    let Some(limiter) = limiter.filter(|l| l.settings.enabled() && (path.starts_with("/api/") || path.starts_with("/img/")))
+    else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    let expensive = limiter.settings.is_expensive(&path);
+    let budget = if expensive { limiter.settings.expensive_budget } else { limiter.settings.default_budget };
+    let decision = limiter.charge(&req, expensive).await;
+
+    let mut response = if decision.allowed {
+        next.call(req).await?.map_into_left_body()
+    } else {
+        let rejected = HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, decision.reset_secs.to_string()))
+            .json(json!({
+                "error": "rate limit exceeded",
+                "retry_after": decision.reset_secs,
+                "budget": if expensive { "expensive" } else { "default" }
+            }));
+        req.into_response(rejected).map_into_right_body()
+    };
+    let headers = response.headers_mut();
+    insert_header(headers, "ratelimit-limit", budget.burst);
+    insert_header(headers, "ratelimit-remaining", decision.remaining);
+    insert_header(headers, "ratelimit-reset", decision.reset_secs);
+    insert_header(headers, "ratelimit-policy", format!("{};w={}", budget.burst, 60 * budget.burst / budget.per_minute.max(1)));
+    Ok(response)
+}
@@ -1,29 +1,108 @@
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_multipart::Multipart;
+use actix_web::http::header;
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
 use surrealdb::Surreal;
 use serde_json::json;
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey as ApiKeyScheme, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::models::{Guitar, Image, ImageUpdateRequest, ErrorResponse};
+use crate::audit::{self, AuditContext, AuditFilter};
+use crate::auth::{self, AuthSettings, OptionalIdentity, RequireAdmin, RequireEditor, RequireViewer};
+use crate::catalog;
+use crate::duplicates::{self, MergeError};
+use crate::models::{Guitar, GuitarModel, Shop, Brand, PriceRange, Image, ImageRole, ImageCreateRequest, ImageIngestRequest, ImageVariant, ImageUploadForm, ImageUploadResponse, UploadRejection, ImageReorderRequest, ImageUpdateRequest, LinkCheck, LinkHealthSummary, LinkReportEntry, LinkHealthReport, RateBudget, ClientUsage, RateLimitReport, AuditChange, AuditEntry, GuitarVersionSummary, VersionDiff, RevertResponse, TrashedGuitar, PurgeResponse, DuplicateGuitar, DuplicatePair, MergeRequest, MergeResponse, Role, Tier, Identity, User, UserCreateRequest, TierUpdateRequest, LoginRequest, LoginResponse, ApiKey, ApiKeyCreateRequest, ApiKeyCreated, ErrorResponse, SpecDeviation, ComponentStatus, ComponentCheck, OriginalityReport, SpecChange, LineageNode, LineageEdge, LineageResponse, LineageLinkRequest, SerialDecodeRequest, SerialDecodeResponse, SerialConsistency, PriceHistoryEntry, PriceChange, PriceHistoryResponse, Condition, ListingStatus, StatusTransitionRequest, StatusTransitionResponse, MarketComparable, ConditionBreakdown, MarketStats, EstimateRequest, EstimateComparable, EstimateResponse, SimilarGuitar, SimilarityDimension};
+use crate::image_proxy::{self, ImageCache, RenderRequest};
+use crate::images;
+use crate::ingest::{self, IngestError, IngestOptions, IngestSettings};
+use crate::lineage;
+use crate::link_health;
+use crate::money::{self, ExchangeRates, Money};
+use crate::market::{self, MarketFilter};
+use crate::originality;
+use crate::paywall::{self, PaywallPolicy};
+use crate::price_history::MarketTimeline;
+use crate::rate_limit::RateLimiter;
+use crate::serial::SerialDecoder;
+use crate::similarity::{self, SimilarityWeights, SpecProfile};
+use crate::specs;
+use crate::storage::ImageStorage;
+use crate::trash;
+use crate::valuation;
+use crate::versions;
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         health,
+        login,
+        whoami,
+        create_user,
+        set_user_tier,
+        list_api_keys,
+        create_api_key,
+        revoke_api_key,
         list_guitars,
         get_guitar_by_slug,
         get_guitar_details,
+        get_similar_guitars,
+        get_price_history,
+        transition_status,
+        get_market_stats,
+        get_guitar_lineage,
+        create_lineage_link,
+        decode_serial,
+        estimate_value,
+        get_guitar_originality,
+        refresh_guitar_originality,
+        list_models,
+        get_model_by_slug,
+        list_shops,
+        get_shop_by_slug,
+        get_brand_by_slug,
+        get_link_health,
+        get_rate_limits,
+        get_guitar_audit,
+        get_audit_feed,
+        list_guitar_versions,
+        get_guitar_version,
+        diff_guitar_versions,
+        revert_guitar,
+        list_trash,
+        restore_guitar,
+        purge_guitar,
+        get_orphans,
+        get_duplicates,
+        merge_guitars,
         update_guitar_images,
+        list_guitar_images,
+        add_guitar_image,
+        ingest_guitar_image,
+        upload_guitar_images,
+        proxy_image,
+        reorder_guitar_images,
+        remove_guitar_image,
         get_api_schema
     ),
     components(
-        schemas(Guitar, Image, ImageUpdateRequest, ErrorResponse)
+        schemas(Guitar, Money, GuitarModel, Shop, Brand, PriceRange, SpecDeviation, ComponentStatus, ComponentCheck, OriginalityReport, SpecChange, LineageNode, LineageEdge, LineageResponse, LineageLinkRequest, SerialDecodeRequest, SerialDecodeResponse, SerialConsistency, PriceHistoryEntry, PriceChange, PriceHistoryResponse, Condition, ListingStatus, StatusTransitionRequest, StatusTransitionResponse, MarketComparable, ConditionBreakdown, MarketStats, EstimateRequest, EstimateComparable, EstimateResponse, Image, ImageRole, ImageVariant, ImageCreateRequest, ImageIngestRequest, ImageUploadForm, ImageUploadResponse, UploadRejection, ImageReorderRequest, ImageUpdateRequest, LinkCheck, LinkHealthSummary, LinkReportEntry, LinkHealthReport, RateBudget, ClientUsage, RateLimitReport, AuditChange, AuditEntry, GuitarVersionSummary, VersionDiff, RevertResponse, TrashedGuitar, PurgeResponse, DuplicateGuitar, DuplicatePair, MergeRequest, MergeResponse, Role, Tier, Identity, UserCreateRequest, TierUpdateRequest, LoginRequest, LoginResponse, ApiKey, ApiKeyCreateRequest, ApiKeyCreated, ErrorResponse, SimilarGuitar, SimilarityDimension)
     ),
     tags(
         (name = "guitars", description = "Guitar management endpoints"),
+        (name = "models", description = "Catalog model (factory spec) endpoints"),
+        (name = "images", description = "Guitar image records, ordering and roles"),
+        (name = "shops", description = "Dealer and shop profiles with inventory"),
+        (name = "brands", description = "Brand profiles with models and price ranges"),
+        (name = "lineage", description = "Reissue, successor and signature lineage endpoints"),
+        (name = "market", description = "Market statistics and comparable listings"),
+        (name = "tools", description = "Collector tools (serial decoding, estimates)"),
+        (name = "auth", description = "Editor login, API keys and users"),
+        (name = "admin", description = "Maintenance and data-quality reports"),
         (name = "health", description = "Health check endpoints"),
         (name = "docs", description = "API documentation endpoints")
     ),
+    modifiers(&SecurityAddon),
     info(
         title = "Guitar API",
         version = "1.0.0",
@@ -32,415 +111,2504 @@ use crate::models::{Guitar, Image, ImageUpdateRequest, ErrorResponse};
 )]
 pub struct ApiDoc;
 
+/// Session tokens (`Authorization: Bearer`) and service API keys (`X-API-Key`)
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKeyScheme::Header(ApiKeyValue::new("X-API-Key"))));
+    }
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         SwaggerUi::new("/swagger-ui/{_:.*}")
             .url("/api-docs/openapi.json", ApiDoc::openapi())
     )
     .service(health)
+    .service(login)
+    .service(whoami)
+    .service(create_user)
+    .service(set_user_tier)
+    .service(list_api_keys)
+    .service(create_api_key)
+    .service(revoke_api_key)
     .service(list_guitars)
     .service(get_guitar_by_slug)
     .service(get_guitar_details)
+    .service(get_similar_guitars)
+    .service(get_price_history)
+    .service(transition_status)
+    .service(get_market_stats)
+    .service(get_guitar_lineage)
+    .service(create_lineage_link)
+    .service(decode_serial)
+    .service(estimate_value)
+    .service(get_guitar_originality)
+    .service(refresh_guitar_originality)
+    .service(list_models)
+    .service(get_model_by_slug)
+    .service(list_shops)
+    .service(get_shop_by_slug)
+    .service(get_brand_by_slug)
+    .service(get_link_health)
+    .service(get_rate_limits)
+    .service(get_guitar_audit)
+    .service(get_audit_feed)
+    .service(list_guitar_versions)
+    .service(get_guitar_version)
+    .service(diff_guitar_versions)
+    .service(revert_guitar)
+    .service(list_trash)
+    .service(restore_guitar)
+    .service(purge_guitar)
+    .service(get_orphans)
+    .service(get_duplicates)
+    .service(merge_guitars)
     .service(debug_woods)
     .service(delete_guitar)
     .service(delete_guitar_post_redirect)
     .service(list_images)
     .service(update_guitar_images)
+    .service(list_guitar_images)
+    .service(add_guitar_image)
+    .service(ingest_guitar_image)
+    .service(upload_guitar_images)
+    .service(proxy_image)
+    .service(reorder_guitar_images)
+    .service(remove_guitar_image)
     .service(get_api_schema);
 }
 
 #[utoipa::path(
     get,
-    path = "/health",
-    tag = "health",
+    path = "/api/admin/rate-limits",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
     responses(
-        (status = 200, description = "Health check successful", body = serde_json::Value)
+        (status = 200, description = "Rate-limit budgets and per-client request counters since the server started", body = RateLimitReport),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse)
     )
 )]
-#[get("/health")]
-async fn health() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({"ok": true}))
+#[get("/api/admin/rate-limits")]
+async fn get_rate_limits(_auth: RequireAdmin, limiter: web::Data<RateLimiter>) -> impl Responder {
+    HttpResponse::Ok().json(limiter.report())
 }
 
-#[delete("/api/guitars/{id}")]
-async fn delete_guitar(
+/// Read `limit` (default 100, at most 1000) and the `since`/`until` bounds of an audit query
+fn audit_filter(query: &std::collections::HashMap<String, String>) -> Result<AuditFilter, HttpResponse> {
+    let limit = match query.get("limit").map(|l| l.trim().parse::<usize>()) {
+        None => 100,
+        Some(Ok(limit)) if (1..=1000).contains(&limit) => limit,
+        Some(_) => return Err(HttpResponse::BadRequest().json(json!({"error": "limit must be between 1 and 1000"}))),
+    };
+    let bound = |name: &str| match query.get(name).map(|v| v.trim()) {
+        None => Ok(None),
+        Some(raw) => chrono::DateTime::parse_from_rfc3339(raw)
+            .map(|at| Some(at.to_rfc3339()))
+            .map_err(|_| HttpResponse::BadRequest().json(json!({"error": format!("{name} must be an RFC 3339 timestamp")}))),
+    };
+    Ok(AuditFilter {
+        since: bound("since")?,
+        until: bound("until")?,
+        limit,
+        ..AuditFilter::default()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{id}/audit",
+    tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')"),
+        ("since" = Option<String>, Query, description = "Only writes at or after this RFC 3339 time"),
+        ("until" = Option<String>, Query, description = "Only writes before this RFC 3339 time"),
+        ("limit" = Option<usize>, Query, description = "Most entries to return (default 100, max 1000)")
+    ),
+    responses(
+        (status = 200, description = "Writes to the guitar and its spec rows, newest first, with the fields each one changed", body = Vec<AuditEntry>),
+        (status = 400, description = "Invalid limit or time bound", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{id}/audit")]
+async fn get_guitar_audit(
+    _auth: RequireEditor,
     db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
     path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let id_str = path.into_inner();
-    let rid = if id_str.contains(':') {
-        id_str
-    } else {
-        format!("guitars:{id_str}")
+    let mut filter = match audit_filter(&query) {
+        Ok(filter) => filter,
+        Err(response) => return response,
+    };
+    // Deleted guitars keep their history, so a missing guitar is not a 404
+    filter.guitar_key = Some(id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string());
+    match audit::fetch(&db, filter).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("table" = Option<String>, Query, description = "Only writes to this table (guitars, guitar_models or a guitar_* table)"),
+        ("event" = Option<String>, Query, description = "Only CREATE, UPDATE or DELETE"),
+        ("actor" = Option<String>, Query, description = "Only writes by this actor (e.g., 'user:alice', 'api_key:importer', 'system')"),
+        ("source" = Option<String>, Query, description = "Only writes through this route or job (e.g., 'link-checker')"),
+        ("guitar" = Option<String>, Query, description = "Only writes to this guitar and its spec rows"),
+        ("since" = Option<String>, Query, description = "Only writes at or after this RFC 3339 time"),
+        ("until" = Option<String>, Query, description = "Only writes before this RFC 3339 time"),
+        ("limit" = Option<usize>, Query, description = "Most entries to return (default 100, max 1000)")
+    ),
+    responses(
+        (status = 200, description = "Audited writes across all guitars and the catalog, newest first", body = Vec<AuditEntry>),
+        (status = 400, description = "Unknown table or event, invalid limit or time bound", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/admin/audit")]
+async fn get_audit_feed(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let mut filter = match audit_filter(&query) {
+        Ok(filter) => filter,
+        Err(response) => return response,
     };
+    let value = |name: &str| query.get(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
 
-    // DELETE FROM guitars WHERE id = rid
-    let res: surrealdb::Result<Vec<serde_json::Value>> = db.delete(rid.as_str()).await;
-    match res {
-        Ok(_) => HttpResponse::NoContent().finish(),
-        Err(e) => {
-            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
-        }
+    filter.table = value("table").map(|t| t.to_lowercase());
+    if filter.table.as_deref().is_some_and(|t| !audit::AUDITED_TABLES.contains(&t)) {
+        return HttpResponse::BadRequest().json(json!({"error": "unknown table", "allowed": audit::AUDITED_TABLES}));
+    }
+    filter.event = value("event").map(|e| e.to_uppercase());
+    if filter.event.as_deref().is_some_and(|e| !audit::EVENTS.contains(&e)) {
+        return HttpResponse::BadRequest().json(json!({"error": "unknown event", "allowed": audit::EVENTS}));
+    }
+    filter.actor = value("actor");
+    filter.source = value("source");
+    filter.guitar_key = value("guitar").map(|g| g.strip_prefix("guitars:").unwrap_or(&g).to_string());
+
+    match audit::fetch(&db, filter).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
     }
 }
 
-// Convenience endpoint for HTML forms (since forms cannot send DELETE)
-#[post("/api/guitars/{id}/delete")]
-async fn delete_guitar_post_redirect(
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{id}/versions",
+    tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    responses(
+        (status = 200, description = "Stored versions of the guitar, newest first", body = Vec<GuitarVersionSummary>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{id}/versions")]
+async fn list_guitar_versions(
+    _auth: RequireEditor,
     db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
     path: web::Path<String>,
 ) -> impl Responder {
     let id_str = path.into_inner();
-    let rid = if id_str.contains(':') {
-        id_str
-    } else {
-        format!("guitars:{id_str}")
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
+    match versions::list(&db, key).await {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{id}/versions/{n}",
+    tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')"),
+        ("n" = i64, Path, description = "Version number")
+    ),
+    responses(
+        (status = 200, description = "The guitar's full detail document as of that version, with the version under `version`; price change, catalog model and shop are shown as they are now", body = serde_json::Value),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "No such version", body = ErrorResponse),
+        (status = 410, description = "The guitar was deleted in that version", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{id}/versions/{n}")]
+async fn get_guitar_version(
+    _auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<(String, i64)>,
+) -> impl Responder {
+    let (id_str, number) = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
+    let version = match versions::load(&db, key, number).await {
+        Ok(Some(version)) => version,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}"), "version": number}))
+        }
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+    let Some(guitar) = version.guitar.clone() else {
+        return HttpResponse::Gone().json(json!({"error": "guitar was deleted in this version", "version": version.summary}));
     };
 
-    let _: surrealdb::Result<Vec<serde_json::Value>> = db.delete(rid.as_str()).await;
-    HttpResponse::SeeOther()
-        .append_header(("Location", "/guitars"))
-        .finish()
+    let mut rows = version.spec_rows();
+    let provenance = rows.remove("guitar_provenance").unwrap_or_default();
+    let mut detail = render_guitar_detail(&db, guitar, specs::spec_sections(rows), provenance).await;
+    if let serde_json::Value::Object(ref mut map) = detail {
+        map.insert("version".to_string(), json!(version.summary));
+    }
+    HttpResponse::Ok().json(detail)
 }
 
 #[utoipa::path(
     get,
-    path = "/api/guitars",
+    path = "/api/guitars/{id}/versions/{from}/diff/{to}",
     tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')"),
+        ("from" = i64, Path, description = "Version to compare from"),
+        ("to" = i64, Path, description = "Version to compare to")
+    ),
     responses(
-        (status = 200, description = "List of guitars with enhanced data", body = Vec<serde_json::Value>),
+        (status = 200, description = "Fields of the guitar and its spec sections that differ between the two versions", body = VersionDiff),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "No such version", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[get("/api/guitars")]
-async fn list_guitars(
+#[get("/api/guitars/{id}/versions/{from}/diff/{to}")]
+async fn diff_guitar_versions(
+    _auth: RequireEditor,
     db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<(String, i64, i64)>,
 ) -> impl Responder {
-    // SELECT * FROM guitars
-    let res: surrealdb::Result<Vec<Guitar>> = db.select("guitars").await;
-    match res {
-        Ok(rows) => {
-            // Transform guitars to include generated slugs and helper data for frontend
-            let guitars_with_enhanced_data: Vec<serde_json::Value> = rows
-                .into_iter()
-                .map(|guitar| {
-                    let generated_slug = guitar.get_slug();
-                    let mut guitar_json = serde_json::to_value(&guitar).unwrap_or_default();
-                    if let serde_json::Value::Object(ref mut map) = guitar_json {
-                        map.insert("slug".to_string(), serde_json::Value::String(generated_slug));
-                        map.insert("display_title".to_string(), serde_json::Value::String(guitar.get_display_title()));
-                    }
-                    guitar_json
-                })
-                .collect();
-            HttpResponse::Ok().json(guitars_with_enhanced_data)
-        }
-        Err(e) => {
-            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+    let (id_str, from, to) = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
+    let mut documents = Vec::new();
+    for number in [from, to] {
+        match versions::load(&db, key, number).await {
+            Ok(Some(version)) => documents.push(version.document()),
+            Ok(None) => {
+                return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}"), "version": number}))
+            }
+            Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
         }
     }
+    HttpResponse::Ok().json(VersionDiff {
+        guitar_id: format!("guitars:{key}"),
+        from,
+        to,
+        changes: versions::diff(&documents[0], &documents[1]),
+    })
 }
 
-
 #[utoipa::path(
-    get,
-    path = "/api/guitars/{slug}",
+    post,
+    path = "/api/guitars/{id}/revert/{n}",
     tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
     params(
-        ("slug" = String, Path, description = "Guitar slug (e.g., 'banker-58-spec-v')")
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')"),
+        ("n" = i64, Path, description = "Version to restore")
     ),
     responses(
-        (status = 200, description = "Guitar found", body = serde_json::Value),
-        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 200, description = "Guitar and spec rows restored in one transaction; the result is stored as a new version", body = RevertResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "No such version", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[get("/api/guitars/{slug}")]
-async fn get_guitar_by_slug(
+#[post("/api/guitars/{id}/revert/{n}")]
+async fn revert_guitar(
+    auth: RequireEditor,
     db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
-    path: web::Path<String>,
+    path: web::Path<(String, i64)>,
 ) -> impl Responder {
-    let slug = path.into_inner();
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/revert/{n}");
+    let (id_str, number) = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
+    match versions::load(&db, key, number).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}"), "version": number}))
+        }
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
 
-    // Since we can't query by slug due to DB issues, get all guitars and filter in Rust
-    let res: surrealdb::Result<Vec<Guitar>> = db.select("guitars").await;
+    if let Err(e) = versions::revert(&db, key, number, &audit).await {
+        return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}));
+    }
+    match versions::list(&db, key).await {
+        Ok(list) => match list.into_iter().next() {
+            Some(version) => HttpResponse::Ok().json(RevertResponse {
+                guitar_id: format!("guitars:{key}"),
+                reverted_to: number,
+                version,
+            }),
+            None => HttpResponse::InternalServerError().json(json!({"error": "revert was not recorded"})),
+        },
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-    match res {
-        Ok(rows) => {
-            // Find guitar by generated slug
-            if let Some(guitar) = rows.into_iter().find(|g| g.get_slug() == slug) {
-                // Get the record ID for fetching detailed specifications
-                let rid = guitar.id.as_ref().map(|id| id.to_string()).unwrap_or_default();
-
-                // Extract just the ID part from the record ID (remove table: prefix)
-                let id_part = if rid.contains(':') {
-                    rid.split(':').nth(1).unwrap_or(&rid)
-                } else {
-                    &rid
-                };
+#[utoipa::path(
+    get,
+    path = "/api/admin/link-health",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("status" = Option<String>, Query, description = "Only links that are ok, broken or probably_sold"),
+        ("kind" = Option<String>, Query, description = "Only listing, hero_image or gallery_image links")
+    ),
+    responses(
+        (status = 200, description = "Results of the last link check of every listing and image URL, problems first", body = LinkHealthReport),
+        (status = 400, description = "Invalid status or kind", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/admin/link-health")]
+async fn get_link_health(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let status = query.get("status").map(|s| s.trim().to_lowercase());
+    if let Some(status) = status.as_deref() {
+        if !["ok", "broken", "probably_sold"].contains(&status) {
+            return HttpResponse::BadRequest().json(json!({"error": "invalid status", "allowed": ["ok", "broken", "probably_sold"]}));
+        }
+    }
+    let kinds = [link_health::KIND_LISTING, link_health::KIND_HERO_IMAGE, link_health::KIND_GALLERY_IMAGE];
+    let kind = query.get("kind").map(|k| k.trim().to_lowercase());
+    if kind.as_deref().is_some_and(|k| !kinds.contains(&k)) {
+        return HttpResponse::BadRequest().json(json!({"error": "invalid kind", "allowed": kinds}));
+    }
 
-                // Get all related specifications using query method with OMIT to exclude record fields
-                let dimensions_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_dimensions WHERE guitar_id = guitars:{};", id_part)).await;
-                let woods_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_woods WHERE guitar_id = guitars:{};", id_part)).await;
-                let finish_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_finish WHERE guitar_id = guitars:{};", id_part)).await;
-                let hardware_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_hardware WHERE guitar_id = guitars:{};", id_part)).await;
-                let electronics_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_electronics WHERE guitar_id = guitars:{};", id_part)).await;
-                let appointments_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_appointments WHERE guitar_id = guitars:{};", id_part)).await;
-                let setup_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_setup WHERE guitar_id = guitars:{};", id_part)).await;
-                let case_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_case WHERE guitar_id = guitars:{};", id_part)).await;
-                let pickups_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_pickups WHERE guitar_id = guitars:{};", id_part)).await;
-                let controls_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_controls WHERE guitar_id = guitars:{};", id_part)).await;
-
-                // Build the response with all specifications
-                let slug = guitar.get_slug();
-                let display_title = guitar.get_display_title();
-                let mut guitar_json = serde_json::to_value(guitar).unwrap_or_default();
-                if let serde_json::Value::Object(ref mut map) = guitar_json {
-                    map.insert("slug".to_string(), serde_json::Value::String(slug));
-                    map.insert("display_title".to_string(), serde_json::Value::String(display_title));
-
-                    // Add specifications from query results
-                    if let Ok(mut dims_resp) = dimensions_res {
-                        if let Ok(dims) = dims_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(dim) = dims.first() {
-                                map.insert("dimensions".to_string(), dim.clone());
-                            }
-                        }
-                    }
+    let checks: surrealdb::Result<Vec<LinkCheck>> = db.select("link_checks").await;
+    let guitars: surrealdb::Result<Vec<Guitar>> = specs::fetch_guitars(&db).await;
+    let (checks, guitars) = match (checks, guitars) {
+        (Ok(checks), Ok(guitars)) => (checks, guitars),
+        (Err(e), _) | (_, Err(e)) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let by_key: std::collections::HashMap<String, &Guitar> = guitars
+        .iter()
+        .filter_map(|g| g.id.as_ref().map(|id| (specs::record_key(id), g)))
+        .collect();
 
-                    if let Ok(mut woods_resp) = woods_res {
-                        if let Ok(woods) = woods_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(wood) = woods.first() {
-                                map.insert("woods".to_string(), wood.clone());
-                            }
-                        }
-                    }
+    let link_status = |check: &LinkCheck| match (check.ok, check.probably_sold) {
+        (_, true) => "probably_sold",
+        (true, false) => "ok",
+        (false, false) => "broken",
+    };
+    let mut summary = LinkHealthSummary::default();
+    for check in &checks {
+        summary.checked += 1;
+        match link_status(check) {
+            "ok" => summary.ok += 1,
+            "broken" => summary.broken += 1,
+            _ => summary.probably_sold += 1,
+        }
+    }
+    let last_checked_at = checks.iter().filter_map(|c| c.checked_at.clone()).max().map(|at| at.to_raw());
 
-                    if let Ok(mut finish_resp) = finish_res {
-                        if let Ok(finish) = finish_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(fin) = finish.first() {
-                                map.insert("finish".to_string(), fin.clone());
-                            }
-                        }
-                    }
+    let mut links: Vec<LinkReportEntry> = checks
+        .iter()
+        .filter(|check| status.as_deref().is_none_or(|s| link_status(check) == s))
+        .filter(|check| kind.as_deref().is_none_or(|k| check.kind == k))
+        .map(|check| {
+            let guitar = check.guitar_id.as_ref().and_then(|id| by_key.get(&specs::record_key(id)));
+            LinkReportEntry {
+                guitar_id: check.guitar_id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+                slug: guitar.map(|g| g.get_slug()).unwrap_or_default(),
+                display_title: guitar.map(|g| g.get_display_title()).unwrap_or_default(),
+                url: check.url.clone(),
+                kind: check.kind.clone(),
+                status_code: check.status_code,
+                ok: check.ok,
+                probably_sold: check.probably_sold,
+                error: check.error.clone(),
+                checked_at: check.checked_at.as_ref().map(|at| at.to_raw()),
+            }
+        })
+        .collect();
+    let rank = |entry: &LinkReportEntry| match (entry.ok, entry.probably_sold) {
+        (_, true) => 0,
+        (false, false) => 1,
+        (true, false) => 2,
+    };
+    links.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.slug.cmp(&b.slug)).then_with(|| a.url.cmp(&b.url)));
 
-                    if let Ok(mut hardware_resp) = hardware_res {
-                        if let Ok(hardware) = hardware_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(hw) = hardware.first() {
-                                map.insert("hardware".to_string(), hw.clone());
-                            }
-                        }
-                    }
+    let mut probably_sold: Vec<String> = guitars
+        .iter()
+        .filter(|g| g.probably_sold == Some(true))
+        .map(|g| g.get_slug())
+        .collect();
+    probably_sold.sort();
 
-                    if let Ok(mut electronics_resp) = electronics_res {
-                        if let Ok(electronics) = electronics_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(elec) = electronics.first() {
-                                map.insert("electronics".to_string(), elec.clone());
-                            }
-                        }
-                    }
+    HttpResponse::Ok().json(LinkHealthReport {
+        summary,
+        last_checked_at,
+        probably_sold,
+        links,
+    })
+}
 
-                    if let Ok(mut appointments_resp) = appointments_res {
-                        if let Ok(appointments) = appointments_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(appt) = appointments.first() {
-                                map.insert("appointments".to_string(), appt.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Health check successful", body = serde_json::Value)
+    )
+)]
+#[get("/health")]
+async fn health() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({"ok": true}))
+}
 
-                    if let Ok(mut setup_resp) = setup_res {
-                        if let Ok(setup) = setup_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(set) = setup.first() {
-                                map.insert("setup".to_string(), set.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Session token for the editor", body = LoginResponse),
+        (status = 401, description = "Unknown user or wrong password", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/auth/login")]
+async fn login(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    settings: web::Data<AuthSettings>,
+    body: web::Json<LoginRequest>,
+) -> impl Responder {
+    let request = body.into_inner();
+    let mut response = match db
+        .query("SELECT * FROM users WHERE username = $username LIMIT 1")
+        .bind(("username", request.username.trim().to_lowercase()))
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+    let user: Option<User> = match response.take::<Vec<User>>(0) {
+        Ok(users) => users.into_iter().next(),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
 
-                    if let Ok(mut case_resp) = case_res {
-                        if let Ok(case_data) = case_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(case_info) = case_data.first() {
-                                map.insert("case".to_string(), case_info.clone());
-                            }
-                        }
-                    }
+    // Unknown users are checked against a dummy hash so the response time doesn't reveal them
+    let password_hash = user.as_ref().map(|user| user.password_hash.clone());
+    let verified = web::block(move || auth::verify_login(&request.password, password_hash.as_deref()))
+        .await
+        .unwrap_or(false);
+    let Some(user) = user.filter(|_| verified) else {
+        return HttpResponse::Unauthorized().json(json!({"error": "invalid username or password"}));
+    };
 
-                    if let Ok(mut pickups_resp) = pickups_res {
-                        if let Ok(pickups) = pickups_resp.take::<Vec<serde_json::Value>>(0) {
-                            if !pickups.is_empty() {
-                                map.insert("pickups".to_string(), serde_json::Value::Array(pickups));
-                            }
-                        }
-                    }
+    match auth::issue_session(&settings, &user.username, user.role, user.tier) {
+        Ok((token, expires_at)) => HttpResponse::Ok().json(LoginResponse {
+            token,
+            token_type: "Bearer".to_string(),
+            expires_at: expires_at.to_rfc3339(),
+            username: user.username,
+            role: user.role,
+            tier: user.tier,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-                    if let Ok(mut controls_resp) = controls_res {
-                        if let Ok(controls) = controls_resp.take::<Vec<serde_json::Value>>(0) {
-                            if !controls.is_empty() {
-                                map.insert("controls".to_string(), serde_json::Value::Array(controls));
-                            }
-                        }
-                    }
-                }
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    tag = "auth",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "The caller's identity and role", body = Identity),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse)
+    )
+)]
+#[get("/api/auth/me")]
+async fn whoami(auth: RequireViewer) -> impl Responder {
+    HttpResponse::Ok().json(auth.identity)
+}
 
-                HttpResponse::Ok().json(guitar_json)
-            } else {
-                HttpResponse::NotFound().json(serde_json::json!({"error": "not found", "slug": slug}))
-            }
-        }
-        Err(e) => {
-            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+#[utoipa::path(
+    post,
+    path = "/api/admin/users",
+    tag = "auth",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    request_body = UserCreateRequest,
+    responses(
+        (status = 201, description = "User created", body = Identity),
+        (status = 400, description = "Missing username or password shorter than 12 characters", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 409, description = "Username taken", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/admin/users")]
+async fn create_user(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    body: web::Json<UserCreateRequest>,
+) -> impl Responder {
+    let request = body.into_inner();
+    let username = request.username.trim().to_lowercase();
+    if username.is_empty() || request.password.chars().count() < 12 {
+        return HttpResponse::BadRequest().json(json!({"error": "username is required and password must have at least 12 characters"}));
+    }
+    let password_hash = match web::block(move || auth::hash_password(&request.password)).await {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+
+    let created = db
+        .query("CREATE users CONTENT { username: $username, password_hash: $password_hash, role: $role, tier: $tier }")
+        .bind(("username", username.clone()))
+        .bind(("password_hash", password_hash))
+        .bind(("role", request.role))
+        .bind(("tier", request.tier))
+        .await
+        .and_then(|response| response.check());
+    match created {
+        Ok(_) => HttpResponse::Created().json(Identity {
+            subject: username,
+            role: request.role,
+            tier: request.tier,
+            method: "session".to_string(),
+        }),
+        Err(e) if e.to_string().contains("already contains") => {
+            HttpResponse::Conflict().json(json!({"error": "username taken", "username": username}))
         }
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
     }
 }
 
 #[utoipa::path(
-    get,
-    path = "/api/guitars/{id}/details",
-    tag = "guitars",
+    put,
+    path = "/api/admin/users/{username}/tier",
+    tag = "auth",
+    security(("bearer_auth" = []), ("api_key" = [])),
     params(
-        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+        ("username" = String, Path, description = "Username")
     ),
+    request_body = TierUpdateRequest,
     responses(
-        (status = 200, description = "Guitar with detailed specifications", body = serde_json::Value),
-        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 200, description = "Subscription tier changed; takes effect at the user's next login", body = Identity),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[get("/api/guitars/{id}/details")]
-async fn get_guitar_details(
+#[put("/api/admin/users/{username}/tier")]
+async fn set_user_tier(
+    _auth: RequireAdmin,
     db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
     path: web::Path<String>,
+    body: web::Json<TierUpdateRequest>,
 ) -> impl Responder {
-    let id_str = path.into_inner();
-    let rid = if id_str.contains(':') {
-        id_str
-    } else {
-        format!("guitars:{id_str}")
+    let username = path.into_inner().trim().to_lowercase();
+    let mut response = match db
+        .query("UPDATE users SET tier = $tier WHERE username = $username RETURN AFTER")
+        .bind(("username", username.clone()))
+        .bind(("tier", body.tier))
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
     };
+    match response.take::<Vec<User>>(0) {
+        Ok(users) => match users.into_iter().next() {
+            Some(user) => HttpResponse::Ok().json(Identity {
+                subject: user.username,
+                role: user.role,
+                tier: user.tier,
+                method: "session".to_string(),
+            }),
+            None => HttpResponse::NotFound().json(json!({"error": "not found", "username": username})),
+        },
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-    // Get the main guitar record by selecting all and filtering (same as get_guitar_by_slug)
-    let guitar_res: surrealdb::Result<Vec<Guitar>> = db.select("guitars").await;
-    match guitar_res {
-        Ok(rows) => {
-            // Find guitar by record ID
-            if let Some(guitar) = rows.into_iter().find(|g| g.id.as_ref().map(|id| id.to_string()) == Some(rid.clone())) {
-                // Extract just the ID part from the record ID (remove table: prefix)
-                let id_part = if rid.contains(':') {
-                    rid.split(':').nth(1).unwrap_or(&rid)
-                } else {
-                    &rid
-                };
+#[utoipa::path(
+    get,
+    path = "/api/admin/api-keys",
+    tag = "auth",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "API keys (without the keys themselves)", body = Vec<ApiKey>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/admin/api-keys")]
+async fn list_api_keys(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+) -> impl Responder {
+    let keys: surrealdb::Result<Vec<ApiKey>> = db.select("api_keys").await;
+    match keys {
+        Ok(mut keys) => {
+            keys.sort_by(|a, b| a.name.cmp(&b.name));
+            HttpResponse::Ok().json(keys)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-                // Get all related specifications using query method with OMIT to exclude record fields
-                let dimensions_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_dimensions WHERE guitar_id = guitars:{};", id_part)).await;
-                let woods_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_woods WHERE guitar_id = guitars:{};", id_part)).await;
-                let finish_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_finish WHERE guitar_id = guitars:{};", id_part)).await;
-                let hardware_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_hardware WHERE guitar_id = guitars:{};", id_part)).await;
-                let electronics_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_electronics WHERE guitar_id = guitars:{};", id_part)).await;
-                let appointments_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_appointments WHERE guitar_id = guitars:{};", id_part)).await;
-                let setup_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_setup WHERE guitar_id = guitars:{};", id_part)).await;
-                let case_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_case WHERE guitar_id = guitars:{};", id_part)).await;
-                let pickups_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_pickups WHERE guitar_id = guitars:{};", id_part)).await;
-                let controls_res: surrealdb::Result<surrealdb::Response> = db.query(format!("SELECT * OMIT id, guitar_id FROM guitar_controls WHERE guitar_id = guitars:{};", id_part)).await;
-
-                // Build the response with all specifications
-                let slug = guitar.get_slug();
-                let display_title = guitar.get_display_title();
-                let mut guitar_json = serde_json::to_value(guitar).unwrap_or_default();
-                if let serde_json::Value::Object(ref mut map) = guitar_json {
-                    map.insert("slug".to_string(), serde_json::Value::String(slug));
-                    map.insert("display_title".to_string(), serde_json::Value::String(display_title));
-
-                    // Add specifications from query results
-                    if let Ok(mut dims_resp) = dimensions_res {
-                        if let Ok(dims) = dims_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(dim) = dims.first() {
-                                map.insert("dimensions".to_string(), dim.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    post,
+    path = "/api/admin/api-keys",
+    tag = "auth",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    request_body = ApiKeyCreateRequest,
+    responses(
+        (status = 201, description = "API key created; the key is only returned here", body = ApiKeyCreated),
+        (status = 400, description = "Missing name", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/admin/api-keys")]
+async fn create_api_key(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    body: web::Json<ApiKeyCreateRequest>,
+) -> impl Responder {
+    let request = body.into_inner();
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(json!({"error": "name is required"}));
+    }
+    let key = auth::generate_api_key();
+    let prefix: String = key.chars().take(auth::API_KEY_PREFIX.len() + 8).collect();
 
-                    if let Ok(mut woods_resp) = woods_res {
-                        if let Ok(woods) = woods_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(wood) = woods.first() {
-                                map.insert("woods".to_string(), wood.clone());
-                            }
-                        }
-                    }
+    let mut response = match db
+        .query("CREATE api_keys CONTENT { name: $name, role: $role, tier: $tier, prefix: $prefix, key_hash: $key_hash }")
+        .bind(("name", name.clone()))
+        .bind(("role", request.role))
+        .bind(("tier", request.tier))
+        .bind(("prefix", prefix.clone()))
+        .bind(("key_hash", auth::hash_api_key(&key)))
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+    match response.take::<Vec<ApiKey>>(0) {
+        Ok(created) => HttpResponse::Created().json(ApiKeyCreated {
+            id: created.first().and_then(|k| k.id.as_ref()).map(|id| id.to_string()).unwrap_or_default(),
+            name,
+            role: request.role,
+            tier: request.tier,
+            prefix,
+            key,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-                    if let Ok(mut finish_resp) = finish_res {
-                        if let Ok(finish) = finish_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(fin) = finish.first() {
-                                map.insert("finish".to_string(), fin.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    delete,
+    path = "/api/admin/api-keys/{id}",
+    tag = "auth",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "API key ID (e.g., 'api_keys:abc' or 'abc')")
+    ),
+    responses(
+        (status = 204, description = "Key revoked"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[delete("/api/admin/api-keys/{id}")]
+async fn revoke_api_key(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("api_keys:").unwrap_or(&id_str).to_string();
+    let mut response = match db
+        .query("UPDATE type::thing('api_keys', $key) SET revoked = true RETURN AFTER")
+        .bind(("key", key.clone()))
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+    match response.take::<Vec<ApiKey>>(0) {
+        Ok(revoked) if !revoked.is_empty() => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("api_keys:{key}")})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-                    if let Ok(mut hardware_resp) = hardware_res {
-                        if let Ok(hardware) = hardware_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(hw) = hardware.first() {
-                                map.insert("hardware".to_string(), hw.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    get,
+    path = "/api/admin/trash",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Guitars in the trash, most recently deleted first", body = Vec<TrashedGuitar>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse)
+    )
+)]
+#[get("/api/admin/trash")]
+async fn list_trash(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+) -> impl Responder {
+    match trash::list(&db).await {
+        Ok(guitars) => {
+            let trashed: Vec<TrashedGuitar> = guitars
+                .into_iter()
+                .map(|guitar| TrashedGuitar {
+                    id: guitar.id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+                    slug: guitar.slug.clone(),
+                    display_title: guitar.get_display_title(),
+                    deleted_at: guitar.deleted_at.as_ref().map(|at| at.to_string()),
+                })
+                .collect();
+            HttpResponse::Ok().json(trashed)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-                    if let Ok(mut electronics_resp) = electronics_res {
-                        if let Ok(electronics) = electronics_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(elec) = electronics.first() {
-                                map.insert("electronics".to_string(), elec.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    post,
+    path = "/api/admin/trash/{id}/restore",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(("id" = String, Path, description = "Guitar ID")),
+    responses(
+        (status = 200, description = "Guitar taken out of the trash", body = Guitar),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 404, description = "No such guitar in the trash", body = ErrorResponse)
+    )
+)]
+#[post("/api/admin/trash/{id}/restore")]
+async fn restore_guitar(
+    auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/admin/trash/{id}/restore");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+    match trash::restore(&db, &key, &audit).await {
+        Ok(Some(guitar)) => HttpResponse::Ok().json(guitar),
+        Ok(None) => HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-                    if let Ok(mut appointments_resp) = appointments_res {
-                        if let Ok(appointments) = appointments_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(appt) = appointments.first() {
-                                map.insert("appointments".to_string(), appt.clone());
-                            }
-                        }
-                    }
+#[utoipa::path(
+    delete,
+    path = "/api/admin/trash/{id}",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(("id" = String, Path, description = "Guitar ID")),
+    responses(
+        (status = 200, description = "Guitar and all its child rows removed in one transaction", body = PurgeResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 404, description = "No such guitar in the trash", body = ErrorResponse)
+    )
+)]
+#[delete("/api/admin/trash/{id}")]
+async fn purge_guitar(
+    auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "DELETE /api/admin/trash/{id}");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+    let not_found = || HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")}));
 
-                    if let Ok(mut setup_resp) = setup_res {
-                        if let Ok(setup) = setup_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(set) = setup.first() {
-                                map.insert("setup".to_string(), set.clone());
-                            }
-                        }
-                    }
+    // Only guitars already in the trash can be purged
+    match trash::is_trashed(&db, &key).await {
+        Ok(true) => {}
+        Ok(false) => return not_found(),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+    match trash::purge(&db, &key, &audit).await {
+        Ok(removed) => HttpResponse::Ok().json(PurgeResponse {
+            guitar_id: format!("guitars:{key}"),
+            removed,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/orphans",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Rows per child table whose guitar no longer exists; `cargo run --bin cleanup-orphans -- --remove` deletes them", body = std::collections::BTreeMap<String, usize>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse)
+    )
+)]
+#[get("/api/admin/orphans")]
+async fn get_orphans(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+) -> impl Responder {
+    match trash::orphans(&db, false, &AuditContext::system("orphan-report")).await {
+        Ok(counts) => HttpResponse::Ok().json(counts),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/duplicates",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("min_score" = Option<f64>, Query, description = "Lowest score to list, 0.0-1.0 (default 0.7)"),
+        ("limit" = Option<usize>, Query, description = "Pairs to return, 1-1000 (default 100)")
+    ),
+    responses(
+        (status = 200, description = "Guitars sharing a serial number, listing URL or near-identical photo, scored on those and on brand/model and specs, best first", body = Vec<DuplicatePair>),
+        (status = 400, description = "Invalid min_score or limit", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse)
+    )
+)]
+#[get("/api/admin/duplicates")]
+async fn get_duplicates(
+    _auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    weights: web::Data<SimilarityWeights>,
+    rates: web::Data<ExchangeRates>,
+    settings: web::Data<IngestSettings>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let min_score = match query.get("min_score").map(|s| s.trim().parse::<f64>()) {
+        None => 0.7,
+        Some(Ok(score)) if (0.0..=1.0).contains(&score) => score,
+        Some(_) => return HttpResponse::BadRequest().json(json!({"error": "min_score must be between 0 and 1"})),
+    };
+    let limit = match query.get("limit").map(|l| l.trim().parse::<usize>()) {
+        None => 100,
+        Some(Ok(limit)) if (1..=1000).contains(&limit) => limit,
+        Some(_) => return HttpResponse::BadRequest().json(json!({"error": "limit must be between 1 and 1000"})),
+    };
+    match duplicates::find(&db, &weights, &rates, settings.duplicate_distance, min_score).await {
+        Ok(mut pairs) => {
+            pairs.truncate(limit);
+            HttpResponse::Ok().json(pairs)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/merge",
+    tag = "admin",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    request_body = MergeRequest,
+    responses(
+        (status = 200, description = "Merged guitar folded into the kept one and deleted in one transaction; its slug now redirects", body = MergeResponse),
+        (status = 400, description = "Same guitar twice, or unknown fields in take_from_merged", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse)
+    )
+)]
+#[post("/api/admin/merge")]
+async fn merge_guitars(
+    auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    settings: web::Data<IngestSettings>,
+    body: web::Json<MergeRequest>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/admin/merge");
+    let request = body.into_inner();
+    let keep = request.keep.strip_prefix("guitars:").unwrap_or(&request.keep);
+    let merge = request.merge.strip_prefix("guitars:").unwrap_or(&request.merge);
+    match duplicates::merge(&db, keep, merge, &request.take_from_merged, settings.duplicate_distance, &audit).await {
+        Ok(merged) => HttpResponse::Ok().json(merged),
+        Err(MergeError::NotFound(id)) => HttpResponse::NotFound().json(json!({"error": "not found", "id": id})),
+        Err(e @ MergeError::SameGuitar) => HttpResponse::BadRequest().json(json!({"error": e.to_string()})),
+        Err(MergeError::UnknownFields(fields)) => HttpResponse::BadRequest().json(json!({
+            "error": "take_from_merged names fields that cannot be taken from the merged guitar",
+            "fields": fields
+        })),
+        Err(e @ MergeError::Database(_)) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[delete("/api/guitars/{id}")]
+async fn delete_guitar(
+    auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "DELETE /api/guitars/{id}");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+
+    // Moves the guitar to the trash; DELETE /api/admin/trash/{id} removes it for good
+    match trash::soft_delete(&db, &key, &audit).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+        }
+    }
+}
+
+// Convenience endpoint for HTML forms (since forms cannot send DELETE)
+#[post("/api/guitars/{id}/delete")]
+async fn delete_guitar_post_redirect(
+    auth: RequireAdmin,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/delete");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+
+    let _ = trash::soft_delete(&db, &key, &audit).await;
+    HttpResponse::SeeOther()
+        .append_header(("Location", "/guitars"))
+        .finish()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars",
+    tag = "guitars",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("currency" = Option<String>, Query, description = "ISO 4217 code to add a converted_price in (e.g., 'EUR')"),
+        ("sort" = Option<String>, Query, description = "'price_asc' or 'price_desc' (compares prices across currencies), 'condition_asc' or 'condition_desc'"),
+        ("min_condition" = Option<String>, Query, description = "Only guitars in this condition or better (e.g., 'very-good', 'Excellent +')")
+    ),
+    responses(
+        (status = 200, description = "List of guitars with enhanced data (`price_change` withheld below the subscriber tier, see each item's `paywall_applied`)", body = Vec<serde_json::Value>),
+        (status = 400, description = "Unsupported currency, sort or condition", body = ErrorResponse),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars")]
+async fn list_guitars(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    rates: web::Data<ExchangeRates>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let currency = query.get("currency").map(|c| money::normalize_currency(c));
+    if let Some(code) = currency.as_deref() {
+        if !rates.supports(code) {
+            return HttpResponse::BadRequest().json(json!({"error": format!("Unsupported currency: {code}")}));
+        }
+    }
+    let sort = query.get("sort").map(String::as_str);
+    if !matches!(sort, None | Some("price_asc") | Some("price_desc") | Some("condition_asc") | Some("condition_desc")) {
+        return HttpResponse::BadRequest().json(json!({"error": "sort must be 'price_asc', 'price_desc', 'condition_asc' or 'condition_desc'"}));
+    }
+    let min_condition = match query.get("min_condition").map(|c| Condition::parse(c).ok_or(c)) {
+        None => None,
+        Some(Ok(condition)) => Some(condition),
+        Some(Err(raw)) => {
+            return HttpResponse::BadRequest().json(json!({"error": format!("Unknown condition: {raw}"), "allowed": Condition::ALL}))
+        }
+    };
+
+    // SELECT * FROM guitars WHERE deleted_at = NONE
+    let res: surrealdb::Result<Vec<Guitar>> = specs::fetch_guitars(&db).await;
+    match res {
+        Ok(mut rows) => {
+            if let Some(minimum) = min_condition {
+                rows.retain(|g| g.get_condition().is_some_and(|c| c >= minimum));
+            }
+
+            // Price history only of the guitars being returned
+            let keys: Vec<String> = rows.iter().filter_map(|g| g.id.as_ref().map(specs::record_key)).collect();
+            let timeline = match MarketTimeline::load_for(&db, &keys).await {
+                Ok(timeline) => timeline,
+                Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+            };
+            let now = chrono::Utc::now();
+            let tier = paywall::tier_of(caller.identity.as_ref());
+
+            // Ungraded guitars go last either way
+            if let Some(order @ ("condition_asc" | "condition_desc")) = sort {
+                rows.sort_by(|a, b| match (a.get_condition(), b.get_condition()) {
+                    (Some(x), Some(y)) if order == "condition_desc" => y.cmp(&x),
+                    (Some(x), Some(y)) => x.cmp(&y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+            }
 
-                    if let Ok(mut case_resp) = case_res {
-                        if let Ok(case_data) = case_resp.take::<Vec<serde_json::Value>>(0) {
-                            if let Some(case_info) = case_data.first() {
-                                map.insert("case".to_string(), case_info.clone());
-                            }
+            // Compare prices in the rate table's base currency; unpriced guitars go last
+            if let Some(order @ ("price_asc" | "price_desc")) = sort {
+                let key = |g: &Guitar| g.get_price().and_then(|p| rates.convert(&p, &rates.base)).map(|p| p.amount_minor);
+                rows.sort_by(|a, b| match (key(a), key(b)) {
+                    (Some(x), Some(y)) if order == "price_desc" => y.cmp(&x),
+                    (Some(x), Some(y)) => x.cmp(&y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+            }
+
+            // Transform guitars to include generated slugs and helper data for frontend
+            let guitars_with_enhanced_data: Vec<serde_json::Value> = rows
+                .into_iter()
+                .map(|guitar| {
+                    let generated_slug = guitar.get_slug();
+                    let mut guitar_json = serde_json::to_value(&guitar).unwrap_or_default();
+                    if let serde_json::Value::Object(ref mut map) = guitar_json {
+                        map.insert("slug".to_string(), serde_json::Value::String(generated_slug));
+                        map.insert("display_title".to_string(), serde_json::Value::String(guitar.get_display_title()));
+                        map.insert("formatted_price".to_string(), serde_json::Value::String(guitar.get_formatted_price()));
+                        map.insert("price".to_string(), json!(guitar.get_price()));
+                        map.insert("condition_grade".to_string(), json!(guitar.get_condition()));
+                        map.insert("condition_color".to_string(), json!(guitar.get_condition_color()));
+                        if let Some(code) = currency.as_deref() {
+                            let converted = guitar.get_price().and_then(|p| rates.convert(&p, code));
+                            map.insert("converted_price".to_string(), json!(converted));
                         }
+                        let key = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+                        map.insert("price_change".to_string(), json!(timeline.price_change(&key, &guitar, now)));
                     }
+                    policy.apply_paywall_restrictions(paywall::GUITAR_LIST, tier, &mut guitar_json);
+                    guitar_json
+                })
+                .collect();
+            HttpResponse::Ok().json(guitars_with_enhanced_data)
+        }
+        Err(e) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+        }
+    }
+}
+
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{slug}",
+    tag = "guitars",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("slug" = String, Path, description = "Guitar slug (e.g., 'banker-58-spec-v')")
+    ),
+    responses(
+        (status = 200, description = "Guitar found (premium fields limited below the subscriber tier, see `paywall_applied`)", body = serde_json::Value),
+        (status = 301, description = "Old slug of a guitar merged into another; `Location` points at the guitar it was merged into"),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{slug}")]
+async fn get_guitar_by_slug(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    path: web::Path<String>,
+) -> impl Responder {
+    let slug = path.into_inner();
+
+    // Since we can't query by slug due to DB issues, get all guitars and filter in Rust
+    let res: surrealdb::Result<Vec<Guitar>> = specs::fetch_guitars(&db).await;
+
+    match res {
+        Ok(rows) => {
+            // Find guitar by generated slug
+            if let Some(guitar) = rows.into_iter().find(|g| g.get_slug() == slug) {
+                let mut detail = build_guitar_detail(&db, guitar).await;
+                let tier = paywall::tier_of(caller.identity.as_ref());
+                policy.apply_paywall_restrictions(paywall::GUITAR_DETAIL, tier, &mut detail);
+                return HttpResponse::Ok().json(detail);
+            }
+            // Slugs of guitars merged into another redirect to the guitar they were merged into
+            let target = match duplicates::redirect_target(&db, &slug).await {
+                Ok(Some(key)) => specs::fetch_guitar(&db, &key).await.ok().flatten(),
+                _ => None,
+            };
+            match target {
+                Some(guitar) => {
+                    let new_slug = guitar.get_slug();
+                    HttpResponse::MovedPermanently()
+                        .append_header(("Location", format!("/api/guitars/{new_slug}")))
+                        .json(json!({"slug": new_slug}))
+                }
+                None => HttpResponse::NotFound().json(serde_json::json!({"error": "not found", "slug": slug})),
+            }
+        }
+        Err(e) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{id}/details",
+    tag = "guitars",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    responses(
+        (status = 200, description = "Guitar with detailed specifications (premium fields limited below the subscriber tier, see `paywall_applied`)", body = serde_json::Value),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{id}/details")]
+async fn get_guitar_details(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id_str = path.into_inner();
+    let rid = if id_str.contains(':') {
+        id_str
+    } else {
+        format!("guitars:{id_str}")
+    };
+
+    // Get the main guitar record by selecting all and filtering (same as get_guitar_by_slug)
+    let guitar_res: surrealdb::Result<Vec<Guitar>> = specs::fetch_guitars(&db).await;
+    match guitar_res {
+        Ok(rows) => {
+            // Find guitar by record ID
+            if let Some(guitar) = rows.into_iter().find(|g| g.id.as_ref().map(|id| id.to_string()) == Some(rid.clone())) {
+                let mut detail = build_guitar_detail(&db, guitar).await;
+                let tier = paywall::tier_of(caller.identity.as_ref());
+                policy.apply_paywall_restrictions(paywall::GUITAR_DETAIL, tier, &mut detail);
+                HttpResponse::Ok().json(detail)
+            } else {
+                HttpResponse::NotFound().json(serde_json::json!({"error": "not found", "id": rid}))
+            }
+        }
+        Err(e) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+        }
+    }
+}
+
+/// Build the full detail document for a guitar: core fields, every spec section and,
+/// when the guitar is linked to a catalog model, the merged effective spec.
+async fn build_guitar_detail(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar: Guitar,
+) -> serde_json::Value {
+    let id_part = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+
+    // Get all related specifications
+    let spec_sections = specs::fetch_guitar_specs(db, &id_part).await;
+    let provenance: Vec<serde_json::Value> = match db
+        .query("SELECT * OMIT id, guitar_id FROM guitar_provenance WHERE guitar_id = type::thing('guitars', $key)")
+        .bind(("key", id_part.clone()))
+        .await
+    {
+        Ok(mut response) => response.take(0).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    render_guitar_detail(db, guitar, spec_sections, provenance).await
+}
+
+/// The detail document of a guitar with the given spec sections and provenance; price change,
+/// catalog model and shop are looked up as they are now
+async fn render_guitar_detail(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar: Guitar,
+    spec_sections: serde_json::Map<String, serde_json::Value>,
+    provenance: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    let id_part = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+    let timeline = MarketTimeline::load(db, Some(&id_part)).await.unwrap_or_default();
+    let price_change = timeline.price_change(&id_part, &guitar, chrono::Utc::now());
+
+    // Look up the catalog model this guitar was built to, if any
+    let catalog_model: Option<GuitarModel> = match guitar.model_ref.as_ref() {
+        Some(model_ref) => db
+            .select(("guitar_models", specs::record_key(model_ref)))
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    let shop: Option<Shop> = match guitar.shop_ref.as_ref() {
+        Some(shop_ref) => db.select(("shops", specs::record_key(shop_ref))).await.ok().flatten(),
+        None => None,
+    };
+
+    // Build the response with all specifications
+    let slug = guitar.get_slug();
+    let display_title = guitar.get_display_title();
+    let model_ref = guitar.model_ref.as_ref().map(|id| id.to_string());
+    let brand_ref = guitar.brand_ref.as_ref().map(|id| id.to_string());
+    let mut guitar_json = serde_json::to_value(guitar).unwrap_or_default();
+    if let serde_json::Value::Object(ref mut map) = guitar_json {
+        map.insert("slug".to_string(), serde_json::Value::String(slug));
+        map.insert("display_title".to_string(), serde_json::Value::String(display_title));
+        map.insert("model_ref".to_string(), json!(model_ref));
+        map.insert("brand_ref".to_string(), json!(brand_ref));
+        map.insert("shop_ref".to_string(), json!(shop.as_ref().and_then(|s| s.id.as_ref()).map(|id| id.to_string())));
+        map.insert("shop".to_string(), json!(shop.map(|s| json!({
+            "slug": s.get_slug(),
+            "name": s.name,
+            "location": s.location,
+            "website": s.website,
+            "logo_url": s.logo_url,
+        }))));
+        map.insert("price_change".to_string(), json!(price_change));
+        map.extend(spec_sections);
+        if !provenance.is_empty() {
+            map.insert("provenance".to_string(), json!(provenance));
+        }
+
+        if let Some(model) = catalog_model {
+            let deviations = catalog::merge_effective_spec(map, &model);
+            map.insert("catalog_model".to_string(), json!({
+                "id": model.id.as_ref().map(|id| id.to_string()),
+                "slug": model.get_slug(),
+                "brand": model.brand,
+                "model": model.model,
+            }));
+            map.insert("spec_deviations".to_string(), json!(deviations));
+        }
+    }
+    guitar_json
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{slug}/similar",
+    tag = "guitars",
+    params(
+        ("slug" = String, Path, description = "Guitar slug (e.g., 'banker-58-spec-v')"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of similar guitars to return (default 6, max 50)")
+    ),
+    responses(
+        (status = 200, description = "Most similar guitars with a per-dimension explanation", body = Vec<SimilarGuitar>),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{slug}/similar")]
+async fn get_similar_guitars(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    weights: web::Data<SimilarityWeights>,
+    rates: web::Data<ExchangeRates>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let slug = path.into_inner();
+    let limit = query
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(6)
+        .min(50);
+
+    let rows: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let Some(reference) = rows.iter().find(|g| g.get_slug() == slug) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "slug": slug}));
+    };
+
+    // Load the spec tables used for scoring in bulk rather than once per guitar
+    let specs = async {
+        let dimensions = specs::fetch_spec_rows_by_guitar(&db, "guitar_dimensions").await?;
+        let woods = specs::fetch_spec_rows_by_guitar(&db, "guitar_woods").await?;
+        let pickups = specs::fetch_spec_rows_by_guitar(&db, "guitar_pickups").await?;
+        let neck_profiles = specs::fetch_normalizations(&db, "neck_profile").await?;
+        Ok::<_, surrealdb::Error>((dimensions, woods, pickups, neck_profiles))
+    }
+    .await;
+    let (dimensions, woods, pickups, neck_profiles) = match specs {
+        Ok(specs) => specs,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+
+    let profile_for = |guitar: &Guitar| {
+        let key = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+        SpecProfile::build(
+            guitar,
+            dimensions.get(&key).and_then(|rows| rows.first()),
+            woods.get(&key).and_then(|rows| rows.first()),
+            pickups.get(&key).map(Vec::as_slice).unwrap_or_default(),
+            &neck_profiles,
+            &rates,
+        )
+    };
+
+    let reference_profile = profile_for(reference);
+    let mut similar: Vec<SimilarGuitar> = rows
+        .iter()
+        .filter(|g| g.id != reference.id)
+        .map(|g| {
+            let result = similarity::score(&reference_profile, &profile_for(g), &weights);
+            SimilarGuitar {
+                id: g.id.as_ref().map(|id| id.to_string()),
+                slug: g.get_slug(),
+                display_title: g.get_display_title(),
+                brand: g.brand.clone(),
+                model: g.model.clone(),
+                formatted_price: g.get_formatted_price(),
+                main_image: g.get_main_image(),
+                score: (result.score * 1000.0).round() / 1000.0,
+                explanation: result.dimensions,
+            }
+        })
+        .filter(|s| s.score > 0.0)
+        .collect();
+
+    similar.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.slug.cmp(&b.slug)));
+    similar.truncate(limit);
+
+    HttpResponse::Ok().json(similar)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/models",
+    tag = "models",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Catalog models with their factory specs (dimensions limited below the subscriber tier, see each item's `paywall_applied`)", body = Vec<GuitarModel>),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/models")]
+async fn list_models(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+) -> impl Responder {
+    let tier = paywall::tier_of(caller.identity.as_ref());
+    let res: surrealdb::Result<Vec<GuitarModel>> = db.select("guitar_models").await;
+    match res {
+        Ok(rows) => {
+            let models: Vec<serde_json::Value> = rows
+                .into_iter()
+                .map(|model| {
+                    let slug = model.get_slug();
+                    let mut model_json = serde_json::to_value(&model).unwrap_or_default();
+                    if let serde_json::Value::Object(ref mut map) = model_json {
+                        map.insert("slug".to_string(), serde_json::Value::String(slug));
+                    }
+                    policy.apply_paywall_restrictions(paywall::GUITAR_MODEL, tier, &mut model_json);
+                    model_json
+                })
+                .collect();
+            HttpResponse::Ok().json(models)
+        }
+        Err(e) => {
+            HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/models/{slug}",
+    tag = "models",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("slug" = String, Path, description = "Catalog model slug (e.g., 'banker-v-custom')")
+    ),
+    responses(
+        (status = 200, description = "Catalog model with the listed instances built to it (dimensions limited below the subscriber tier, see `paywall_applied`)", body = serde_json::Value),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 404, description = "Model not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/models/{slug}")]
+async fn get_model_by_slug(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    path: web::Path<String>,
+) -> impl Responder {
+    let slug = path.into_inner();
+
+    let models: Vec<GuitarModel> = match db.select("guitar_models").await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let Some(model) = models.into_iter().find(|m| m.get_slug() == slug) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "slug": slug}));
+    };
+
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let instances: Vec<serde_json::Value> = guitars
+        .iter()
+        .filter(|g| g.model_ref.is_some() && g.model_ref == model.id)
+        .map(listing_summary)
+        .collect();
+
+    let mut model_json = serde_json::to_value(&model).unwrap_or_default();
+    if let serde_json::Value::Object(ref mut map) = model_json {
+        map.insert("slug".to_string(), serde_json::Value::String(model.get_slug()));
+        map.insert("instances".to_string(), serde_json::Value::Array(instances));
+    }
+    policy.apply_paywall_restrictions(paywall::GUITAR_MODEL, paywall::tier_of(caller.identity.as_ref()), &mut model_json);
+    HttpResponse::Ok().json(model_json)
+}
+
+/// The guitar's catalog model as an originality reference: its label and factory specs.
+/// None when the guitar is not linked to a model.
+async fn model_reference(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar: &Guitar,
+) -> Result<Option<(serde_json::Value, serde_json::Map<String, serde_json::Value>)>, HttpResponse> {
+    let Some(model_ref) = guitar.model_ref.as_ref() else {
+        return Ok(None);
+    };
+    let model: Option<GuitarModel> = db
+        .select(("guitar_models", specs::record_key(model_ref)))
+        .await
+        .map_err(|e| HttpResponse::InternalServerError().json(json!({"error": e.to_string()})))?;
+    let Some(model) = model else {
+        return Err(HttpResponse::NotFound().json(json!({"error": "catalog model not found", "model_ref": model_ref.to_string()})));
+    };
+    Ok(Some((
+        json!({
+            "kind": "model",
+            "id": model.id.as_ref().map(|id| id.to_string()),
+            "label": format!("{} {}", model.brand, model.model),
+        }),
+        model.specs.clone().unwrap_or_default(),
+    )))
+}
+
+/// Assess a guitar's spec rows against a reference spec
+async fn originality_report(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar: &Guitar,
+    reference: serde_json::Value,
+    reference_specs: &serde_json::Map<String, serde_json::Value>,
+) -> OriginalityReport {
+    let id_part = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+    let instance_specs = specs::fetch_guitar_specs(db, &id_part).await;
+    let components = originality::assess(&instance_specs, reference_specs);
+    let count = |status: ComponentStatus| components.iter().filter(|c| c.status == status).count();
+    OriginalityReport {
+        guitar_id: format!("guitars:{id_part}"),
+        reference,
+        originality_score: originality::originality_score(&components),
+        original: count(ComponentStatus::Original),
+        replaced: count(ComponentStatus::Replaced),
+        unknown: count(ComponentStatus::Unknown),
+        components,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{id}/originality",
+    tag = "guitars",
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')"),
+        ("reference" = Option<String>, Query, description = "Reference guitar ID or slug; defaults to the guitar's catalog model")
+    ),
+    responses(
+        (status = 200, description = "Per-component originality and overall originality score", body = OriginalityReport),
+        (status = 400, description = "Guitar has no reference spec to compare against", body = ErrorResponse),
+        (status = 404, description = "Guitar or reference not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{id}/originality")]
+async fn get_guitar_originality(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+    let guitar = match specs::fetch_guitar(&db, &key).await {
+        Ok(Some(guitar)) => guitar,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+
+    // Reference spec: an explicit reference guitar, otherwise the linked catalog model
+    let (reference, reference_specs) = if let Some(reference_key) = query.get("reference") {
+        // Look the reference up by id first; only slugs need the full list
+        let by_id = specs::fetch_guitar(&db, reference_key.strip_prefix("guitars:").unwrap_or(reference_key)).await;
+        let reference_guitar = match by_id {
+            Ok(Some(reference_guitar)) => Some(reference_guitar),
+            Ok(None) => match specs::fetch_guitars(&db).await {
+                Ok(rows) => rows.into_iter().find(|g| g.get_slug() == *reference_key),
+                Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+            },
+            Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+        };
+        let Some(reference_guitar) = reference_guitar else {
+            return HttpResponse::NotFound().json(json!({"error": "reference not found", "reference": reference_key}));
+        };
+        let reference_id = reference_guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+        (
+            json!({
+                "kind": "guitar",
+                "id": reference_guitar.id.as_ref().map(|id| id.to_string()),
+                "label": reference_guitar.get_display_title(),
+            }),
+            specs::fetch_guitar_specs(&db, &reference_id).await,
+        )
+    } else {
+        match model_reference(&db, &guitar).await {
+            Ok(Some(reference)) => reference,
+            Ok(None) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "Guitar is not linked to a catalog model; pass ?reference=<guitar id or slug>"
+                }))
+            }
+            Err(response) => return response,
+        }
+    };
+
+    HttpResponse::Ok().json(originality_report(&db, &guitar, reference, &reference_specs).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guitars/{id}/originality",
+    tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    responses(
+        (status = 200, description = "Originality against the catalog model, stored as the guitar's originality_score", body = OriginalityReport),
+        (status = 400, description = "Guitar is not linked to a catalog model", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar or catalog model not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/guitars/{id}/originality")]
+async fn refresh_guitar_originality(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/originality");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+    let guitar = match specs::fetch_guitar(&db, &key).await {
+        Ok(Some(guitar)) => guitar,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+
+    // The cached score is only ever measured against the guitar's own catalog model
+    let (reference, reference_specs) = match model_reference(&db, &guitar).await {
+        Ok(Some(reference)) => reference,
+        Ok(None) => return HttpResponse::BadRequest().json(json!({"error": "Guitar is not linked to a catalog model"})),
+        Err(response) => return response,
+    };
+    let report = originality_report(&db, &guitar, reference, &reference_specs).await;
+
+    let res = db
+        .query("UPDATE type::thing('guitars', $key) SET originality_score = $score;")
+        .bind(("key", key))
+        .bind(("score", report.originality_score))
+        .bind(("audit", audit))
+        .await
+        .and_then(|response| response.check());
+    match res {
+        Ok(_) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{slug}/lineage",
+    tag = "lineage",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("slug" = String, Path, description = "Guitar slug (e.g., 'gibson-2008-flying-v-reissue-50th-anniversary')")
+    ),
+    responses(
+        (status = 200, description = "Family tree of reissues, successors and signature models with spec deltas per hop (fields the detail paywall restricts are left out below the subscriber tier, see `paywall_applied`)", body = LineageResponse),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{slug}/lineage")]
+async fn get_guitar_lineage(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    path: web::Path<String>,
+) -> impl Responder {
+    let slug = path.into_inner();
+    let tier = paywall::tier_of(caller.identity.as_ref());
+
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let models: Vec<GuitarModel> = match db.select("guitar_models").await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let guitar_node = |g: &Guitar| format!("guitars:{}", g.id.as_ref().map(specs::record_key).unwrap_or_default());
+    let model_node = |m: &GuitarModel| format!("guitar_models:{}", m.id.as_ref().map(specs::record_key).unwrap_or_default());
+
+    let Some(root) = guitars.iter().find(|g| g.get_slug() == slug) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "slug": slug}));
+    };
+
+    // Load every lineage edge; the graph is small enough to walk in memory
+    let statements: String = lineage::RELATIONS
+        .iter()
+        .map(|relation| {
+            format!(
+                "SELECT record::tb(in) AS from_tb, record::id(in) AS from_id, record::tb(out) AS to_tb, record::id(out) AS to_id, note FROM {relation};"
+            )
+        })
+        .collect();
+    let mut response = match db.query(statements).await {
+        Ok(response) => response,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+
+    let key_of = |v: Option<&serde_json::Value>| match v {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let mut edges: Vec<lineage::RawEdge> = Vec::new();
+    for (index, relation) in lineage::RELATIONS.iter().enumerate() {
+        let rows: Vec<serde_json::Value> = response.take(index).unwrap_or_default();
+        edges.extend(rows.iter().map(|row| lineage::RawEdge {
+            relation: relation.to_string(),
+            from: format!("{}:{}", key_of(row.get("from_tb")), key_of(row.get("from_id"))),
+            to: format!("{}:{}", key_of(row.get("to_tb")), key_of(row.get("to_id"))),
+            note: row.get("note").and_then(|v| v.as_str()).map(str::to_string),
+        }));
+    }
+
+    // Guitars linked to a catalog model hang off that model in the tree
+    edges.extend(guitars.iter().filter_map(|g| {
+        let model_ref = g.model_ref.as_ref()?;
+        Some(lineage::RawEdge {
+            relation: lineage::INSTANCE_OF.to_string(),
+            from: guitar_node(g),
+            to: format!("guitar_models:{}", specs::record_key(model_ref)),
+            note: None,
+        })
+    }));
+
+    let root_id = guitar_node(root);
+    let (family, family_edges) = lineage::collect_family(std::slice::from_ref(&root_id), &edges);
+
+    // Build a node summary and a full spec document for everything in the family; documents
+    // go through the detail and model paywall first, so deltas don't reveal restricted fields
+    let mut paywall_applied = false;
+    let mut restrict = |response: &str, document: serde_json::Map<String, serde_json::Value>| {
+        let mut document = serde_json::Value::Object(document);
+        paywall_applied |= policy.apply_paywall_restrictions(response, tier, &mut document);
+        match document {
+            serde_json::Value::Object(mut map) => {
+                map.remove("paywall_applied");
+                map
+            }
+            _ => serde_json::Map::new(),
+        }
+    };
+    let mut nodes: Vec<LineageNode> = Vec::new();
+    let mut documents: std::collections::HashMap<String, serde_json::Map<String, serde_json::Value>> =
+        std::collections::HashMap::new();
+    for node_id in &family {
+        if let Some(g) = guitars.iter().find(|g| guitar_node(g) == *node_id) {
+            let mut document = match serde_json::to_value(g) {
+                Ok(serde_json::Value::Object(map)) => map,
+                _ => serde_json::Map::new(),
+            };
+            let id_part = g.id.as_ref().map(specs::record_key).unwrap_or_default();
+            document.extend(specs::fetch_guitar_specs(&db, &id_part).await);
+            documents.insert(node_id.clone(), restrict(paywall::GUITAR_DETAIL, document));
+            nodes.push(LineageNode {
+                id: node_id.clone(),
+                kind: "guitar".to_string(),
+                label: g.get_display_title(),
+                slug: g.get_slug(),
+                production_year: g.production_year,
+                spec_version_year: g.spec_version_year,
+            });
+        } else if let Some(m) = models.iter().find(|m| model_node(m) == *node_id) {
+            let mut document = match serde_json::to_value(m) {
+                Ok(serde_json::Value::Object(map)) => restrict(paywall::GUITAR_MODEL, map),
+                _ => serde_json::Map::new(),
+            };
+            if let Some(model_specs) = document.remove("specs").and_then(|v| v.as_object().cloned()) {
+                document.extend(model_specs);
+            }
+            documents.insert(node_id.clone(), document);
+            nodes.push(LineageNode {
+                id: node_id.clone(),
+                kind: "model".to_string(),
+                label: format!("{} {}", m.brand, m.model),
+                slug: m.get_slug(),
+                production_year: None,
+                spec_version_year: m.spec_version_year,
+            });
+        }
+    }
+    nodes.sort_by_key(|n| (n.spec_version_year.or(n.production_year), n.production_year, n.label.clone()));
+
+    let empty = serde_json::Map::new();
+    let edges: Vec<LineageEdge> = family_edges
+        .into_iter()
+        .map(|edge| LineageEdge {
+            spec_delta: lineage::spec_delta(
+                documents.get(&edge.to).unwrap_or(&empty),
+                documents.get(&edge.from).unwrap_or(&empty),
+            ),
+            relation: edge.relation,
+            from: edge.from,
+            to: edge.to,
+            note: edge.note,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(LineageResponse {
+        root: root_id,
+        nodes,
+        edges,
+        paywall_applied,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/lineage",
+    tag = "lineage",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    request_body = LineageLinkRequest,
+    responses(
+        (status = 201, description = "Lineage edge created", body = serde_json::Value),
+        (status = 400, description = "Invalid relation or record ids", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/lineage")]
+async fn create_lineage_link(
+    _auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    body: web::Json<LineageLinkRequest>,
+) -> impl Responder {
+    let link = body.into_inner();
+
+    if !lineage::RELATIONS.contains(&link.relation.as_str()) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!("relation must be one of: {}", lineage::RELATIONS.join(", "))
+        }));
+    }
+    let parse_node = |rid: &str| {
+        rid.split_once(':')
+            .filter(|(table, key)| lineage::NODE_TABLES.contains(table) && !key.is_empty())
+            .map(|(table, key)| (table.to_string(), key.to_string()))
+    };
+    let (Some((from_tb, from_id)), Some((to_tb, to_id))) = (parse_node(&link.from), parse_node(&link.to)) else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "from and to must be guitars:<id> or guitar_models:<id> record ids"
+        }));
+    };
+    if link.from == link.to {
+        return HttpResponse::BadRequest().json(json!({"error": "A record cannot be linked to itself"}));
+    }
+
+    // The relation name is checked against RELATIONS above, so it is safe to inline
+    let query = format!(
+        "LET $from = type::thing($from_tb, $from_id);
+         LET $to = type::thing($to_tb, $to_id);
+         IF !record::exists($from) OR !record::exists($to) {{ THROW 'from or to record does not exist' }};
+         RELATE $from->{}->$to SET note = $note, created_at = time::now() RETURN NONE;",
+        link.relation
+    );
+    let res = db
+        .query(query)
+        .bind(("from_tb", from_tb))
+        .bind(("from_id", from_id))
+        .bind(("to_tb", to_tb))
+        .bind(("to_id", to_id))
+        .bind(("note", link.note.clone()))
+        .await
+        .and_then(|response| response.check());
+
+    match res {
+        Ok(_) => HttpResponse::Created().json(json!(link)),
+        Err(surrealdb::Error::Db(e)) => HttpResponse::BadRequest().json(json!({"error": e.to_string()})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tools/decode-serial",
+    tag = "tools",
+    request_body = SerialDecodeRequest,
+    responses(
+        (status = 200, description = "Decoded serial with inferred year, factory and ranking", body = SerialDecodeResponse),
+        (status = 400, description = "No serial given and none stored on the guitar", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/tools/decode-serial")]
+async fn decode_serial(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    decoder: web::Data<SerialDecoder>,
+    body: web::Json<SerialDecodeRequest>,
+) -> impl Responder {
+    let request = body.into_inner();
+
+    // Optionally look up the guitar to fill in serial/brand and check production_year
+    let guitar = match request.guitar_id.as_deref() {
+        Some(id_str) => {
+            let key = id_str.strip_prefix("guitars:").unwrap_or(id_str);
+            let rid = format!("guitars:{key}");
+            match specs::fetch_guitar(&db, key).await {
+                Ok(Some(guitar)) => Some((rid, guitar)),
+                Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": rid})),
+                Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+            }
+        }
+        None => None,
+    };
+
+    let serial = request
+        .serial
+        .clone()
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| guitar.as_ref().map(|(_, g)| g.serial_number.clone()).filter(|s| !s.trim().is_empty()));
+    let Some(serial) = serial else {
+        return HttpResponse::BadRequest().json(json!({"error": "No serial provided"}));
+    };
+    let brand = request.brand.clone().or_else(|| guitar.as_ref().map(|(_, g)| g.brand.clone()));
+
+    let info = decoder.decode(&serial, brand.as_deref(), request.country.as_deref());
+    let years = info.as_ref().map(|i| i.years.clone()).unwrap_or_default();
+    let confidence = match years.len() {
+        0 => "none",
+        1 => "high",
+        2..=3 => "medium",
+        _ => "low",
+    };
+
+    let consistency = guitar.as_ref().and_then(|(rid, g)| {
+        let (first, last) = (years.iter().min()?, years.iter().max()?);
+        let range = if first == last { first.to_string() } else { format!("{first}-{last}") };
+        let (consistent, message) = match g.production_year {
+            Some(year) if years.contains(&year) => (true, format!("Serial agrees with production_year {year}")),
+            Some(year) => (false, format!("Serial decodes to {range} but production_year is {year}")),
+            None => (true, format!("Serial decodes to {range}; no production_year stored")),
+        };
+        Some(SerialConsistency {
+            guitar_id: rid.clone(),
+            production_year: g.production_year,
+            consistent,
+            message,
+        })
+    });
+
+    HttpResponse::Ok().json(SerialDecodeResponse {
+        serial,
+        brand,
+        decoded: info.is_some(),
+        rule: info.as_ref().map(|i| i.rule.clone()),
+        year: (years.len() == 1).then(|| years[0]),
+        years,
+        factory: info.as_ref().and_then(|i| i.factory.clone()),
+        ranking: info.as_ref().and_then(|i| i.ranking),
+        day_of_year: info.as_ref().and_then(|i| i.day_of_year),
+        batch: info.as_ref().and_then(|i| i.batch),
+        confidence: confidence.to_string(),
+        notes: info.map(|i| i.notes).unwrap_or_default(),
+        consistency,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/guitars/{slug}/price-history",
+    tag = "guitars",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("slug" = String, Path, description = "Guitar slug (e.g., 'banker-58-spec-v')")
+    ),
+    responses(
+        (status = 200, description = "Price and status changes, oldest first, with the latest price change; below the subscriber tier only the most recent entries", body = PriceHistoryResponse),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/guitars/{slug}/price-history")]
+async fn get_price_history(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    path: web::Path<String>,
+) -> impl Responder {
+    let slug = path.into_inner();
+
+    let rows: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let Some(guitar) = rows.into_iter().find(|g| g.get_slug() == slug) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "slug": slug}));
+    };
+
+    let key = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+    let timeline = match MarketTimeline::load(&db, Some(&key)).await {
+        Ok(timeline) => timeline,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+
+    let history = PriceHistoryResponse {
+        guitar_id: guitar.id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+        slug: guitar.get_slug(),
+        entries: timeline
+            .history
+            .get(&key)
+            .map(|points| points.iter().map(|p| p.to_entry()).collect())
+            .unwrap_or_default(),
+        price_change: timeline.price_change(&key, &guitar, chrono::Utc::now()),
+        paywall_applied: false,
+    };
+    let mut body = serde_json::to_value(history).unwrap_or_default();
+    policy.apply_paywall_restrictions(paywall::PRICE_HISTORY, paywall::tier_of(caller.identity.as_ref()), &mut body);
+    HttpResponse::Ok().json(body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/market/stats",
+    tag = "market",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("brand" = Option<String>, Query, description = "Brand, case-insensitive (e.g., 'Gibson')"),
+        ("model" = Option<String>, Query, description = "Model, case-insensitive (e.g., 'Heritage Reissue Flying V')"),
+        ("spec_version_year" = Option<i32>, Query, description = "Spec year the guitar was built to (e.g., 1958)"),
+        ("currency" = Option<String>, Query, description = "Currency to convert prices to; defaults to the exchange-rate base")
+    ),
+    responses(
+        (status = 200, description = "Price percentiles, days on market and condition breakdown with the comparable listings; quartiles, days on market and most comparables need the subscriber tier", body = MarketStats),
+        (status = 400, description = "Missing filter, bad year or unsupported currency", body = ErrorResponse),
+        (status = 401, description = "Invalid or expired credentials", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/market/stats")]
+async fn get_market_stats(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    rates: web::Data<ExchangeRates>,
+    policy: web::Data<PaywallPolicy>,
+    caller: OptionalIdentity,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let text = |name: &str| query.get(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let spec_version_year = match text("spec_version_year").map(|y| y.parse::<i32>()) {
+        None => None,
+        Some(Ok(year)) => Some(year),
+        Some(Err(_)) => {
+            return HttpResponse::BadRequest().json(json!({"error": "spec_version_year must be a year"}))
+        }
+    };
+    let filter = MarketFilter {
+        brand: text("brand"),
+        model: text("model"),
+        spec_version_year,
+    };
+    if filter.brand.is_none() && filter.model.is_none() {
+        return HttpResponse::BadRequest().json(json!({"error": "brand or model is required"}));
+    }
+    let currency = money::normalize_currency(&text("currency").unwrap_or_else(|| rates.base.clone()));
+    if !rates.supports(&currency) {
+        return HttpResponse::BadRequest().json(json!({"error": format!("Unsupported currency: {currency}")}));
+    }
+
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let timeline = match MarketTimeline::load(&db, None).await {
+        Ok(timeline) => timeline,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+
+    let comparables = market::comparables(&guitars, &filter, &timeline, &rates, &currency, chrono::Utc::now());
+    let mut stats = serde_json::to_value(market::summarize(&filter, &currency, comparables)).unwrap_or_default();
+    policy.apply_paywall_restrictions(paywall::MARKET_STATS, paywall::tier_of(caller.identity.as_ref()), &mut stats);
+    HttpResponse::Ok().json(stats)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tools/estimate",
+    tag = "tools",
+    request_body = EstimateRequest,
+    responses(
+        (status = 200, description = "Estimated value range with confidence and the comparables used", body = EstimateResponse),
+        (status = 400, description = "Missing brand/model or unsupported currency", body = ErrorResponse),
+        (status = 404, description = "No comparable listings", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/tools/estimate")]
+async fn estimate_value(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    rates: web::Data<ExchangeRates>,
+    body: web::Json<EstimateRequest>,
+) -> impl Responder {
+    let request = body.into_inner();
+    if request.brand.trim().is_empty() || request.model.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({"error": "brand and model are required"}));
+    }
+    if let Some(code) = request.currency.as_deref() {
+        if !rates.supports(code) {
+            return HttpResponse::BadRequest().json(json!({"error": format!("Unsupported currency: {code}")}));
+        }
+    }
+
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+
+    match valuation::estimate(&request, &guitars, &rates) {
+        Some(estimate) => HttpResponse::Ok().json(estimate),
+        None => HttpResponse::NotFound().json(json!({
+            "error": "no comparable listings",
+            "brand": request.brand,
+            "model": request.model
+        })),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guitars/{id}/status",
+    tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    request_body = StatusTransitionRequest,
+    responses(
+        (status = 200, description = "Status changed and timestamped", body = StatusTransitionResponse),
+        (status = 400, description = "Unknown status or illegal transition", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 409, description = "Status was changed by another request", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/guitars/{id}/status")]
+async fn transition_status(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+    body: web::Json<StatusTransitionRequest>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/status");
+    let id_str = path.into_inner();
+    let rid = if id_str.contains(':') {
+        id_str
+    } else {
+        format!("guitars:{id_str}")
+    };
+    let Some(target) = ListingStatus::parse(&body.status) else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!("Unknown status: {}", body.status),
+            "allowed": ListingStatus::ALL
+        }));
+    };
+
+    let rows: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let Some(guitar) = rows.into_iter().find(|g| g.id.as_ref().map(|id| id.to_string()) == Some(rid.clone())) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "id": rid}));
+    };
+
+    // Guitars without a status are treated as drafts; unrecognized legacy values may move anywhere
+    let current = match guitar.status.as_deref() {
+        None => Some(ListingStatus::Draft),
+        Some(raw) => ListingStatus::parse(raw),
+    };
+    if let Some(current) = current {
+        if !current.can_transition_to(target) {
+            return HttpResponse::BadRequest().json(json!({
+                "error": format!("Cannot move from {} to {}", current.as_str(), target.as_str()),
+                "allowed": current.next()
+            }));
+        }
+    }
+
+    let changed_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let key = guitar.id.as_ref().map(specs::record_key).unwrap_or_default();
+    let res = db
+        .query(
+            "UPDATE type::thing('guitars', $key) SET
+                status = $status,
+                status_changed_at = <datetime> $changed_at,
+                sold_at = IF $status = 'Sold' THEN <datetime> $changed_at ELSE sold_at END
+             WHERE status = $current
+             RETURN VALUE record::id(id)",
+        )
+        .bind(("key", key))
+        .bind(("status", target.as_str()))
+        .bind(("changed_at", changed_at.clone()))
+        .bind(("current", guitar.status.clone()))
+        .bind(("audit", audit))
+        .await
+        .and_then(|response| response.check());
+
+    let updated: Vec<String> = match res.and_then(|mut response| response.take(0)) {
+        Ok(updated) => updated,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    if updated.is_empty() {
+        return HttpResponse::Conflict().json(json!({"error": "status was changed by another request", "id": rid}));
+    }
+
+    HttpResponse::Ok().json(StatusTransitionResponse {
+        id: rid,
+        from: guitar.status,
+        to: target,
+        changed_at,
+        allowed_next: target.next().to_vec(),
+    })
+}
+
+/// Compact listing card used in model, shop and brand responses
+fn listing_summary(g: &Guitar) -> serde_json::Value {
+    json!({
+        "id": g.id.as_ref().map(|id| id.to_string()),
+        "slug": g.get_slug(),
+        "display_title": g.get_display_title(),
+        "production_year": g.production_year,
+        "condition": g.condition,
+        "status": g.status,
+        "formatted_price": g.get_formatted_price(),
+        "main_image": g.get_main_image(),
+    })
+}
+
+/// Linked through shop_ref, or by shop_slug for rows imported before shops existed
+fn sold_by(shop: &Shop, guitar: &Guitar) -> bool {
+    match &guitar.shop_ref {
+        Some(shop_ref) => shop.id.as_ref() == Some(shop_ref),
+        None => guitar.shop_slug.as_deref().is_some_and(|slug| slug == shop.get_slug()),
+    }
+}
+
+/// Linked through brand_ref, or by brand name for rows imported before brands existed
+fn made_by(brand: &Brand, guitar: &Guitar) -> bool {
+    match &guitar.brand_ref {
+        Some(brand_ref) => brand.id.as_ref() == Some(brand_ref),
+        None => guitar.brand.trim().eq_ignore_ascii_case(brand.name.trim()),
+    }
+}
+
+/// Requested currency if supported, else the profile's own, else the rate table's base
+fn display_currency(rates: &ExchangeRates, requested: Option<&String>, profile: Option<&str>) -> String {
+    requested
+        .map(|c| money::normalize_currency(c))
+        .into_iter()
+        .chain(profile.map(money::normalize_currency))
+        .find(|c| rates.supports(c))
+        .unwrap_or_else(|| rates.base.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/shops",
+    tag = "shops",
+    responses(
+        (status = 200, description = "Dealer and shop profiles with their inventory counts", body = Vec<Shop>),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/shops")]
+async fn list_shops(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+) -> impl Responder {
+    let shops: Vec<Shop> = match db.select("shops").await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+
+    let mut shops_json: Vec<serde_json::Value> = shops
+        .iter()
+        .map(|shop| {
+            let mut shop_json = serde_json::to_value(shop).unwrap_or_default();
+            if let serde_json::Value::Object(ref mut map) = shop_json {
+                map.insert("slug".to_string(), serde_json::Value::String(shop.get_slug()));
+                map.insert("inventory_count".to_string(), json!(guitars.iter().filter(|g| sold_by(shop, g)).count()));
+            }
+            shop_json
+        })
+        .collect();
+    shops_json.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    HttpResponse::Ok().json(shops_json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/shops/{slug}",
+    tag = "shops",
+    params(
+        ("slug" = String, Path, description = "Shop slug (e.g., 'carter-vintage')"),
+        ("currency" = Option<String>, Query, description = "Currency for the inventory price range; defaults to the shop's currency")
+    ),
+    responses(
+        (status = 200, description = "Shop profile with its inventory and price range", body = serde_json::Value),
+        (status = 404, description = "Shop not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/shops/{slug}")]
+async fn get_shop_by_slug(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    rates: web::Data<ExchangeRates>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let slug = path.into_inner();
+
+    let shops: Vec<Shop> = match db.select("shops").await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let Some(shop) = shops.into_iter().find(|s| s.get_slug() == slug) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "slug": slug}));
+    };
+
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let inventory: Vec<&Guitar> = guitars.iter().filter(|g| sold_by(&shop, g)).collect();
+    let currency = display_currency(&rates, query.get("currency"), shop.currency.as_deref());
+
+    let mut shop_json = serde_json::to_value(&shop).unwrap_or_default();
+    if let serde_json::Value::Object(ref mut map) = shop_json {
+        map.insert("slug".to_string(), serde_json::Value::String(shop.get_slug()));
+        map.insert("inventory_count".to_string(), json!(inventory.len()));
+        map.insert("price_range".to_string(), json!(market::price_range(inventory.iter().copied(), &rates, &currency)));
+        map.insert("inventory".to_string(), inventory.iter().map(|g| listing_summary(g)).collect());
+    }
+    HttpResponse::Ok().json(shop_json)
+}
 
-                    if let Ok(mut pickups_resp) = pickups_res {
-                        if let Ok(pickups) = pickups_resp.take::<Vec<serde_json::Value>>(0) {
-                            if !pickups.is_empty() {
-                                map.insert("pickups".to_string(), serde_json::Value::Array(pickups));
-                            }
-                        }
-                    }
+#[utoipa::path(
+    get,
+    path = "/api/brands/{slug}",
+    tag = "brands",
+    params(
+        ("slug" = String, Path, description = "Brand slug (e.g., 'nik-huber')"),
+        ("currency" = Option<String>, Query, description = "Currency for price ranges; defaults to the brand's currency")
+    ),
+    responses(
+        (status = 200, description = "Brand profile with its models and their listing price ranges", body = serde_json::Value),
+        (status = 404, description = "Brand not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/api/brands/{slug}")]
+async fn get_brand_by_slug(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    rates: web::Data<ExchangeRates>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let slug = path.into_inner();
 
-                    if let Ok(mut controls_resp) = controls_res {
-                        if let Ok(controls) = controls_resp.take::<Vec<serde_json::Value>>(0) {
-                            if !controls.is_empty() {
-                                map.insert("controls".to_string(), serde_json::Value::Array(controls));
-                            }
-                        }
-                    }
-                }
+    let brands: Vec<Brand> = match db.select("brands").await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let Some(brand) = brands.into_iter().find(|b| b.get_slug() == slug) else {
+        return HttpResponse::NotFound().json(json!({"error": "not found", "slug": slug}));
+    };
 
-                HttpResponse::Ok().json(guitar_json)
-            } else {
-                HttpResponse::NotFound().json(serde_json::json!({"error": "not found", "id": rid}))
-            }
+    let guitars: Vec<Guitar> = match specs::fetch_guitars(&db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
         }
+    };
+    let catalog: Vec<GuitarModel> = match db.select("guitar_models").await {
+        Ok(rows) => rows,
         Err(e) => {
-            HttpResponse::InternalServerError().json(serde_json::json!({"error": e.to_string()}))
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
         }
+    };
+    let listings: Vec<&Guitar> = guitars.iter().filter(|g| made_by(&brand, g)).collect();
+    let currency = display_currency(&rates, query.get("currency"), brand.currency.as_deref());
+
+    // One entry per model and spec year, whether it has a catalog entry, listings or both
+    let mut models: std::collections::BTreeMap<(String, Option<i32>), Option<&GuitarModel>> = std::collections::BTreeMap::new();
+    for model in catalog.iter().filter(|m| m.brand.trim().eq_ignore_ascii_case(brand.name.trim())) {
+        models.insert((model.model.clone(), model.spec_version_year), Some(model));
+    }
+    for guitar in &listings {
+        models.entry((guitar.model.clone(), guitar.spec_version_year)).or_insert(None);
     }
+    let models_json: Vec<serde_json::Value> = models
+        .into_iter()
+        .map(|((model, spec_version_year), catalog_model)| {
+            let model_listings: Vec<&Guitar> = listings
+                .iter()
+                .copied()
+                .filter(|g| match catalog_model {
+                    Some(m) if g.model_ref.is_some() => g.model_ref == m.id,
+                    _ => g.model == model && g.spec_version_year == spec_version_year,
+                })
+                .collect();
+            json!({
+                "model": model,
+                "spec_version_year": spec_version_year,
+                "catalog_slug": catalog_model.map(GuitarModel::get_slug),
+                "listing_count": model_listings.len(),
+                "price_range": market::price_range(model_listings.iter().copied(), &rates, &currency),
+            })
+        })
+        .collect();
+
+    let mut brand_json = serde_json::to_value(&brand).unwrap_or_default();
+    if let serde_json::Value::Object(ref mut map) = brand_json {
+        map.insert("slug".to_string(), serde_json::Value::String(brand.get_slug()));
+        map.insert("listing_count".to_string(), json!(listings.len()));
+        map.insert("price_range".to_string(), json!(market::price_range(listings.iter().copied(), &rates, &currency)));
+        map.insert("models".to_string(), serde_json::Value::Array(models_json));
+    }
+    HttpResponse::Ok().json(brand_json)
 }
 
 #[get("/api/debug/woods")]
@@ -502,111 +2670,693 @@ async fn list_images(
 }
 
 #[utoipa::path(
-    put,
+    get,
     path = "/api/guitars/{id}/images",
-    tag = "guitars",
+    tag = "images",
     params(
         ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
     ),
-    request_body = ImageUpdateRequest,
     responses(
-        (status = 200, description = "Guitar images updated successfully", body = serde_json::Value),
-        (status = 400, description = "Bad request - invalid image data", body = ErrorResponse),
+        (status = 200, description = "The guitar's images in display order", body = Vec<Image>),
         (status = 404, description = "Guitar not found", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[put("/api/guitars/{id}/images")]
-async fn update_guitar_images(
+#[get("/api/guitars/{id}/images")]
+async fn list_guitar_images(
     db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
     path: web::Path<String>,
-    body: web::Json<serde_json::Value>,
 ) -> impl Responder {
     let id_str = path.into_inner();
-    let rid = if id_str.contains(':') {
-        id_str
-    } else {
-        format!("guitars:{id_str}")
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
+
+    match specs::fetch_guitar(&db, key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+    match images::fetch_for_guitar(&db, key).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guitars/{id}/images",
+    tag = "images",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    request_body = ImageCreateRequest,
+    responses(
+        (status = 201, description = "Image added", body = Image),
+        (status = 400, description = "Missing src", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/guitars/{id}/images")]
+async fn add_guitar_image(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+    body: web::Json<ImageCreateRequest>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/images");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+    let request = body.into_inner();
+    if request.src.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({"error": "src is required"}));
+    }
+
+    let guitar: surrealdb::Result<Option<Guitar>> = specs::fetch_guitar(&db, &key).await;
+    match guitar {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+
+    match images::add(&db, &key, request, &audit).await {
+        Ok(Some(image)) => HttpResponse::Created().json(image),
+        Ok(None) => HttpResponse::InternalServerError().json(json!({"error": "image was not created"})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+/// HTTP response for a failed image ingest
+fn ingest_error_response(error: IngestError) -> HttpResponse {
+    match error {
+        IngestError::TooLarge { .. } => HttpResponse::PayloadTooLarge().json(json!({"error": error.to_string()})),
+        IngestError::Unsupported(_) => HttpResponse::BadRequest().json(json!({"error": error.to_string()})),
+        IngestError::Download(_) => HttpResponse::BadGateway().json(json!({"error": error.to_string()})),
+        IngestError::Duplicate { ref image, distance } => HttpResponse::Conflict().json(json!({
+            "error": "duplicate image",
+            "duplicate_of": image.id.as_ref().map(|id| id.to_string()),
+            "guitar_id": image.guitar_id.as_ref().map(|id| id.to_string()),
+            "src": image.src,
+            "distance": distance
+        })),
+        IngestError::Storage(_) | IngestError::Database(_) | IngestError::NotRecorded => {
+            HttpResponse::InternalServerError().json(json!({"error": error.to_string()}))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guitars/{id}/images/ingest",
+    tag = "images",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    request_body = ImageIngestRequest,
+    responses(
+        (status = 201, description = "Image downloaded, cleaned of EXIF data, stored with thumbnails and added", body = Image),
+        (status = 400, description = "Missing url or not a supported image", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 409, description = "A near-identical image is already stored (on any guitar)", body = ErrorResponse),
+        (status = 413, description = "Image is larger than IMAGE_MAX_BYTES", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 502, description = "The image could not be downloaded", body = ErrorResponse)
+    )
+)]
+#[post("/api/guitars/{id}/images/ingest")]
+async fn ingest_guitar_image(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    storage: web::Data<ImageStorage>,
+    settings: web::Data<IngestSettings>,
+    path: web::Path<String>,
+    body: web::Json<ImageIngestRequest>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/images/ingest");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+    let request = body.into_inner();
+    if request.url.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({"error": "url is required"}));
+    }
+
+    let guitar: surrealdb::Result<Option<Guitar>> = specs::fetch_guitar(&db, &key).await;
+    match guitar {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+
+    let bytes = match ingest::download(request.url.trim(), settings.max_bytes).await {
+        Ok(bytes) => bytes,
+        Err(e) => return ingest_error_response(e),
+    };
+    let options = IngestOptions {
+        alt: request.alt,
+        role: request.role,
+        source: request.source.or(Some("ingested".to_string())),
+        position: request.position,
     };
+    match ingest::ingest(&db, &storage, &settings, &key, bytes, options, &audit).await {
+        Ok(image) => HttpResponse::Created().json(image),
+        Err(e) => ingest_error_response(e),
+    }
+}
 
-    // Extract image data from request body
-    let hero_image_url = body.get("hero_image_url").and_then(|v| v.as_str());
-    let image_gallery = body.get("image_gallery").and_then(|v| v.as_array());
-    let image_source = body.get("image_source").and_then(|v| v.as_str());
-    let condition = body.get("condition").and_then(|v| v.as_str());
-    let status = body.get("status").and_then(|v| v.as_str());
+/// A file part of an upload, read into memory
+struct UploadedFile {
+    filename: Option<String>,
+    bytes: Vec<u8>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guitars/{id}/images/upload",
+    tag = "images",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    request_body(content = ImageUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Files stored with EXIF removed and thumbnails generated; duplicates are listed under rejected", body = ImageUploadResponse),
+        (status = 400, description = "No files, too many files, or a file that is not the image it claims to be", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 409, description = "Every file duplicates an image already stored", body = ErrorResponse),
+        (status = 413, description = "A file is larger than IMAGE_MAX_BYTES", body = ErrorResponse),
+        (status = 415, description = "A file's type is not in IMAGE_ALLOWED_TYPES", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/api/guitars/{id}/images/upload")]
+async fn upload_guitar_images(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    storage: web::Data<ImageStorage>,
+    settings: web::Data<IngestSettings>,
+    path: web::Path<String>,
+    mut payload: Multipart,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "POST /api/guitars/{id}/images/upload");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+
+    let guitar: surrealdb::Result<Option<Guitar>> = specs::fetch_guitar(&db, &key).await;
+    match guitar {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+
+    // Read and validate every part before storing anything
+    let mut files: Vec<UploadedFile> = Vec::new();
+    let mut alt = None;
+    let mut role = None;
+    let mut source = None;
+    while let Some(item) = payload.next().await {
+        let mut field = match item {
+            Ok(field) => field,
+            Err(e) => return HttpResponse::BadRequest().json(json!({"error": e.to_string()})),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        let filename = field.content_disposition().and_then(|d| d.get_filename()).map(str::to_string);
+        let declared = field.content_type().map(|mime| mime.essence_str().to_lowercase());
+
+        let is_file = filename.is_some() || name == "file";
+        if is_file {
+            if files.len() == settings.max_files {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": format!("at most {} files per upload", settings.max_files)
+                }));
+            }
+            if !declared.as_ref().is_some_and(|t| settings.allowed_types.contains(t)) {
+                return HttpResponse::UnsupportedMediaType().json(json!({
+                    "error": "file type not allowed",
+                    "filename": filename,
+                    "content_type": declared,
+                    "allowed": settings.allowed_types
+                }));
+            }
+        }
 
-    // Build update query
-    let mut update_fields = Vec::new();
+        let limit = if is_file { settings.max_bytes } else { 4096 };
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => return HttpResponse::BadRequest().json(json!({"error": e.to_string()})),
+            };
+            if bytes.len() + chunk.len() > limit {
+                return HttpResponse::PayloadTooLarge().json(json!({
+                    "error": format!("{name} is larger than {limit} bytes"),
+                    "filename": filename
+                }));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
 
-    if let Some(hero_url) = hero_image_url {
-        update_fields.push(format!("hero_image_url = \"{}\"", hero_url));
+        if is_file {
+            // The declared type must match what the bytes actually are
+            let detected = ingest::sniff_content_type(&bytes);
+            if detected.is_none() || detected.map(str::to_string) != declared {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "file is not the image type it claims to be",
+                    "filename": filename,
+                    "content_type": declared,
+                    "detected": detected
+                }));
+            }
+            files.push(UploadedFile { filename, bytes });
+            continue;
+        }
+        let value = String::from_utf8_lossy(&bytes).trim().to_string();
+        match name.as_str() {
+            "alt" if !value.is_empty() => alt = Some(value),
+            "source" if !value.is_empty() => source = Some(value),
+            "role" => match serde_json::from_value::<ImageRole>(json!(value.to_lowercase())) {
+                Ok(parsed) => role = Some(parsed),
+                Err(_) => {
+                    return HttpResponse::BadRequest().json(json!({
+                        "error": "invalid role",
+                        "allowed": ["hero", "gallery", "detail"]
+                    }))
+                }
+            },
+            _ => {}
+        }
+    }
+    if files.is_empty() {
+        return HttpResponse::BadRequest().json(json!({"error": "no files uploaded; send them as 'file' parts"}));
     }
 
-    if let Some(gallery) = image_gallery {
-        let gallery_strings: Vec<String> = gallery
-            .iter()
-            .filter_map(|v| v.as_str())
-            .map(|s| format!("\"{}\"", s))
-            .collect();
-        if !gallery_strings.is_empty() {
-            update_fields.push(format!("image_gallery = [{}]", gallery_strings.join(", ")));
+    let mut created = Vec::new();
+    let mut rejected = Vec::new();
+    let mut first_error = None;
+    for (index, file) in files.into_iter().enumerate() {
+        let options = IngestOptions {
+            alt: alt.clone(),
+            role: if index == 0 { role } else { None },
+            source: source.clone().or(Some("upload".to_string())),
+            position: None,
+        };
+        match ingest::ingest(&db, &storage, &settings, &key, file.bytes, options, &audit).await {
+            Ok(image) => created.push(image),
+            Err(e @ (IngestError::Duplicate { .. } | IngestError::Unsupported(_))) => {
+                let duplicate_of = match &e {
+                    IngestError::Duplicate { image, .. } => image.id.as_ref().map(|id| id.to_string()),
+                    _ => None,
+                };
+                rejected.push(UploadRejection {
+                    filename: file.filename,
+                    error: e.to_string(),
+                    duplicate_of,
+                });
+                first_error.get_or_insert(e);
+            }
+            Err(e) => return ingest_error_response(e),
         }
     }
 
-    if let Some(source) = image_source {
-        update_fields.push(format!("image_source = \"{}\"", source));
+    match first_error {
+        Some(e) if created.is_empty() => ingest_error_response(e),
+        _ => HttpResponse::Created().json(ImageUploadResponse { images: created, rejected }),
+    }
+}
+
+/// Cache lifetime of proxied renditions; clients revalidate with the ETag afterwards
+const PROXY_MAX_AGE: u32 = 7 * 24 * 60 * 60;
+
+/// Placeholder image served when the original can't be fetched or decoded; drawn off the async
+/// workers once per size and format, then served from the cache
+async fn placeholder_response(mut response: actix_web::HttpResponseBuilder, cache: &ImageCache, request: RenderRequest) -> HttpResponse {
+    let name = request.placeholder_cache_name();
+    let bytes = match cache.get(&name).await {
+        Some(bytes) => bytes,
+        None => {
+            let bytes = web::block(move || image_proxy::placeholder(request)).await.unwrap_or_default();
+            if !bytes.is_empty() {
+                cache.put(&name, &bytes).await;
+            }
+            bytes
+        }
+    };
+    response
+        .content_type(image_proxy::content_type(request.format))
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .insert_header(("X-Image-Fallback", "placeholder"))
+        .body(bytes)
+}
+
+#[utoipa::path(
+    get,
+    path = "/img/{image_id}",
+    tag = "images",
+    params(
+        ("image_id" = String, Path, description = "Image ID (e.g., 'images:abc' or 'abc')"),
+        ("w" = Option<u32>, Query, description = "Fit within this width (never upscaled)"),
+        ("h" = Option<u32>, Query, description = "Fit within this height (never upscaled)"),
+        ("fmt" = Option<String>, Query, description = "jpeg (default), png, webp, or avif when built with the avif feature")
+    ),
+    responses(
+        (status = 200, description = "The image, resized; a striped placeholder (X-Image-Fallback: placeholder) when the remote image can't be fetched (at most 640px on either side)", content_type = "image/*"),
+        (status = 304, description = "Not modified (If-None-Match matched the ETag)"),
+        (status = 400, description = "Invalid size or format", body = ErrorResponse),
+        (status = 404, description = "No such image, or its guitar is in the trash; the body is a placeholder image", content_type = "image/*"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/img/{image_id}")]
+async fn proxy_image(
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    cache: web::Data<ImageCache>,
+    storage: web::Data<ImageStorage>,
+    settings: web::Data<IngestSettings>,
+    http_request: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let key = images::image_key(&path.into_inner());
+
+    let mut dimensions = [None, None];
+    for (slot, name) in dimensions.iter_mut().zip(["w", "h"]) {
+        if let Some(raw) = query.get(name) {
+            match raw.parse::<u32>() {
+                Ok(value) if value > 0 && value <= cache.max_dimension => *slot = Some(value),
+                _ => {
+                    return HttpResponse::BadRequest().json(json!({
+                        "error": format!("{name} must be between 1 and {}", cache.max_dimension)
+                    }))
+                }
+            }
+        }
     }
+    let format = match query.get("fmt").map(|raw| image_proxy::parse_format(raw)) {
+        None => "jpg",
+        Some(Some(format)) => format,
+        Some(None) => {
+            let mut allowed = vec!["jpeg", "png", "webp"];
+            if cfg!(feature = "avif") {
+                allowed.push("avif");
+            }
+            return HttpResponse::BadRequest().json(json!({"error": "unsupported fmt", "allowed": allowed}));
+        }
+    };
+    let request = RenderRequest {
+        width: dimensions[0],
+        height: dimensions[1],
+        format,
+    };
+
+    // Images of trashed guitars are not served, like the guitars themselves
+    let image = match images::fetch_visible(&db, &key).await {
+        Ok(Some(image)) => image,
+        Ok(None) => return placeholder_response(HttpResponse::NotFound(), &cache, request).await,
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    };
+
+    let base = image_proxy::cache_base(&key, &image.src);
+    let rendition_name = request.cache_name(&base);
+    let etag = format!("\"{rendition_name}\"");
+    let cached_response = |bytes: Vec<u8>| {
+        HttpResponse::Ok()
+            .content_type(image_proxy::content_type(format))
+            .insert_header((header::CACHE_CONTROL, format!("public, max-age={PROXY_MAX_AGE}")))
+            .insert_header((header::ETAG, etag.clone()))
+            .body(bytes)
+    };
 
-    if let Some(cond) = condition {
-        update_fields.push(format!("condition = \"{}\"", cond));
+    let if_none_match = http_request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if if_none_match.is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*")) {
+        return HttpResponse::NotModified()
+            .insert_header((header::CACHE_CONTROL, format!("public, max-age={PROXY_MAX_AGE}")))
+            .insert_header((header::ETAG, etag.clone()))
+            .finish();
     }
+    if let Some(bytes) = cache.get(&rendition_name).await {
+        return cached_response(bytes);
+    }
+
+    // Fetch the original once; images stored on this server are read from disk
+    let original_name = format!("{base}.orig");
+    let original = match cache.get(&original_name).await {
+        Some(bytes) => bytes,
+        None => {
+            let fetched = match storage.local_path(&image.src) {
+                Some(file) => tokio::fs::read(&file).await.map_err(|e| e.to_string()),
+                None => ingest::download(&image.src, settings.max_bytes).await.map_err(|e| e.to_string()),
+            };
+            match fetched {
+                Ok(bytes) => {
+                    cache.put(&original_name, &bytes).await;
+                    bytes
+                }
+                Err(e) => {
+                    log::warn!("Image proxy could not fetch {}: {e}", image.src);
+                    return placeholder_response(HttpResponse::Ok(), &cache, request).await;
+                }
+            }
+        }
+    };
 
-    if let Some(stat) = status {
-        update_fields.push(format!("status = \"{}\"", stat));
+    match web::block(move || image_proxy::render(&original, request)).await {
+        Ok(Ok(bytes)) => {
+            cache.put(&rendition_name, &bytes).await;
+            cached_response(bytes)
+        }
+        Ok(Err(e)) => {
+            log::warn!("Image proxy could not render {}: {e}", image.src);
+            placeholder_response(HttpResponse::Ok(), &cache, request).await
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
     }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/guitars/{id}/images/order",
+    tag = "images",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    request_body = ImageReorderRequest,
+    responses(
+        (status = 200, description = "Images in their new order", body = Vec<Image>),
+        (status = 400, description = "image_ids is not exactly the guitar's images", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[put("/api/guitars/{id}/images/order")]
+async fn reorder_guitar_images(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<String>,
+    body: web::Json<ImageReorderRequest>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "PUT /api/guitars/{id}/images/order");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
 
-    if update_fields.is_empty() {
+    match specs::fetch_guitar(&db, key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+    let current = match images::fetch_for_guitar(&db, key).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    };
+    let mut current_keys: Vec<String> = current.iter().filter_map(|i| i.id.as_ref().map(specs::record_key)).collect();
+    let ordered: Vec<String> = body.image_ids.iter().map(|id| images::image_key(id)).collect();
+    let mut requested = ordered.clone();
+    current_keys.sort();
+    requested.sort();
+    if current_keys != requested {
         return HttpResponse::BadRequest().json(json!({
-            "error": "No valid image fields provided"
+            "error": "image_ids must list every image of the guitar exactly once",
+            "image_ids": current_keys.iter().map(|k| format!("images:{k}")).collect::<Vec<_>>()
         }));
     }
 
-    update_fields.push("updated_at = time::now()".to_string());
-    let update_query = format!("UPDATE {} SET {}", rid, update_fields.join(", "));
+    if let Err(e) = images::reorder(&db, key, &ordered, &audit).await {
+        return HttpResponse::InternalServerError().json(json!({"error": e.to_string()}));
+    }
+    match images::fetch_for_guitar(&db, key).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/guitars/{id}/images/{image_id}",
+    tag = "images",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')"),
+        ("image_id" = String, Path, description = "Image ID (e.g., 'images:abc' or 'abc')")
+    ),
+    responses(
+        (status = 204, description = "Image removed and remaining images renumbered"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found, or image not found on this guitar", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[delete("/api/guitars/{id}/images/{image_id}")]
+async fn remove_guitar_image(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "DELETE /api/guitars/{id}/images/{image_id}");
+    let (id_str, image_id) = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str);
+    let image = images::image_key(&image_id);
 
-    // Execute update
-    let res: surrealdb::Result<surrealdb::Response> = db.query(update_query).await;
+    match specs::fetch_guitar(&db, key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+    match images::remove(&db, key, &image, &audit).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("images:{image}")})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+}
 
-    match res {
-        Ok(_) => {
-            // Return updated guitar data
-            let guitar_res: surrealdb::Result<Vec<Guitar>> = db.select(rid.as_str()).await;
-            match guitar_res {
-                Ok(rows) if !rows.is_empty() => {
-                    let guitar = &rows[0];
-                    let mut guitar_json = serde_json::to_value(guitar).unwrap_or_default();
-                    if let serde_json::Value::Object(ref mut map) = guitar_json {
-                        map.insert("slug".to_string(), serde_json::Value::String(guitar.get_slug()));
-                        map.insert("display_title".to_string(), serde_json::Value::String(guitar.get_display_title()));
-                        map.insert("formatted_price".to_string(), serde_json::Value::String(guitar.get_formatted_price()));
-                        map.insert("main_image".to_string(), serde_json::Value::String(guitar.get_main_image().unwrap_or_default()));
-                        map.insert("has_images".to_string(), serde_json::Value::Bool(guitar.has_images()));
-                        map.insert("image_count".to_string(), serde_json::Value::Number(serde_json::Number::from(guitar.get_image_count())));
-                        map.insert("condition_color".to_string(), serde_json::Value::String(guitar.get_condition_color().to_string()));
-                        map.insert("status_color".to_string(), serde_json::Value::String(guitar.get_status_color().to_string()));
-                    }
-                    HttpResponse::Ok().json(guitar_json)
-                }
-                _ => HttpResponse::NotFound().json(json!({"error": "Guitar not found after update"}))
-            }
+#[utoipa::path(
+    put,
+    path = "/api/guitars/{id}/images",
+    tag = "guitars",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = String, Path, description = "Guitar ID (e.g., 'guitars:123' or '123')")
+    ),
+    request_body = ImageUpdateRequest,
+    responses(
+        (status = 200, description = "Guitar images updated; only the images of the roles sent (hero_image_url, image_gallery) are replaced", body = serde_json::Value),
+        (status = 400, description = "Invalid image data or condition, or a status (use POST /api/guitars/{id}/status); nothing is written", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Requires the editor role", body = ErrorResponse),
+        (status = 404, description = "Guitar not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[put("/api/guitars/{id}/images")]
+async fn update_guitar_images(
+    auth: RequireEditor,
+    db: web::Data<Surreal<surrealdb::engine::remote::http::Client>>,
+    storage: web::Data<ImageStorage>,
+    path: web::Path<String>,
+    body: web::Json<serde_json::Value>,
+) -> impl Responder {
+    let audit = AuditContext::for_identity(&auth.identity, "PUT /api/guitars/{id}/images");
+    let id_str = path.into_inner();
+    let key = id_str.strip_prefix("guitars:").unwrap_or(&id_str).to_string();
+
+    // Status changes go through the transition checks and timestamps of the status endpoint
+    if body.get("status").is_some() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "status cannot be set here; use POST /api/guitars/{id}/status"
+        }));
+    }
+
+    // Validate every field before anything is written
+    let hero = match body.get("hero_image_url") {
+        None => None,
+        Some(serde_json::Value::Null) => Some(None),
+        Some(serde_json::Value::String(url)) => Some(Some(url.trim().to_string())),
+        Some(_) => return HttpResponse::BadRequest().json(json!({"error": "hero_image_url must be a string or null"})),
+    };
+    let gallery = match body.get("image_gallery") {
+        None => None,
+        Some(serde_json::Value::Array(urls)) if urls.iter().all(|url| url.is_string()) => {
+            Some(urls.iter().filter_map(|url| url.as_str()).map(|url| url.trim().to_string()).collect::<Vec<_>>())
+        }
+        Some(_) => return HttpResponse::BadRequest().json(json!({"error": "image_gallery must be an array of strings"})),
+    };
+    let image_source = match body.get("image_source") {
+        None => None,
+        Some(serde_json::Value::String(source)) => Some(source.clone()),
+        Some(_) => return HttpResponse::BadRequest().json(json!({"error": "image_source must be a string"})),
+    };
+    let condition = match body.get("condition").map(|cond| cond.as_str().and_then(Condition::parse).ok_or(cond)) {
+        None => None,
+        Some(Ok(grade)) => Some(grade.label().to_string()),
+        Some(Err(cond)) => {
+            return HttpResponse::BadRequest().json(json!({"error": format!("Unknown condition: {cond}"), "allowed": Condition::ALL}));
         }
+    };
+    if hero.is_none() && gallery.is_none() && image_source.is_none() && condition.is_none() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "No valid image fields provided"
+        }));
+    }
+
+    // Trashed guitars are not editable, same as the other image write endpoints
+    match specs::fetch_guitar(&db, &key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    }
+
+    // hero_image_url/image_gallery are derived from the guitar's images records, so only the
+    // records of the role that was sent are replaced, together with the guitar fields
+    let update = images::LegacyImageUpdate {
+        hero,
+        gallery,
+        image_source,
+        condition,
+    };
+    let removed = match images::apply_legacy_update(&db, &key, update, &audit).await {
+        Ok(removed) => removed,
         Err(e) => {
-            HttpResponse::InternalServerError().json(json!({
+            return HttpResponse::InternalServerError().json(json!({
                 "error": "Failed to update guitar images",
                 "details": e.to_string()
             }))
         }
+    };
+    for image in &removed {
+        images::delete_stored_assets(&storage, image).await;
+    }
+
+    // Return updated guitar data
+    match specs::fetch_guitar(&db, &key).await {
+        Ok(Some(guitar)) => {
+            let mut guitar_json = serde_json::to_value(&guitar).unwrap_or_default();
+            if let serde_json::Value::Object(ref mut map) = guitar_json {
+                map.insert("slug".to_string(), serde_json::Value::String(guitar.get_slug()));
+                map.insert("display_title".to_string(), serde_json::Value::String(guitar.get_display_title()));
+                map.insert("formatted_price".to_string(), serde_json::Value::String(guitar.get_formatted_price()));
+                map.insert("main_image".to_string(), serde_json::Value::String(guitar.get_main_image().unwrap_or_default()));
+                map.insert("has_images".to_string(), serde_json::Value::Bool(guitar.has_images()));
+                map.insert("image_count".to_string(), serde_json::Value::Number(serde_json::Number::from(guitar.get_image_count())));
+                map.insert("condition_color".to_string(), serde_json::Value::String(guitar.get_condition_color().to_string()));
+                map.insert("status_color".to_string(), serde_json::Value::String(guitar.get_status_color().to_string()));
+            }
+            HttpResponse::Ok().json(guitar_json)
+        }
+        Ok(None) => HttpResponse::NotFound().json(json!({"error": "not found", "id": format!("guitars:{key}")})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
     }
 }
 
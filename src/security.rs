@@ -0,0 +1,235 @@
+use std::env;
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+
+use crate::config::Profile;
+
+/// Who may call one group of routes from a browser on another origin
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    /// Allowed origins (`scheme://host[:port]`); `*` allows any
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+}
+
+impl CorsPolicy {
+    fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods.iter().any(|allowed| allowed.eq_ignore_ascii_case(method))
+    }
+
+    fn allows_headers(&self, requested: &str) -> bool {
+        requested
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .all(|name| self.allowed_headers.iter().any(|allowed| allowed.eq_ignore_ascii_case(name)))
+    }
+}
+
+/// CORS policies per route group: public reads (GET/HEAD outside `/api/admin`) and admin
+/// writes (every other method, and all of `/api/admin`)
+#[derive(Debug, Clone)]
+pub struct CorsSettings {
+    pub public_read: CorsPolicy,
+    pub admin_write: CorsPolicy,
+    /// How long browsers may cache a preflight answer, in seconds
+    pub max_age: u32,
+}
+
+/// Response headers browsers may read cross-origin
+const EXPOSED_HEADERS: &str =
+    "ETag, Retry-After, RateLimit-Limit, RateLimit-Remaining, RateLimit-Reset, RateLimit-Policy, X-Image-Fallback";
+
+fn list(name: &str, default: &[&str]) -> Vec<String> {
+    match env::var(name) {
+        Ok(value) => value
+            .split(',')
+            .map(|item| item.trim().trim_end_matches('/').to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        Err(_) => default.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+impl CorsSettings {
+    /// Read `CORS_{PUBLIC,ADMIN}_{ORIGINS,METHODS,HEADERS}` and `CORS_MAX_AGE`. In production
+    /// any origin may read but no other origin may write; the dev profile allows any origin both ways.
+    pub fn from_env(profile: Profile) -> Self {
+        let headers = ["Content-Type", "Authorization", "X-API-Key", "If-None-Match"];
+        let admin_origins: &[&str] = match profile {
+            Profile::Production => &[],
+            Profile::Dev => &["*"],
+        };
+        Self {
+            public_read: CorsPolicy {
+                allowed_origins: list("CORS_PUBLIC_ORIGINS", &["*"]),
+                allowed_methods: list("CORS_PUBLIC_METHODS", &["GET", "HEAD"]),
+                allowed_headers: list("CORS_PUBLIC_HEADERS", &headers),
+            },
+            admin_write: CorsPolicy {
+                allowed_origins: list("CORS_ADMIN_ORIGINS", admin_origins),
+                allowed_methods: list("CORS_ADMIN_METHODS", &["GET", "POST", "PUT", "PATCH", "DELETE"]),
+                allowed_headers: list("CORS_ADMIN_HEADERS", &headers),
+            },
+            max_age: env::var("CORS_MAX_AGE").ok().and_then(|v| v.parse().ok()).unwrap_or(3600),
+        }
+    }
+
+    fn policy(&self, path: &str, method: &str) -> &CorsPolicy {
+        let read = method.eq_ignore_ascii_case("GET") || method.eq_ignore_ascii_case("HEAD");
+        if read && !path.starts_with("/api/admin/") {
+            &self.public_read
+        } else {
+            &self.admin_write
+        }
+    }
+}
+
+/// Headers added to every response
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    /// `Strict-Transport-Security` value; None in the dev profile, which runs over plain HTTP
+    pub hsts: Option<String>,
+    pub frame_options: String,
+    pub referrer_policy: String,
+    /// CSP of API and image responses, which never load anything
+    pub content_security_policy: String,
+    /// CSP of the Swagger UI pages, which load their own scripts, styles and the OpenAPI document
+    pub swagger_content_security_policy: String,
+}
+
+impl SecurityHeaders {
+    /// Safe defaults; `SECURITY_HSTS_MAX_AGE` (seconds, 0 disables) and `SECURITY_REFERRER_POLICY`
+    /// override them. HSTS is off in the dev profile.
+    pub fn from_env(profile: Profile) -> Self {
+        let hsts_max_age = env::var("SECURITY_HSTS_MAX_AGE")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(match profile {
+                Profile::Production => 31_536_000,
+                Profile::Dev => 0,
+            });
+        Self {
+            hsts: (hsts_max_age > 0).then(|| format!("max-age={hsts_max_age}; includeSubDomains")),
+            frame_options: "DENY".to_string(),
+            referrer_policy: env::var("SECURITY_REFERRER_POLICY")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| "strict-origin-when-cross-origin".to_string()),
+            content_security_policy: "default-src 'none'; frame-ancestors 'none'".to_string(),
+            swagger_content_security_policy: "default-src 'self'; script-src 'self'; \
+                style-src 'self' 'unsafe-inline'; img-src 'self' data:; connect-src 'self'; frame-ancestors 'none'"
+                .to_string(),
+        }
+    }
+}
+
+fn set_header(headers: &mut header::HeaderMap, name: HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+/// Middleware applying the CORS policy of the route group: answers preflight requests itself
+/// and adds `Access-Control-Allow-Origin` to responses for allowed origins.
+pub async fn cors(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let settings = req.app_data::<web::Data<CorsSettings>>().cloned();
+    let origin = req
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let (Some(settings), Some(origin)) = (settings, origin) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    let preflight_method = req
+        .headers()
+        .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    if let (true, Some(method)) = (req.method() == Method::OPTIONS, preflight_method) {
+        let policy = settings.policy(req.path(), &method);
+        let requested_headers = req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if !(policy.allows_origin(&origin) && policy.allows_method(&method) && policy.allows_headers(requested_headers)) {
+            let rejected = HttpResponse::Forbidden()
+                .insert_header((header::VARY, "Origin"))
+                .json(json!({"error": "CORS request not allowed", "origin": origin, "method": method}));
+            return Ok(req.into_response(rejected).map_into_right_body());
+        }
+        let allowed = HttpResponse::NoContent()
+            .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, policy.allowed_methods.join(", ")))
+            .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, policy.allowed_headers.join(", ")))
+            .insert_header((header::ACCESS_CONTROL_MAX_AGE, settings.max_age.to_string()))
+            .insert_header((header::VARY, "Origin, Access-Control-Request-Method, Access-Control-Request-Headers"))
+            .finish();
+        return Ok(req.into_response(allowed).map_into_right_body());
+    }
+
+    let allowed = settings.policy(req.path(), req.method().as_str()).allows_origin(&origin);
+    let mut response = next.call(req).await?.map_into_left_body();
+    let headers = response.headers_mut();
+    headers.append(header::VARY, HeaderValue::from_static("Origin"));
+    if allowed {
+        set_header(headers, header::ACCESS_CONTROL_ALLOW_ORIGIN, &origin);
+        set_header(headers, header::ACCESS_CONTROL_EXPOSE_HEADERS, EXPOSED_HEADERS);
+    }
+    Ok(response)
+}
+
+/// Middleware adding HSTS, nosniff, frame-options, referrer-policy and a CSP (a looser one
+/// for the Swagger UI) unless the handler set them itself
+pub async fn security_headers(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let settings = req.app_data::<web::Data<SecurityHeaders>>().cloned();
+    let swagger = req.path().starts_with("/swagger-ui");
+    let mut response = next.call(req).await?;
+    let Some(settings) = settings else {
+        return Ok(response);
+    };
+
+    let headers = response.headers_mut();
+    let csp = if swagger {
+        &settings.swagger_content_security_policy
+    } else {
+        &settings.content_security_policy
+    };
+    let mut defaults = vec![
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        (header::X_FRAME_OPTIONS, settings.frame_options.as_str()),
+        (header::REFERRER_POLICY, settings.referrer_policy.as_str()),
+        (header::CONTENT_SECURITY_POLICY, csp.as_str()),
+    ];
+    if let Some(hsts) = settings.hsts.as_deref() {
+        defaults.push((header::STRICT_TRANSPORT_SECURITY, hsts));
+    }
+    for (name, value) in defaults {
+        if !headers.contains_key(&name) {
+            set_header(headers, name, value);
+        }
+    }
+    Ok(response)
+}
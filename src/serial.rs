@@ -0,0 +1,572 @@
+use serde::{Deserialize, Serialize};
+
+/// A serial rule for small builders, loaded from the `SERIAL_RULES_PATH` JSON file.
+///
+/// `mask` is matched position by position against the serial:
+/// `Y` year digit, `D` day-of-year digit, `R` ranking digit, `#` any digit,
+/// anything else must match literally (case-insensitive). Two `Y` digits are
+/// added to `century`; four are read as the full year.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomSerialRule {
+    pub brand: String,
+    pub name: String,
+    pub mask: String,
+    #[serde(default)]
+    pub factory: Option<String>,
+    #[serde(default = "default_century")]
+    pub century: i32,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+fn default_century() -> i32 {
+    2000
+}
+
+/// What a serial number says about a guitar
+#[derive(Debug, Clone, Default)]
+pub struct SerialInfo {
+    pub rule: String,
+    /// Candidate production years; several when a scheme was reused across years
+    pub years: Vec<i32>,
+    pub factory: Option<String>,
+    /// Position in the day's (or batch's) production, when the scheme encodes one
+    pub ranking: Option<u32>,
+    pub day_of_year: Option<u32>,
+    pub batch: Option<u32>,
+    pub notes: Vec<String>,
+}
+
+/// Decodes serial numbers with the built-in Gibson and Fender rules plus custom rules
+#[derive(Debug, Clone, Default)]
+pub struct SerialDecoder {
+    custom_rules: Vec<CustomSerialRule>,
+}
+
+impl SerialDecoder {
+    pub fn new(custom_rules: Vec<CustomSerialRule>) -> Self {
+        Self { custom_rules }
+    }
+
+    /// Load custom rules from a JSON array file
+    pub fn from_file(path: &str) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let rules: Vec<CustomSerialRule> = serde_json::from_str(&contents)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(Self::new(rules))
+    }
+
+    /// Decode a serial. `country` ("US", "MX", "JP") disambiguates Fender prefixes
+    /// that were reused across factories.
+    pub fn decode(&self, serial: &str, brand: Option<&str>, country: Option<&str>) -> Option<SerialInfo> {
+        let serial = serial.trim();
+        if serial.is_empty() {
+            return None;
+        }
+        let brand_lower = brand.map(|b| b.to_lowercase()).unwrap_or_default();
+
+        let builtin = if brand_lower.contains("gibson") {
+            decode_gibson(serial)
+        } else if brand_lower.contains("fender") || brand_lower.contains("squier") {
+            decode_fender(serial, country)
+        } else {
+            None
+        };
+
+        builtin.or_else(|| {
+            self.custom_rules
+                .iter()
+                .filter(|rule| brand_lower.is_empty() || brand_lower.contains(&rule.brand.to_lowercase()))
+                .find_map(|rule| decode_custom(serial, rule))
+        })
+    }
+}
+
+fn digits_value(digits: &str) -> Option<u32> {
+    digits.parse().ok()
+}
+
+/// Gibson 1961-1969 number ranges. Gibson reused numbers across these years, so a
+/// range can map to several candidate years.
+const GIBSON_1960S_RANGES: [(u32, u32, &[i32]); 40] = [
+    (100, 41_199, &[1961]),
+    (42_440, 61_180, &[1962]),
+    (61_450, 64_222, &[1963]),
+    (64_240, 70_501, &[1964]),
+    (71_180, 96_600, &[1962, 1963]),
+    (96_601, 99_999, &[1963]),
+    (100_000, 106_099, &[1963, 1967]),
+    (106_100, 108_999, &[1963]),
+    (109_000, 109_999, &[1963, 1967]),
+    (110_000, 111_549, &[1963]),
+    (111_550, 115_799, &[1963, 1967]),
+    (115_800, 118_299, &[1963]),
+    (118_300, 120_999, &[1963, 1967]),
+    (121_000, 139_999, &[1963]),
+    (140_000, 140_100, &[1963, 1967]),
+    (140_101, 144_304, &[1963]),
+    (144_305, 144_380, &[1964]),
+    (144_381, 145_000, &[1963]),
+    (147_001, 149_891, &[1963, 1964]),
+    (149_892, 152_989, &[1963]),
+    (152_990, 174_222, &[1964]),
+    (174_223, 176_643, &[1964, 1965]),
+    (176_644, 199_999, &[1964]),
+    (200_000, 250_199, &[1964]),
+    (250_540, 290_998, &[1965]),
+    (300_000, 305_999, &[1965, 1967]),
+    (306_000, 307_984, &[1965]),
+    (309_653, 310_999, &[1965, 1967]),
+    (311_000, 320_149, &[1965]),
+    (320_150, 320_699, &[1967]),
+    (320_700, 325_999, &[1965]),
+    (326_000, 347_099, &[1965, 1967]),
+    (347_100, 348_092, &[1965, 1967]),
+    (348_093, 349_100, &[1966]),
+    (349_121, 368_638, &[1965, 1967]),
+    (368_640, 369_890, &[1966]),
+    (370_000, 399_999, &[1966, 1967]),
+    (400_001, 599_999, &[1965, 1966, 1967, 1968, 1969]),
+    (600_000, 899_999, &[1966, 1967, 1968, 1969]),
+    (900_000, 999_999, &[1968, 1969]),
+];
+
+fn decode_gibson(serial: &str) -> Option<SerialInfo> {
+    // 1952-1960 ink stamp: year digit, a space, then the ranking ("8 1234" is 1958)
+    if let Some((year_digit, rank)) = serial.split_once(' ') {
+        let rank = rank.trim();
+        if year_digit.len() == 1
+            && (3..=4).contains(&rank.len())
+            && serial.chars().all(|c| c.is_ascii_digit() || c == ' ')
+        {
+            let digit: i32 = year_digit.parse().ok()?;
+            let year = if digit == 0 { 1960 } else { 1950 + digit };
+            if year < 1952 {
+                return None;
+            }
+            return Some(SerialInfo {
+                rule: "gibson_ink_1952_1960".to_string(),
+                years: vec![year],
+                factory: Some("Kalamazoo, Michigan".to_string()),
+                ranking: digits_value(rank),
+                notes: vec!["Ink-stamped serial: first digit is the last digit of the year".to_string()],
+                ..Default::default()
+            });
+        }
+    }
+
+    if !serial.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let d: Vec<u32> = serial.chars().filter_map(|c| c.to_digit(10)).collect();
+
+    match d.len() {
+        // YDDDYRRR, 1977-2005
+        8 => {
+            let yy = (d[0] * 10 + d[4]) as i32;
+            let year = if yy >= 77 { 1900 + yy } else { 2000 + yy };
+            if !(1977..=2005).contains(&year) {
+                return None;
+            }
+            let day = digits_value(&serial[1..4])?;
+            if !(1..=366).contains(&day) {
+                return None;
+            }
+            let rank = digits_value(&serial[5..8])?;
+            // Ranking 001-499 was Kalamazoo, 500-999 Nashville, until Kalamazoo closed in 1984
+            let factory = if year <= 1984 && rank < 500 {
+                "Kalamazoo, Michigan"
+            } else {
+                "Nashville, Tennessee"
+            };
+            Some(SerialInfo {
+                rule: "gibson_ydddyrrr_1977_2005".to_string(),
+                years: vec![year],
+                factory: Some(factory.to_string()),
+                ranking: Some(rank),
+                day_of_year: Some(day),
+                notes: vec!["Ink-stamped YDDDYRRR: digits 1 and 5 are the year, 2-4 the day of the year".to_string()],
+                ..Default::default()
+            })
+        }
+        // YDDDYBRRR, 2005-2014
+        9 => {
+            let yy = (d[0] * 10 + d[4]) as i32;
+            let year = 2000 + yy;
+            if !(2005..=2014).contains(&year) {
+                return None;
+            }
+            let day = digits_value(&serial[1..4])?;
+            if !(1..=366).contains(&day) {
+                return None;
+            }
+            Some(SerialInfo {
+                rule: "gibson_ydddybrrr_2005_2014".to_string(),
+                years: vec![year],
+                factory: Some("Nashville, Tennessee".to_string()),
+                ranking: digits_value(&serial[6..9]),
+                day_of_year: Some(day),
+                batch: Some(d[5]),
+                notes: vec!["Ink-stamped YDDDYBRRR: digit 6 is the batch number".to_string()],
+            })
+        }
+        // 1961-1969 impressed numbers
+        3..=6 => {
+            let number = digits_value(serial)?;
+            let (_, _, years) = GIBSON_1960S_RANGES
+                .iter()
+                .find(|(low, high, _)| (*low..=*high).contains(&number))?;
+            let mut notes = vec!["1961-1969 impressed serial, looked up in Gibson's published ranges".to_string()];
+            if years.len() > 1 {
+                notes.push("Gibson reused this range; confirm the year with features such as pots or headstock".to_string());
+            }
+            Some(SerialInfo {
+                rule: "gibson_1961_1969_ranges".to_string(),
+                years: years.to_vec(),
+                factory: Some("Kalamazoo, Michigan".to_string()),
+                notes,
+                ..Default::default()
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Fender prefix rules: the year is `base` plus the `year_digits` digits after the prefix
+struct FenderPrefix {
+    prefix: &'static str,
+    country: &'static str,
+    base: i32,
+    year_digits: usize,
+    first: i32,
+    last: i32,
+    factory: &'static str,
+}
+
+const FENDER_PREFIXES: [FenderPrefix; 14] = [
+    FenderPrefix { prefix: "MX", country: "MX", base: 2000, year_digits: 2, first: 2010, last: 2029, factory: "Ensenada, Mexico" },
+    FenderPrefix { prefix: "MZ", country: "MX", base: 2000, year_digits: 1, first: 2000, last: 2009, factory: "Ensenada, Mexico" },
+    FenderPrefix { prefix: "MN", country: "MX", base: 1990, year_digits: 1, first: 1990, last: 1999, factory: "Ensenada, Mexico" },
+    FenderPrefix { prefix: "US", country: "US", base: 2000, year_digits: 2, first: 2010, last: 2029, factory: "Corona, California" },
+    FenderPrefix { prefix: "DZ", country: "US", base: 2000, year_digits: 1, first: 2000, last: 2009, factory: "Corona, California" },
+    FenderPrefix { prefix: "DN", country: "US", base: 1990, year_digits: 1, first: 1990, last: 1999, factory: "Corona, California" },
+    FenderPrefix { prefix: "CZ", country: "US", base: 2000, year_digits: 1, first: 2000, last: 2009, factory: "Corona, California (Custom Shop)" },
+    FenderPrefix { prefix: "JD", country: "JP", base: 2000, year_digits: 2, first: 2008, last: 2029, factory: "Dyna Gakki, Japan" },
+    FenderPrefix { prefix: "JV", country: "JP", base: 0, year_digits: 0, first: 1982, last: 1984, factory: "FujiGen, Japan" },
+    FenderPrefix { prefix: "SQ", country: "JP", base: 0, year_digits: 0, first: 1983, last: 1984, factory: "FujiGen, Japan" },
+    FenderPrefix { prefix: "Z", country: "US", base: 2000, year_digits: 1, first: 2000, last: 2009, factory: "Corona, California" },
+    FenderPrefix { prefix: "N", country: "US", base: 1990, year_digits: 1, first: 1990, last: 1999, factory: "Corona, California" },
+    FenderPrefix { prefix: "E", country: "US", base: 1980, year_digits: 1, first: 1979, last: 1989, factory: "Corona, California" },
+    FenderPrefix { prefix: "S", country: "US", base: 1970, year_digits: 1, first: 1976, last: 1981, factory: "Fullerton, California" },
+];
+
+/// Japanese single-letter prefixes (only applied when the country is JP)
+const FENDER_JAPAN_LETTERS: [(char, i32, i32); 9] = [
+    ('A', 1985, 1986),
+    ('B', 1985, 1986),
+    ('C', 1985, 1986),
+    ('E', 1984, 1987),
+    ('F', 1986, 1987),
+    ('G', 1987, 1988),
+    ('H', 1988, 1989),
+    ('K', 1990, 1991),
+    ('L', 1991, 1992),
+];
+
+fn decode_fender(serial: &str, country: Option<&str>) -> Option<SerialInfo> {
+    let s: String = serial
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    let country = country.map(|c| c.to_uppercase());
+
+    // Japanese single-letter prefixes clash with US ones, so they need an explicit country
+    if country.as_deref() == Some("JP") {
+        let mut chars = s.chars();
+        if let Some(letter) = chars.next() {
+            let rest: String = chars.collect();
+            if rest.len() == 6 && rest.chars().all(|c| c.is_ascii_digit()) {
+                if let Some((_, from, to)) = FENDER_JAPAN_LETTERS.iter().find(|(l, _, _)| *l == letter) {
+                    return Some(SerialInfo {
+                        rule: format!("fender_japan_{}", letter.to_ascii_lowercase()),
+                        years: (*from..=*to).collect(),
+                        factory: Some("FujiGen, Japan".to_string()),
+                        ranking: digits_value(&rest),
+                        notes: vec!["Made in Japan letter prefix".to_string()],
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+    }
+
+    for rule in &FENDER_PREFIXES {
+        if country.as_deref().is_some_and(|c| c != rule.country) {
+            continue;
+        }
+        let Some(rest) = s.strip_prefix(rule.prefix) else {
+            continue;
+        };
+        if rest.len() <= rule.year_digits || !rest.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        let coded_year = match rest.get(..rule.year_digits) {
+            Some(digits) if !digits.is_empty() => digits.parse::<i32>().ok().map(|d| rule.base + d),
+            _ => None,
+        };
+        let years: Vec<i32> = match coded_year {
+            // Fender made necks and bodies ahead of time, so assembly can be a year later
+            Some(year) if (rule.first..=rule.last).contains(&year) => vec![year, year + 1],
+            _ => (rule.first..=rule.last).collect(),
+        };
+        // The E series spans the 1985 move from Fullerton to Corona
+        let factory = match (rule.prefix, coded_year) {
+            ("E", Some(year)) if year < 1985 => "Fullerton, California",
+            _ => rule.factory,
+        };
+
+        return Some(SerialInfo {
+            rule: format!("fender_{}", rule.prefix.to_lowercase()),
+            years,
+            factory: Some(factory.to_string()),
+            ranking: rest.get(rule.year_digits..).and_then(digits_value),
+            notes: vec![format!(
+                "Fender {} prefix ({}); serials can predate final assembly by up to a year",
+                rule.prefix, rule.country
+            )],
+            ..Default::default()
+        });
+    }
+
+    // Pre-CBS and CBS era neck plate serials
+    if let Some(rest) = s.strip_prefix('L').filter(|r| r.len() == 5 && r.chars().all(|c| c.is_ascii_digit())) {
+        return Some(SerialInfo {
+            rule: "fender_l_series".to_string(),
+            years: (1963..=1965).collect(),
+            factory: Some("Fullerton, California".to_string()),
+            ranking: digits_value(rest),
+            notes: vec!["L-series neck plate serial".to_string()],
+            ..Default::default()
+        });
+    }
+    if let Some(rest) = s.strip_prefix('F').filter(|r| r.len() == 6 && r.chars().all(|c| c.is_ascii_digit())) {
+        return Some(SerialInfo {
+            rule: "fender_f_series".to_string(),
+            years: (1965..=1973).collect(),
+            factory: Some("Fullerton, California".to_string()),
+            ranking: digits_value(rest),
+            notes: vec!["F-series neck plate serial (CBS era)".to_string()],
+            ..Default::default()
+        });
+    }
+    if let Some(rest) = s.strip_prefix('V').filter(|r| (5..=6).contains(&r.len()) && r.chars().all(|c| c.is_ascii_digit())) {
+        return Some(SerialInfo {
+            rule: "fender_v_vintage_reissue".to_string(),
+            years: (1982..=2012).collect(),
+            factory: Some("Fullerton/Corona, California".to_string()),
+            ranking: digits_value(rest),
+            notes: vec!["American Vintage reissue serial; the year is not encoded".to_string()],
+            ..Default::default()
+        });
+    }
+
+    None
+}
+
+fn decode_custom(serial: &str, rule: &CustomSerialRule) -> Option<SerialInfo> {
+    let serial_chars: Vec<char> = serial.chars().collect();
+    let mask_chars: Vec<char> = rule.mask.chars().collect();
+    if serial_chars.len() != mask_chars.len() {
+        return None;
+    }
+
+    let (mut year, mut day, mut rank) = (String::new(), String::new(), String::new());
+    for (s, m) in serial_chars.iter().zip(mask_chars.iter()) {
+        match m {
+            'Y' | 'D' | 'R' | '#' if !s.is_ascii_digit() => return None,
+            'Y' => year.push(*s),
+            'D' => day.push(*s),
+            'R' => rank.push(*s),
+            '#' => {}
+            literal if !literal.eq_ignore_ascii_case(s) => return None,
+            _ => {}
+        }
+    }
+
+    let years = match year.len() {
+        0 => Vec::new(),
+        4 => vec![year.parse().ok()?],
+        _ => vec![rule.century + year.parse::<i32>().ok()?],
+    };
+    let mut notes = vec![format!("Custom {} rule", rule.brand)];
+    notes.extend(rule.notes.clone());
+
+    Some(SerialInfo {
+        rule: rule.name.clone(),
+        years,
+        factory: rule.factory.clone(),
+        ranking: digits_value(&rank),
+        day_of_year: digits_value(&day),
+        notes,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder() -> SerialDecoder {
+        let rules: Vec<CustomSerialRule> = serde_json::from_str(include_str!("../data/serial_rules.example.json")).unwrap();
+        SerialDecoder::new(rules)
+    }
+
+    /// (brand, country, serial, expected rule, expected years, expected ranking)
+    type Case = (&'static str, Option<&'static str>, &'static str, &'static str, Vec<i32>, Option<u32>);
+
+    fn check(cases: Vec<Case>) {
+        let decoder = decoder();
+        for (brand, country, serial, rule, years, ranking) in cases {
+            let info = decoder
+                .decode(serial, Some(brand), country)
+                .unwrap_or_else(|| panic!("{brand} {serial} should decode"));
+            assert_eq!(info.rule, rule, "{brand} {serial}");
+            assert_eq!(info.years, years, "{brand} {serial}");
+            assert_eq!(info.ranking, ranking, "{brand} {serial}");
+        }
+    }
+
+    fn check_undecoded(cases: &[(&str, Option<&str>, &str)]) {
+        let decoder = decoder();
+        for (brand, country, serial) in cases {
+            assert!(decoder.decode(serial, Some(brand), *country).is_none(), "{brand} {serial} should not decode");
+        }
+    }
+
+    #[test]
+    fn gibson_ink_stamp() {
+        check(vec![
+            ("Gibson", None, "8 1234", "gibson_ink_1952_1960", vec![1958], Some(1234)),
+            ("Gibson", None, "2 345", "gibson_ink_1952_1960", vec![1952], Some(345)),
+            ("Gibson", None, "0 1234", "gibson_ink_1952_1960", vec![1960], Some(1234)),
+        ]);
+        check_undecoded(&[("Gibson", None, "1 1234"), ("Gibson", None, "8 12"), ("Gibson", None, "8 12a4")]);
+    }
+
+    #[test]
+    fn gibson_ydddyrrr() {
+        check(vec![
+            ("Gibson", None, "80124456", "gibson_ydddyrrr_1977_2005", vec![1984], Some(456)),
+            ("Gibson", None, "82765501", "gibson_ydddyrrr_1977_2005", vec![1985], Some(501)),
+            ("Gibson", None, "91234567", "gibson_ydddyrrr_1977_2005", vec![1994], Some(567)),
+            ("Gibson", None, "00015123", "gibson_ydddyrrr_1977_2005", vec![2005], Some(123)),
+        ]);
+        let decoder = decoder();
+        let kalamazoo = decoder.decode("80124456", Some("Gibson"), None).unwrap();
+        assert_eq!(kalamazoo.factory.as_deref(), Some("Kalamazoo, Michigan"));
+        assert_eq!(kalamazoo.day_of_year, Some(12));
+        let nashville = decoder.decode("80124556", Some("Gibson"), None).unwrap();
+        assert_eq!(nashville.factory.as_deref(), Some("Nashville, Tennessee"));
+        // Year 2007 is past the scheme, day 000 and 367 do not exist
+        check_undecoded(&[("Gibson", None, "00017123"), ("Gibson", None, "80004456"), ("Gibson", None, "83674456")]);
+    }
+
+    #[test]
+    fn gibson_ydddybrrr() {
+        check(vec![
+            ("Gibson", None, "012380045", "gibson_ydddybrrr_2005_2014", vec![2008], Some(45)),
+            ("Gibson", None, "112341001", "gibson_ydddybrrr_2005_2014", vec![2014], Some(1)),
+        ]);
+        let info = decoder().decode("012380045", Some("Gibson"), None).unwrap();
+        assert_eq!(info.batch, Some(0));
+        assert_eq!(info.day_of_year, Some(123));
+        check_undecoded(&[("Gibson", None, "212351001"), ("Gibson", None, "000040001")]);
+    }
+
+    #[test]
+    fn gibson_1960s_ranges() {
+        check(vec![
+            ("Gibson", None, "3000", "gibson_1961_1969_ranges", vec![1961], None),
+            ("Gibson", None, "123456", "gibson_1961_1969_ranges", vec![1963], None),
+            ("Gibson", None, "71180", "gibson_1961_1969_ranges", vec![1962, 1963], None),
+            ("Gibson", None, "500000", "gibson_1961_1969_ranges", vec![1965, 1966, 1967, 1968, 1969], None),
+            ("Gibson", None, "999999", "gibson_1961_1969_ranges", vec![1968, 1969], None),
+        ]);
+        let reused = decoder().decode("71180", Some("Gibson"), None).unwrap();
+        assert_eq!(reused.notes.len(), 2);
+        // Numbers between published ranges are not guessed
+        check_undecoded(&[("Gibson", None, "145500"), ("Gibson", None, "99")]);
+    }
+
+    #[test]
+    fn fender_prefixes() {
+        check(vec![
+            ("Fender", Some("MX"), "MX15123456", "fender_mx", vec![2015, 2016], Some(123456)),
+            ("Fender", Some("MX"), "MZ5123456", "fender_mz", vec![2005, 2006], Some(123456)),
+            ("Fender", Some("MX"), "MN8123456", "fender_mn", vec![1998, 1999], Some(123456)),
+            ("Fender", Some("US"), "US12345678", "fender_us", vec![2012, 2013], Some(345678)),
+            ("Fender", None, "DZ1123456", "fender_dz", vec![2001, 2002], Some(123456)),
+            ("Fender", None, "DN9123456", "fender_dn", vec![1999, 2000], Some(123456)),
+            ("Fender", None, "CZ512345", "fender_cz", vec![2005, 2006], Some(12345)),
+            ("Fender", Some("JP"), "JD12345678", "fender_jd", vec![2012, 2013], Some(345678)),
+            ("Fender", Some("JP"), "JV12345", "fender_jv", vec![1982, 1983, 1984], Some(12345)),
+            ("Squier", Some("JP"), "SQ12345", "fender_sq", vec![1983, 1984], Some(12345)),
+            ("Fender", None, "Z1234567", "fender_z", vec![2001, 2002], Some(234567)),
+            ("Fender", None, "N2123456", "fender_n", vec![1992, 1993], Some(123456)),
+            ("Fender", None, "E312345", "fender_e", vec![1983, 1984], Some(12345)),
+            ("Fender", None, "S812345", "fender_s", vec![1978, 1979], Some(12345)),
+            // A coded year outside the prefix's era falls back to the whole era
+            ("Fender", None, "S512345", "fender_s", (1976..=1981).collect(), Some(12345)),
+            // Separators and case are ignored
+            ("Fender", None, "us 12-345678", "fender_us", vec![2012, 2013], Some(345678)),
+        ]);
+        let decoder = decoder();
+        let factory = |serial: &str| decoder.decode(serial, Some("Fender"), None).unwrap().factory.unwrap();
+        assert_eq!(factory("E312345"), "Fullerton, California");
+        assert_eq!(factory("E612345"), "Corona, California");
+        // The country excludes prefixes of other factories
+        check_undecoded(&[("Fender", Some("US"), "MX15123456"), ("Fender", None, "XY123456")]);
+    }
+
+    #[test]
+    fn fender_neck_plate_series() {
+        check(vec![
+            ("Fender", None, "L12345", "fender_l_series", vec![1963, 1964, 1965], Some(12345)),
+            ("Fender", None, "F123456", "fender_f_series", (1965..=1973).collect(), Some(123456)),
+            ("Fender", None, "V012345", "fender_v_vintage_reissue", (1982..=2012).collect(), Some(12345)),
+        ]);
+    }
+
+    #[test]
+    fn fender_japan_letters() {
+        check(vec![
+            ("Fender", Some("JP"), "A123456", "fender_japan_a", vec![1985, 1986], Some(123456)),
+            ("Fender", Some("JP"), "E123456", "fender_japan_e", vec![1984, 1985, 1986, 1987], Some(123456)),
+            ("Fender", Some("JP"), "G123456", "fender_japan_g", vec![1987, 1988], Some(123456)),
+            ("Fender", Some("JP"), "L123456", "fender_japan_l", vec![1991, 1992], Some(123456)),
+            // Without the country the same serial reads as a US E-series
+            ("Fender", None, "E123456", "fender_e", vec![1981, 1982], Some(23456)),
+        ]);
+    }
+
+    #[test]
+    fn custom_masks() {
+        check(vec![
+            ("Banker", None, "BK-18-042", "banker_bk_yy_rrr", vec![2018], Some(42)),
+            ("Banker Guitars", None, "bk-21-007", "banker_bk_yy_rrr", vec![2021], Some(7)),
+            ("Nik Huber", None, "2015123", "nik_huber_yyyy_rrr", vec![2015], Some(123)),
+        ]);
+        let info = decoder().decode("BK-18-042", None, None).unwrap();
+        assert_eq!(info.factory.as_deref(), Some("Baltimore, Maryland"));
+        assert_eq!(info.notes.len(), 2);
+        // Wrong literal, wrong length, letters in digit positions, or another brand's rule
+        check_undecoded(&[
+            ("Banker", None, "BX-18-042"),
+            ("Banker", None, "BK-18-0423"),
+            ("Banker", None, "BK-1A-042"),
+            ("Nik Huber", None, "BK-18-042"),
+        ]);
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use crate::models::{Guitar, SimilarityDimension};
+use crate::money::ExchangeRates;
+
+/// Relative weight of each dimension when scoring guitar similarity
+#[derive(Debug, Clone)]
+pub struct SimilarityWeights {
+    pub body_style: f64,
+    pub neck_profile: f64,
+    pub scale_length: f64,
+    pub nut_width: f64,
+    pub woods: f64,
+    pub pickups: f64,
+    pub price_band: f64,
+}
+
+impl Default for SimilarityWeights {
+    fn default() -> Self {
+        Self {
+            body_style: 3.0,
+            neck_profile: 1.5,
+            scale_length: 2.0,
+            nut_width: 1.0,
+            woods: 2.0,
+            pickups: 2.0,
+            price_band: 1.5,
+        }
+    }
+}
+
+impl SimilarityWeights {
+    /// Read weights from `SIMILAR_WEIGHT_*` environment variables, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let weight = |name: &str, fallback: f64| {
+            env::var(format!("SIMILAR_WEIGHT_{name}"))
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v >= 0.0)
+                .unwrap_or(fallback)
+        };
+        Self {
+            body_style: weight("BODY_STYLE", defaults.body_style),
+            neck_profile: weight("NECK_PROFILE", defaults.neck_profile),
+            scale_length: weight("SCALE_LENGTH", defaults.scale_length),
+            nut_width: weight("NUT_WIDTH", defaults.nut_width),
+            woods: weight("WOODS", defaults.woods),
+            pickups: weight("PICKUPS", defaults.pickups),
+            price_band: weight("PRICE_BAND", defaults.price_band),
+        }
+    }
+}
+
+/// The comparable spec fields of one guitar, normalized for scoring
+#[derive(Debug, Clone, Default)]
+pub struct SpecProfile {
+    pub body_style: Option<String>,
+    pub neck_profile: Option<String>,
+    pub scale_length: Option<f64>,
+    pub nut_width: Option<f64>,
+    pub woods: Vec<String>,
+    pub pickup_configuration: Option<String>,
+    pub price_band: Option<u8>,
+}
+
+impl SpecProfile {
+    /// Build a profile from a guitar and its `guitar_dimensions`, `guitar_woods` and `guitar_pickups` rows;
+    /// the price is converted to the base currency of `rates` before it is banded
+    pub fn build(
+        guitar: &Guitar,
+        dimensions: Option<&serde_json::Value>,
+        woods: Option<&serde_json::Value>,
+        pickups: &[serde_json::Value],
+        neck_profiles: &HashMap<String, String>,
+        rates: &ExchangeRates,
+    ) -> Self {
+        let text = |row: Option<&serde_json::Value>, field: &str| {
+            row.and_then(|r| r.get(field))
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+
+        let body_style = Some(guitar.body_style.trim())
+            .filter(|s| !s.is_empty())
+            .map(normalize_body_style);
+        let neck_profile = text(dimensions, "neck_profile")
+            .map(|raw| normalize_neck_profile(&raw, neck_profiles));
+        let scale_length = text(dimensions, "scale_length").and_then(|s| parse_inches(&s));
+        let nut_width = text(dimensions, "nut_width").and_then(|s| parse_inches(&s));
+
+        let mut wood_list: Vec<String> = ["body", "top", "neck", "fingerboard"]
+            .iter()
+            .filter_map(|field| text(woods, field))
+            .map(|w| w.to_lowercase())
+            .collect();
+        wood_list.sort();
+        wood_list.dedup();
+
+        Self {
+            body_style,
+            neck_profile,
+            scale_length,
+            nut_width,
+            woods: wood_list,
+            pickup_configuration: pickup_configuration(pickups),
+            price_band: guitar
+                .get_price()
+                .and_then(|price| rates.convert(&price, &rates.base))
+                .and_then(|price| price_band(price.amount_minor)),
+        }
+    }
+}
+
+/// Result of scoring one candidate against the reference guitar
+#[derive(Debug, Clone)]
+pub struct SimilarityScore {
+    /// Weighted score in the range 0.0..=1.0
+    pub score: f64,
+    pub dimensions: Vec<SimilarityDimension>,
+}
+
+/// Score how similar `candidate` is to `reference`.
+///
+/// Dimensions where either side has no data are reported but excluded from the
+/// weighted total, so sparse records are not penalized for missing specs.
+pub fn score(
+    reference: &SpecProfile,
+    candidate: &SpecProfile,
+    weights: &SimilarityWeights,
+) -> SimilarityScore {
+    let mut dimensions = vec![
+        compare_text("body_style", weights.body_style, &reference.body_style, &candidate.body_style, token_overlap),
+        compare_text("neck_profile", weights.neck_profile, &reference.neck_profile, &candidate.neck_profile, token_overlap),
+        compare_measurement("scale_length", weights.scale_length, reference.scale_length, candidate.scale_length, 0.75),
+        compare_measurement("nut_width", weights.nut_width, reference.nut_width, candidate.nut_width, 0.125),
+        compare_woods(weights.woods, &reference.woods, &candidate.woods),
+        compare_text(
+            "pickups",
+            weights.pickups,
+            &reference.pickup_configuration,
+            &candidate.pickup_configuration,
+            |a, b| if a == b { 1.0 } else { 0.0 },
+        ),
+        compare_price_band(weights.price_band, reference.price_band, candidate.price_band),
+    ];
+    dimensions.retain(|d| d.weight > 0.0);
+
+    let (weighted, total_weight) = dimensions
+        .iter()
+        .filter(|d| d.compared)
+        .fold((0.0, 0.0), |(sum, total), d| (sum + d.score * d.weight, total + d.weight));
+    let score = if total_weight > 0.0 { weighted / total_weight } else { 0.0 };
+
+    SimilarityScore { score, dimensions }
+}
+
+/// Parse a measurement in inches such as `24.75`, `24 3/4"` or `1 11/16" (Vintage)`
+pub fn parse_inches(raw: &str) -> Option<f64> {
+    let cleaned: String = raw
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '/' || *c == ' ')
+        .collect();
+    let mut total = 0.0;
+    let mut parsed_any = false;
+    for part in cleaned.split_whitespace() {
+        let value = match part.split_once('/') {
+            Some((num, den)) => {
+                let num: f64 = num.parse().ok()?;
+                let den: f64 = den.parse().ok()?;
+                if den == 0.0 {
+                    return None;
+                }
+                num / den
+            }
+            None => part.parse().ok()?,
+        };
+        total += value;
+        parsed_any = true;
+    }
+    parsed_any.then_some(total)
+}
+
+/// Map a raw neck profile onto its canonical name using `spec_normalizations`
+pub fn normalize_neck_profile(raw: &str, mappings: &HashMap<String, String>) -> String {
+    let key = raw.trim().to_lowercase();
+    mappings
+        .get(&key)
+        .cloned()
+        .unwrap_or_else(|| key.replace(['\'', '"'], ""))
+        .to_lowercase()
+}
+
+/// Lowercase a body style and drop filler words ("Flying V" and "V" still overlap)
+pub fn normalize_body_style(raw: &str) -> String {
+    raw.to_lowercase()
+        .split_whitespace()
+        .filter(|word| *word != "style" && *word != "shape")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Summarize pickups as a bridge-to-neck configuration string, e.g. `HH` or `SSS`
+pub fn pickup_configuration(pickups: &[serde_json::Value]) -> Option<String> {
+    let position_rank = |p: &serde_json::Value| {
+        match p
+            .get("position")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "bridge" => 0,
+            "middle" => 1,
+            "neck" => 2,
+            _ => 3,
+        }
+    };
+
+    let mut ordered: Vec<&serde_json::Value> = pickups.iter().collect();
+    ordered.sort_by_key(|p| position_rank(p));
+
+    let config: String = ordered
+        .iter()
+        .filter_map(|p| p.get("type").and_then(|v| v.as_str()))
+        .filter_map(|kind| {
+            let kind = kind.to_lowercase();
+            if kind.contains("humbucker") || kind.contains("paf") {
+                Some('H')
+            } else if kind.contains("p90") || kind.contains("p-90") {
+                Some('P')
+            } else if kind.contains("single") {
+                Some('S')
+            } else {
+                None
+            }
+        })
+        .collect();
+    Some(config).filter(|c| !c.is_empty())
+}
+
+/// Bucket a price in minor units of the base currency into a coarse band; `None` for "price on request"
+pub fn price_band(amount_minor: i64) -> Option<u8> {
+    const BAND_LIMITS: [i64; 6] = [100_000, 250_000, 500_000, 1_000_000, 2_500_000, 5_000_000];
+    if amount_minor <= 0 {
+        return None;
+    }
+    let band = BAND_LIMITS
+        .iter()
+        .position(|limit| amount_minor < *limit)
+        .unwrap_or(BAND_LIMITS.len());
+    Some(band as u8)
+}
+
+/// Share of alphanumeric tokens two strings have in common (Jaccard index)
+pub fn token_overlap(a: &str, b: &str) -> f64 {
+    let tokens = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (tokens(a), tokens(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+fn missing(dimension: &str, weight: f64) -> SimilarityDimension {
+    SimilarityDimension {
+        dimension: dimension.to_string(),
+        weight,
+        score: 0.0,
+        compared: false,
+        reason: "Not enough data to compare".to_string(),
+    }
+}
+
+fn compare_text(
+    dimension: &str,
+    weight: f64,
+    reference: &Option<String>,
+    candidate: &Option<String>,
+    similarity: impl Fn(&str, &str) -> f64,
+) -> SimilarityDimension {
+    let (Some(a), Some(b)) = (reference, candidate) else {
+        return missing(dimension, weight);
+    };
+    let score = if a == b { 1.0 } else { similarity(a, b) };
+    let reason = if score >= 1.0 {
+        format!("Same {}: {a}", dimension.replace('_', " "))
+    } else if score > 0.0 {
+        format!("Similar {}: {b} vs {a}", dimension.replace('_', " "))
+    } else {
+        format!("Different {}: {b} vs {a}", dimension.replace('_', " "))
+    };
+    SimilarityDimension {
+        dimension: dimension.to_string(),
+        weight,
+        score,
+        compared: true,
+        reason,
+    }
+}
+
+fn compare_measurement(
+    dimension: &str,
+    weight: f64,
+    reference: Option<f64>,
+    candidate: Option<f64>,
+    tolerance: f64,
+) -> SimilarityDimension {
+    let (Some(a), Some(b)) = (reference, candidate) else {
+        return missing(dimension, weight);
+    };
+    let delta = (a - b).abs();
+    let score = (1.0 - delta / tolerance).max(0.0);
+    let reason = if delta < 0.01 {
+        format!("Same {}: {a}\"", dimension.replace('_', " "))
+    } else {
+        format!("{}: {b}\" vs {a}\" ({delta:.3}\" apart)", dimension.replace('_', " "))
+    };
+    SimilarityDimension {
+        dimension: dimension.to_string(),
+        weight,
+        score,
+        compared: true,
+        reason,
+    }
+}
+
+fn compare_woods(weight: f64, reference: &[String], candidate: &[String]) -> SimilarityDimension {
+    if reference.is_empty() || candidate.is_empty() {
+        return missing("woods", weight);
+    }
+    let a: HashSet<&String> = reference.iter().collect();
+    let b: HashSet<&String> = candidate.iter().collect();
+    let mut shared: Vec<&str> = a.intersection(&b).map(|s| s.as_str()).collect();
+    shared.sort();
+    let score = shared.len() as f64 / a.union(&b).count() as f64;
+    let reason = if shared.is_empty() {
+        "No woods in common".to_string()
+    } else {
+        format!("Shared woods: {}", shared.join(", "))
+    };
+    SimilarityDimension {
+        dimension: "woods".to_string(),
+        weight,
+        score,
+        compared: true,
+        reason,
+    }
+}
+
+fn compare_price_band(weight: f64, reference: Option<u8>, candidate: Option<u8>) -> SimilarityDimension {
+    let (Some(a), Some(b)) = (reference, candidate) else {
+        return missing("price_band", weight);
+    };
+    let distance = a.abs_diff(b);
+    let (score, reason) = match distance {
+        0 => (1.0, "Same price band".to_string()),
+        1 => (0.5, "Adjacent price band".to_string()),
+        _ => (0.0, format!("{distance} price bands apart")),
+    };
+    SimilarityDimension {
+        dimension: "price_band".to_string(),
+        weight,
+        score,
+        compared: true,
+        reason,
+    }
+}
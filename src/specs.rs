@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+use crate::models::Guitar;
+
+/// Extract the id part of a guitar record id (`guitars:abc` -> `abc`)
+pub fn record_key(id: &Thing) -> String {
+    id.id.to_raw()
+}
+
+/// Every guitar that is not in the trash
+pub async fn fetch_guitars(db: &Surreal<surrealdb::engine::remote::http::Client>) -> surrealdb::Result<Vec<Guitar>> {
+    let mut response = db.query("SELECT * FROM guitars WHERE deleted_at = NONE").await?;
+    response.take(0)
+}
+
+/// One guitar by the id part of its record id; None when missing or in the trash
+pub async fn fetch_guitar(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    key: &str,
+) -> surrealdb::Result<Option<Guitar>> {
+    let mut response = db
+        .query("SELECT * FROM type::thing('guitars', $key) WHERE deleted_at = NONE")
+        .bind(("key", key.to_string()))
+        .await?;
+    let rows: Vec<Guitar> = response.take(0)?;
+    Ok(rows.into_iter().next())
+}
+
+/// Load every row of a spec child table, grouped by the id part of its `guitar_id`.
+///
+/// Rows are returned without their own `id` and `guitar_id` fields, matching the
+/// shape the detail endpoints return.
+pub async fn fetch_spec_rows_by_guitar(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    table: &str,
+) -> surrealdb::Result<HashMap<String, Vec<serde_json::Value>>> {
+    let mut response = db
+        .query(format!(
+            "SELECT *, record::id(guitar_id) AS guitar_key OMIT id, guitar_id FROM {table};"
+        ))
+        .await?;
+    let rows: Vec<serde_json::Value> = response.take(0)?;
+
+    let mut grouped: HashMap<String, Vec<serde_json::Value>> = HashMap::new();
+    for mut row in rows {
+        let key = match row.as_object_mut().and_then(|map| map.remove("guitar_key")) {
+            Some(serde_json::Value::String(key)) => key,
+            Some(other) => other.to_string(),
+            None => continue,
+        };
+        grouped.entry(key).or_default().push(row);
+    }
+    Ok(grouped)
+}
+
+/// Load the normalization mappings for one category as `raw_value -> normalized_value`
+pub async fn fetch_normalizations(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    category: &str,
+) -> surrealdb::Result<HashMap<String, String>> {
+    let mut response = db
+        .query("SELECT raw_value, normalized_value FROM spec_normalizations WHERE category = $category;")
+        .bind(("category", category.to_string()))
+        .await?;
+    let rows: Vec<serde_json::Value> = response.take(0)?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let raw = row.get("raw_value")?.as_str()?.to_lowercase();
+            let normalized = row.get("normalized_value")?.as_str()?.to_string();
+            Some((raw, normalized))
+        })
+        .collect())
+}
+
+/// Spec sections stored as one row per guitar, as `(response key, table)`
+pub const SINGLE_ROW_SECTIONS: [(&str, &str); 8] = [
+    ("dimensions", "guitar_dimensions"),
+    ("woods", "guitar_woods"),
+    ("finish", "guitar_finish"),
+    ("hardware", "guitar_hardware"),
+    ("electronics", "guitar_electronics"),
+    ("appointments", "guitar_appointments"),
+    ("setup", "guitar_setup"),
+    ("case", "guitar_case"),
+];
+
+/// Spec sections stored as several rows per guitar, as `(response key, table)`
+pub const MULTI_ROW_SECTIONS: [(&str, &str); 2] = [
+    ("pickups", "guitar_pickups"),
+    ("controls", "guitar_controls"),
+];
+
+/// Load every spec section of one guitar, keyed the way the detail endpoints return them.
+///
+/// Single-row sections map to an object and multi-row sections to an array. Sections
+/// with no rows (or that fail to load) are left out.
+pub async fn fetch_guitar_specs(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    id_part: &str,
+) -> serde_json::Map<String, serde_json::Value> {
+    let tables: Vec<(&str, &str)> = SINGLE_ROW_SECTIONS
+        .iter()
+        .chain(MULTI_ROW_SECTIONS.iter())
+        .copied()
+        .collect();
+    let statements: String = tables
+        .iter()
+        .map(|(_, table)| {
+            format!("SELECT * OMIT id, guitar_id FROM {table} WHERE guitar_id = type::thing('guitars', $key);")
+        })
+        .collect();
+
+    let Ok(mut response) = db.query(statements).bind(("key", id_part.to_string())).await else {
+        return serde_json::Map::new();
+    };
+
+    let mut rows_by_table = HashMap::new();
+    for (index, (_, table)) in tables.iter().enumerate() {
+        if let Ok(rows) = response.take::<Vec<serde_json::Value>>(index) {
+            rows_by_table.insert(table.to_string(), rows);
+        }
+    }
+    spec_sections(rows_by_table)
+}
+
+/// Arrange one guitar's spec rows (by table, without `id` and `guitar_id`) into the sections
+/// of the detail endpoints. Sections with no rows are left out.
+pub fn spec_sections(
+    mut rows_by_table: HashMap<String, Vec<serde_json::Value>>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut sections = serde_json::Map::new();
+    for (section, table) in SINGLE_ROW_SECTIONS {
+        if let Some(row) = rows_by_table.remove(table).and_then(|rows| rows.into_iter().next()) {
+            sections.insert(section.to_string(), row);
+        }
+    }
+    for (section, table) in MULTI_ROW_SECTIONS {
+        if let Some(rows) = rows_by_table.remove(table).filter(|rows| !rows.is_empty()) {
+            sections.insert(section.to_string(), serde_json::Value::Array(rows));
+        }
+    }
+    sections
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use surrealdb::Surreal;
+
+use crate::audit::AuditContext;
+use crate::models::Guitar;
+use crate::versions;
+
+// Deleting a guitar only sets deleted_at (specs::fetch_guitars leaves it out of reads);
+// purging removes the row and everything that points at it. Graph edges of a deleted
+// record are removed by SurrealDB itself. Audit log and versions are history and stay.
+
+/// Tables whose rows belong to one guitar through `guitar_id`
+pub fn child_tables() -> Vec<&'static str> {
+    versions::SPEC_TABLES
+        .iter()
+        .copied()
+        .chain(["images", "link_checks", "price_history", "slug_redirects"])
+        .collect()
+}
+
+/// Move a live guitar to the trash; false when there is no such guitar or it is already trashed
+pub async fn soft_delete(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    key: &str,
+    audit: &AuditContext,
+) -> surrealdb::Result<bool> {
+    let mut response = db
+        .query("UPDATE type::thing('guitars', $key) SET deleted_at = time::now() WHERE deleted_at = NONE RETURN VALUE id")
+        .bind(("key", key.to_string()))
+        .bind(("audit", audit.clone()))
+        .await?;
+    let updated: Vec<surrealdb::sql::Thing> = response.take(0)?;
+    Ok(!updated.is_empty())
+}
+
+/// Take a guitar out of the trash; None when it isn't in the trash
+pub async fn restore(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    key: &str,
+    audit: &AuditContext,
+) -> surrealdb::Result<Option<Guitar>> {
+    let mut response = db
+        .query("UPDATE type::thing('guitars', $key) SET deleted_at = NONE WHERE deleted_at != NONE RETURN AFTER")
+        .bind(("key", key.to_string()))
+        .bind(("audit", audit.clone()))
+        .await?;
+    let restored: Vec<Guitar> = response.take(0)?;
+    Ok(restored.into_iter().next())
+}
+
+/// Whether the guitar exists and is in the trash
+pub async fn is_trashed(db: &Surreal<surrealdb::engine::remote::http::Client>, key: &str) -> surrealdb::Result<bool> {
+    let mut response = db
+        .query("SELECT VALUE id FROM type::thing('guitars', $key) WHERE deleted_at != NONE")
+        .bind(("key", key.to_string()))
+        .await?;
+    let trashed: Vec<surrealdb::sql::Thing> = response.take(0)?;
+    Ok(!trashed.is_empty())
+}
+
+/// Guitars in the trash, most recently deleted first
+pub async fn list(db: &Surreal<surrealdb::engine::remote::http::Client>) -> surrealdb::Result<Vec<Guitar>> {
+    let mut response = db
+        .query("SELECT * FROM guitars WHERE deleted_at != NONE ORDER BY deleted_at DESC")
+        .await?;
+    response.take(0)
+}
+
+/// Remove a trashed guitar and all its child rows in one transaction; returns the rows removed
+/// per child table
+pub async fn purge(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    key: &str,
+    audit: &AuditContext,
+) -> surrealdb::Result<BTreeMap<String, usize>> {
+    let counts: Vec<String> = child_tables()
+        .iter()
+        .map(|table| format!("{table}: array::len((DELETE {table} WHERE guitar_id = $guitar RETURN BEFORE))"))
+        .collect();
+    let mut response = db
+        .query(format!(
+            "BEGIN TRANSACTION;
+             LET $defer_versioning = true;
+             LET $guitar = type::thing('guitars', $key);
+             IF (SELECT VALUE deleted_at FROM $guitar)[0] = NONE {{ THROW 'guitar is not in the trash'; }};
+             LET $removed = {{ {} }};
+             DELETE $guitar;
+             fn::record_guitar_version($guitar, $audit);
+             COMMIT TRANSACTION;
+             $removed;",
+            counts.join(", ")
+        ))
+        .bind(("key", key.to_string()))
+        .bind(("audit", audit.clone()))
+        .await?
+        .check()?;
+    let last = response.num_statements() - 1;
+    let removed: Option<BTreeMap<String, usize>> = response.take(last)?;
+    Ok(removed.unwrap_or_default())
+}
+
+/// Rows of each child table whose guitar no longer exists (trashed guitars still exist);
+/// with `remove` they are deleted as well
+pub async fn orphans(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    remove: bool,
+    audit: &AuditContext,
+) -> surrealdb::Result<BTreeMap<String, usize>> {
+    let tables = child_tables();
+    let statements: String = tables
+        .iter()
+        .map(|table| {
+            let filter = format!("FROM {table} WHERE type::is::record(guitar_id) AND guitar_id.id = NONE");
+            if remove {
+                format!("array::len((DELETE {filter} RETURN BEFORE));")
+            } else {
+                format!("count((SELECT id {filter}));")
+            }
+        })
+        .collect();
+    // Removed rows belong to no guitar, so there is no version to record
+    let mut response = db
+        .query(format!("LET $defer_versioning = true; {statements}"))
+        .bind(("audit", audit.clone()))
+        .await?
+        .check()?;
+
+    let mut counts = BTreeMap::new();
+    for (index, table) in tables.iter().enumerate() {
+        let count: Option<usize> = response.take(index + 1)?;
+        counts.insert(table.to_string(), count.unwrap_or_default());
+    }
+    Ok(counts)
+}
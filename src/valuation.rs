@@ -0,0 +1,305 @@
+use std::collections::BTreeSet;
+
+use crate::market;
+use crate::models::{Condition, EstimateComparable, EstimateRequest, EstimateResponse, Guitar};
+use crate::money::{self, ExchangeRates, Money};
+
+/// Number of nearest listings the estimate is built from
+pub const NEIGHBOURS: usize = 5;
+
+/// Value change per condition grade step (excellent -> very good is about -6%)
+const CONDITION_STEP: f64 = 0.06;
+/// Value change per year of age difference, capped at `MAX_AGE_ADJUSTMENT`
+const AGE_RATE: f64 = 0.01;
+const MAX_AGE_ADJUSTMENT: f64 = 0.25;
+
+fn condition_factor(grade: f64) -> f64 {
+    1.0 + CONDITION_STEP * (grade - 5.0)
+}
+
+/// An older guitar of the same model is worth more; `years_older` is how much older the target is
+fn age_factor(years_older: i32) -> f64 {
+    1.0 + (AGE_RATE * years_older as f64).clamp(-MAX_AGE_ADJUSTMENT, MAX_AGE_ADJUSTMENT)
+}
+
+/// A fully modified guitar is valued at half of an all-original one
+fn originality_factor(score: f64) -> f64 {
+    0.5 + 0.5 * score.clamp(0.0, 100.0) / 100.0
+}
+
+fn model_tokens(model: &str) -> BTreeSet<String> {
+    model
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Jaccard similarity of model name tokens ("Heritage Reissue Flying V" vs "Flying V Heritage Korina Reissue")
+fn model_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (model_tokens(a), model_tokens(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+fn round3(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+struct Neighbour<'a> {
+    guitar: &'a Guitar,
+    price: Money,
+    adjusted_minor: f64,
+    distance: f64,
+}
+
+/// Estimate the value of the described guitar from its nearest priced listings of the same brand.
+/// Returns None when no listing is comparable.
+pub fn estimate(request: &EstimateRequest, guitars: &[Guitar], rates: &ExchangeRates) -> Option<EstimateResponse> {
+    let currency = money::normalize_currency(request.currency.as_deref().unwrap_or(&rates.base));
+    let mut notes = Vec::new();
+
+    let grade = request.condition.as_deref().and_then(Condition::parse).map(Condition::grade);
+    match (request.condition.as_deref(), grade) {
+        (None, _) => notes.push("no condition given; condition not adjusted".to_string()),
+        (Some(raw), None) => notes.push(format!("condition '{raw}' not recognized; condition not adjusted")),
+        _ => {}
+    }
+    if request.year.is_none() {
+        notes.push("no year given; age not adjusted".to_string());
+    }
+    let originality = request.originality.unwrap_or(100.0);
+
+    let mut neighbours: Vec<Neighbour> = guitars
+        .iter()
+        .filter(|g| g.brand.trim().eq_ignore_ascii_case(request.brand.trim()))
+        .filter_map(|guitar| {
+            let similarity = model_similarity(&request.model, &guitar.model);
+            if similarity == 0.0 {
+                return None;
+            }
+            let price = guitar.get_price()?;
+            let converted = rates.convert(&price, &currency)?;
+            let mut adjusted = converted.amount_minor as f64;
+            let mut distance = (1.0 - similarity) * 2.0;
+
+            match (request.year, guitar.production_year) {
+                (Some(target), Some(year)) => {
+                    adjusted *= age_factor(year - target);
+                    distance += (year - target).abs().min(20) as f64 / 10.0;
+                }
+                _ => distance += 0.5,
+            }
+            let listing_grade = guitar.get_condition().map(Condition::grade);
+            match (grade, listing_grade) {
+                (Some(target), Some(listing)) => {
+                    adjusted *= condition_factor(target) / condition_factor(listing);
+                    distance += (target - listing).abs() * 0.25;
+                }
+                _ => distance += 0.25,
+            }
+            adjusted *= originality_factor(originality) / originality_factor(guitar.originality_score.unwrap_or(100.0));
+
+            Some(Neighbour {
+                guitar,
+                price,
+                adjusted_minor: adjusted,
+                distance: round3(distance),
+            })
+        })
+        .collect();
+
+    neighbours.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then_with(|| a.guitar.get_slug().cmp(&b.guitar.get_slug()))
+            .then_with(|| a.price.amount_minor.cmp(&b.price.amount_minor))
+    });
+    neighbours.truncate(NEIGHBOURS);
+    if neighbours.is_empty() {
+        return None;
+    }
+
+    let raw_weights: Vec<f64> = neighbours.iter().map(|n| 1.0 / (1.0 + n.distance)).collect();
+    let total_weight: f64 = raw_weights.iter().sum();
+    let weights: Vec<f64> = raw_weights.iter().map(|w| w / total_weight).collect();
+
+    let value: f64 = neighbours.iter().zip(&weights).map(|(n, w)| n.adjusted_minor * w).sum();
+    let variance: f64 = neighbours
+        .iter()
+        .zip(&weights)
+        .map(|(n, w)| w * (n.adjusted_minor - value).powi(2))
+        .sum();
+    let mean_distance: f64 = neighbours.iter().zip(&weights).map(|(n, w)| n.distance * w).sum();
+
+    let confidence = match neighbours.len() {
+        n if n >= 3 && mean_distance <= 0.75 => "high",
+        n if n >= 2 && mean_distance <= 1.5 => "medium",
+        _ => "low",
+    };
+    // Fewer or farther comparables widen the range
+    let minimum_spread = match confidence {
+        "high" => 0.05,
+        "medium" => 0.10,
+        _ => 0.20,
+    };
+    let spread = variance.sqrt().max(value * minimum_spread);
+    if neighbours.len() < 3 {
+        notes.push(format!("only {} comparable listing(s) found", neighbours.len()));
+    }
+
+    let comparables = neighbours
+        .iter()
+        .zip(&weights)
+        .map(|(n, w)| EstimateComparable {
+            id: n.guitar.id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+            slug: n.guitar.get_slug(),
+            display_title: n.guitar.get_display_title(),
+            production_year: n.guitar.production_year,
+            condition_slug: market::condition_slug(n.guitar),
+            price: n.price.clone(),
+            adjusted_price: Money::new(n.adjusted_minor.round() as i64, &currency),
+            distance: n.distance,
+            weight: round3(*w),
+        })
+        .collect();
+
+    Some(EstimateResponse {
+        low: Money::new((value - spread).max(0.0).round() as i64, &currency),
+        estimate: Money::new(value.round() as i64, &currency),
+        high: Money::new((value + spread).round() as i64, &currency),
+        currency,
+        confidence: confidence.to_string(),
+        comparables,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// Reverb listings from guitars.json mapped onto the guitars table shape
+    fn fixtures() -> Vec<Guitar> {
+        let listings: Vec<Value> = serde_json::from_str(include_str!("../guitars.json")).unwrap();
+        listings
+            .iter()
+            .map(|listing| {
+                let year = listing["year"].as_str().and_then(|y| y.get(..4)).and_then(|y| y.parse::<i32>().ok());
+                let slug = listing["_links"]["web"]["href"].as_str().and_then(|href| href.rsplit('/').next());
+                serde_json::from_value(json!({
+                    "id": null,
+                    "external_source": "reverb",
+                    "brand": listing["make"],
+                    "model": listing["model"],
+                    "slug": slug,
+                    "price_cents": listing["price"]["amount_cents"],
+                    "price_currency": listing["price"]["currency"],
+                    "condition": listing["condition"]["display_name"],
+                    "condition_slug": listing["condition"]["slug"],
+                    "production_year": year,
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn request(condition: &str, originality: f64) -> EstimateRequest {
+        EstimateRequest {
+            brand: "Gibson".to_string(),
+            model: "Heritage Reissue Flying V".to_string(),
+            year: Some(1982),
+            condition: Some(condition.to_string()),
+            originality: Some(originality),
+            currency: None,
+        }
+    }
+
+    fn estimate_minor(request: &EstimateRequest) -> i64 {
+        estimate(request, &fixtures(), &ExchangeRates::default()).unwrap().estimate.amount_minor
+    }
+
+    #[test]
+    fn fixtures_load_from_guitars_json() {
+        let guitars = fixtures();
+        assert_eq!(guitars.len(), 3);
+        assert!(guitars.iter().all(|g| g.get_price().is_some() && g.production_year.is_some()));
+    }
+
+    #[test]
+    fn estimate_uses_all_matching_listings_within_their_price_range() {
+        let response = estimate(&request("very-good", 100.0), &fixtures(), &ExchangeRates::default()).unwrap();
+
+        assert_eq!(response.comparables.len(), 3);
+        assert_eq!(response.currency, "USD");
+        // The exact model match in the same year and condition is the nearest neighbour
+        assert_eq!(response.comparables[0].slug, "92119042-gibson-heritage-reissue-flying-v-1982-natural");
+        assert_eq!(response.comparables[0].distance, 0.0);
+        assert!(response.low.amount_minor < response.estimate.amount_minor);
+        assert!(response.estimate.amount_minor < response.high.amount_minor);
+        assert!((999_900..=1_381_412).contains(&response.estimate.amount_minor));
+        let weight: f64 = response.comparables.iter().map(|c| c.weight).sum();
+        assert!((weight - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn estimate_is_deterministic() {
+        let first = estimate(&request("excellent", 90.0), &fixtures(), &ExchangeRates::default());
+        let mut reversed = fixtures();
+        reversed.reverse();
+        let second = estimate(&request("excellent", 90.0), &reversed, &ExchangeRates::default());
+        assert_eq!(serde_json::to_value(first).unwrap(), serde_json::to_value(second).unwrap());
+    }
+
+    #[test]
+    fn better_condition_raises_the_estimate() {
+        assert!(estimate_minor(&request("mint", 100.0)) > estimate_minor(&request("very-good", 100.0)));
+        assert!(estimate_minor(&request("very-good", 100.0)) > estimate_minor(&request("fair", 100.0)));
+    }
+
+    #[test]
+    fn modifications_lower_the_estimate() {
+        assert!(estimate_minor(&request("very-good", 60.0)) < estimate_minor(&request("very-good", 100.0)));
+    }
+
+    #[test]
+    fn older_guitar_is_valued_higher() {
+        let mut older = request("very-good", 100.0);
+        older.year = Some(1975);
+        assert!(estimate_minor(&older) > estimate_minor(&request("very-good", 100.0)));
+    }
+
+    #[test]
+    fn estimate_converts_to_requested_currency() {
+        let mut eur = request("very-good", 100.0);
+        eur.currency = Some("eur".to_string());
+        let rates = ExchangeRates::default();
+        let response = estimate(&eur, &fixtures(), &rates).unwrap();
+        assert_eq!(response.currency, "EUR");
+        let usd = Money::new(estimate_minor(&request("very-good", 100.0)), "USD");
+        let expected = rates.convert(&usd, "EUR").unwrap().amount_minor;
+        assert!((response.estimate.amount_minor - expected).abs() <= 1);
+    }
+
+    #[test]
+    fn no_comparables_for_other_brands() {
+        let mut fender = request("very-good", 100.0);
+        fender.brand = "Fender".to_string();
+        assert!(estimate(&fender, &fixtures(), &ExchangeRates::default()).is_none());
+    }
+
+    #[test]
+    fn condition_grades_follow_reverb_slugs() {
+        let grade = |raw: &str| Condition::parse(raw).map(Condition::grade);
+        assert_eq!(grade("mint"), Some(6.0));
+        assert_eq!(grade("very-good"), Some(4.0));
+        assert_eq!(grade("Excellent +"), Some(5.5));
+        assert_eq!(grade("unknown"), None);
+        assert!(fixtures().iter().all(|g| g.get_condition() == Some(Condition::VeryGood)));
+    }
+}
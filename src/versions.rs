@@ -0,0 +1,203 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::{Map, Value};
+use surrealdb::Surreal;
+
+use crate::audit::AuditContext;
+use crate::models::{AuditChange, Guitar, GuitarVersionSummary};
+use crate::specs;
+
+// The version_<table> events (migrations/20261019_add_guitar_versions.surql) snapshot a
+// guitar and its spec rows into guitar_versions after every write that changes them.
+
+/// Spec tables stored in a version snapshot, next to the guitar row
+pub const SPEC_TABLES: [&str; 12] = [
+    "guitar_dimensions",
+    "guitar_appointments",
+    "guitar_woods",
+    "guitar_finish",
+    "guitar_hardware",
+    "guitar_electronics",
+    "guitar_setup",
+    "guitar_case",
+    "guitar_pickups",
+    "guitar_controls",
+    "guitar_badges",
+    "guitar_provenance",
+];
+
+/// One stored version of a guitar
+#[derive(Debug, Clone)]
+pub struct GuitarVersion {
+    pub summary: GuitarVersionSummary,
+    /// The guitar row, or None when the guitar was deleted in this version
+    pub guitar: Option<Guitar>,
+    /// The snapshot as plain JSON: `guitar` plus one array of rows per spec table
+    pub snapshot: Map<String, Value>,
+}
+
+impl GuitarVersion {
+    /// Rows of each spec table without their `id` and `guitar_id`, as the detail endpoints show them
+    pub fn spec_rows(&self) -> HashMap<String, Vec<Value>> {
+        SPEC_TABLES
+            .iter()
+            .map(|table| {
+                let rows = self
+                    .snapshot
+                    .get(*table)
+                    .and_then(Value::as_array)
+                    .map(|rows| {
+                        rows.iter()
+                            .cloned()
+                            .map(|mut row| {
+                                if let Some(map) = row.as_object_mut() {
+                                    map.remove("id");
+                                    map.remove("guitar_id");
+                                }
+                                row
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (table.to_string(), rows)
+            })
+            .collect()
+    }
+
+    /// The guitar's fields and spec sections in one document, for comparing versions
+    pub fn document(&self) -> Map<String, Value> {
+        let mut document = self
+            .snapshot
+            .get("guitar")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        let mut rows = self.spec_rows();
+        let badges = rows.remove("guitar_badges").unwrap_or_default();
+        let provenance = rows.remove("guitar_provenance").unwrap_or_default();
+        document.extend(specs::spec_sections(rows));
+        if !badges.is_empty() {
+            document.insert("badges".to_string(), Value::Array(badges));
+        }
+        if !provenance.is_empty() {
+            document.insert("provenance".to_string(), Value::Array(provenance));
+        }
+        document
+    }
+}
+
+/// Versions of a guitar, newest first
+pub async fn list(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+) -> surrealdb::Result<Vec<GuitarVersionSummary>> {
+    let mut response = db
+        .query(
+            "SELECT version, deleted, actor, source, <string> created_at AS created_at FROM guitar_versions
+             WHERE guitar_id = type::thing('guitars', $key) ORDER BY version DESC",
+        )
+        .bind(("key", guitar_key.to_string()))
+        .await?;
+    response.take(0)
+}
+
+/// Version `version` of a guitar
+pub async fn load(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+    version: i64,
+) -> surrealdb::Result<Option<GuitarVersion>> {
+    let mut response = db
+        .query(
+            "LET $rows = SELECT * FROM guitar_versions WHERE guitar_id = type::thing('guitars', $key) AND version = $version;
+             SELECT version, deleted, actor, source, <string> created_at AS created_at FROM $rows;
+             SELECT VALUE snapshot.guitar FROM $rows;
+             SELECT VALUE snapshot FROM $rows;",
+        )
+        .bind(("key", guitar_key.to_string()))
+        .bind(("version", version))
+        .await?;
+    let summaries: Vec<GuitarVersionSummary> = response.take(1)?;
+    let guitars: Vec<Option<Guitar>> = response.take(2)?;
+    // Read as plain JSON so record ids come out as "table:key" and datetimes as RFC 3339
+    let snapshots: surrealdb::Value = response.take(3)?;
+    let snapshot = match snapshots.into_inner().into_json() {
+        Value::Array(rows) => rows.into_iter().next().and_then(|row| row.as_object().cloned()),
+        _ => None,
+    };
+    Ok(summaries.into_iter().next().map(|summary| GuitarVersion {
+        summary,
+        guitar: guitars.into_iter().next().flatten(),
+        snapshot: snapshot.unwrap_or_default(),
+    }))
+}
+
+/// Field-by-field changes from one version's document to another's. Spec sections stored as
+/// one row are compared per field (`dimensions.scale_length`), lists as a whole.
+pub fn diff(from: &Map<String, Value>, to: &Map<String, Value>) -> Vec<AuditChange> {
+    let mut changes = Vec::new();
+    let keys: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    for key in keys {
+        let before = from.get(key).unwrap_or(&Value::Null);
+        let after = to.get(key).unwrap_or(&Value::Null);
+        if before == after {
+            continue;
+        }
+        match (before, after) {
+            (Value::Object(before), Value::Object(after)) => {
+                let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+                for field in fields {
+                    let (old, new) = (before.get(field), after.get(field));
+                    if old != new {
+                        changes.push(AuditChange {
+                            field: format!("{key}.{field}"),
+                            before: old.cloned().unwrap_or(Value::Null),
+                            after: new.cloned().unwrap_or(Value::Null),
+                        });
+                    }
+                }
+            }
+            _ => changes.push(AuditChange {
+                field: key.clone(),
+                before: before.clone(),
+                after: after.clone(),
+            }),
+        }
+    }
+    changes
+}
+
+/// Restore a guitar and its spec rows to version `version` in one transaction, recording the
+/// result as a new version. Spec rows added since are deleted; a deleted version deletes the guitar.
+pub async fn revert(
+    db: &Surreal<surrealdb::engine::remote::http::Client>,
+    guitar_key: &str,
+    version: i64,
+    audit: &AuditContext,
+) -> surrealdb::Result<()> {
+    db.query(
+        "BEGIN TRANSACTION;
+         LET $defer_versioning = true;
+         LET $guitar = type::thing('guitars', $key);
+         LET $target = (SELECT VALUE snapshot FROM guitar_versions WHERE guitar_id = $guitar AND version = $version)[0];
+         IF $target = NONE { THROW 'version not found'; };
+         IF $target.guitar = NONE { DELETE $guitar; } ELSE { UPSERT $guitar CONTENT $target.guitar; };
+         FOR $table IN $tables {
+             LET $rows = $target[$table] ?? [];
+             DELETE type::table($table) WHERE guitar_id = $guitar AND id NOTINSIDE $rows.id;
+             FOR $row IN $rows {
+                 LET $id = $row.id;
+                 UPSERT $id CONTENT $row;
+             };
+         };
+         fn::record_guitar_version($guitar, $audit);
+         COMMIT TRANSACTION;",
+    )
+    .bind(("key", guitar_key.to_string()))
+    .bind(("version", version))
+    .bind(("tables", SPEC_TABLES))
+    .bind(("audit", audit.clone()))
+    .await?
+    .check()?;
+    Ok(())
+}